@@ -1,23 +1,51 @@
-use super::statement::StatementNode;
-use crate::token::{token_type::TokenType, Token};
+use super::{pattern::Pattern, statement::StatementNode};
+use crate::token::{symbol::Symbol, token_type::TokenType, Token};
 
 #[derive(Debug, Clone)]
 pub enum ExpressionNode {
     // None,
-    Ident(Token),
+    Ident(Ident),
     Int(Token),
+    Float(Token),
     Bool(Token),
+    Str(Token),
     UnaryOperator(Token, Box<ExpressionNode>),
     BinaryOperator(Token, Box<ExpressionNode>, Box<ExpressionNode>),
-    Block(Vec<StatementNode>),
-    If(Token, Box<ExpressionNode>, Block, Option<Block>),
-    Function(Token, Vec<Ident>, Block),
+    Block(Block),
+    If(Token, Box<ExpressionNode>, Block, Option<Else>),
+    Function(Token, Vec<Param>, Block),
+    // `macro(params) { body }`, gets the arguments of its calls as quoted code
+    // and gives back the code the call is replaced with (see evaluation::expand_macros)
+    Macro(Token, Vec<Param>, Block),
     FunctionCall(Token, Box<ExpressionNode>, Vec<ExpressionNode>),
+    // the token is the `[` in both
+    Array(Token, Vec<ExpressionNode>),
+    Index(Token, Box<ExpressionNode>, Box<ExpressionNode>),
+    // the `{`, and the key value pairs in the order they were written
+    Hash(Token, Vec<(ExpressionNode, ExpressionNode)>),
+    // the `(`, `(a, b)`, `(a,)` with a single element or `()` with none
+    Tuple(Token, Vec<ExpressionNode>),
+    // the token is the `=` (or `+=` and the like)
+    Assign(Token, Ident, Box<ExpressionNode>),
+    // `Point { x: 1, y: 2 }`, the `{` and the name of the struct (see StatementNode::Struct)
+    // with the fields in the order they were written, `Point { x }` is short for `Point { x: x }`
+    Struct(Token, Ident, Vec<(Ident, ExpressionNode)>),
+    // the token is the `.`
+    Field(Token, Box<ExpressionNode>, Ident),
+    // `point.x = value`, the token is the `=` (or `+=` and the like)
+    // the target is a Field of a variable, or of such a Field in turn (`line.start.x = 0`)
+    // a struct is a value like any other, so this sets the variable to a copy with the new field
+    AssignField(Token, Box<ExpressionNode>, Box<ExpressionNode>),
+    // loops are preceded by an optional label, that break and continue can refer to
+    While(Token, Option<Token>, Box<ExpressionNode>, Block),
+    For(Token, Option<Token>, Ident, Box<ExpressionNode>, Block),
+    // the value that is matched, and the arms in the order they are tried
+    Match(Token, Box<ExpressionNode>, Vec<MatchArm>),
 }
 
 impl From<Ident> for ExpressionNode {
     fn from(value: Ident) -> Self {
-        Self::Ident(value.0)
+        Self::Ident(value)
     }
 }
 impl From<Int> for ExpressionNode {
@@ -25,11 +53,21 @@ impl From<Int> for ExpressionNode {
         Self::Int(value.0)
     }
 }
+impl From<Float> for ExpressionNode {
+    fn from(value: Float) -> Self {
+        Self::Float(value.0)
+    }
+}
 impl From<Bool> for ExpressionNode {
     fn from(value: Bool) -> Self {
         Self::Bool(value.0)
     }
 }
+impl From<Str> for ExpressionNode {
+    fn from(value: Str) -> Self {
+        Self::Str(value.0)
+    }
+}
 impl From<UnaryOperator> for ExpressionNode {
     fn from(value: UnaryOperator) -> Self {
         Self::UnaryOperator(value.token, Box::from(value.operand))
@@ -42,7 +80,7 @@ impl From<BinaryOperator> for ExpressionNode {
 }
 impl From<Block> for ExpressionNode {
     fn from(value: Block) -> Self {
-        Self::Block(value.statements)
+        Self::Block(value)
     }
 }
 impl From<If> for ExpressionNode {
@@ -60,30 +98,125 @@ impl From<Function> for ExpressionNode {
         Self::Function(value.token, value.args, value.body)
     }
 }
+impl From<Macro> for ExpressionNode {
+    fn from(value: Macro) -> Self {
+        Self::Macro(value.token, value.params, value.body)
+    }
+}
 impl From<FunctionCall> for ExpressionNode {
     fn from(value: FunctionCall) -> Self {
         Self::FunctionCall(value.token, Box::from(value.function), value.args)
     }
 }
+impl From<Array> for ExpressionNode {
+    fn from(value: Array) -> Self {
+        Self::Array(value.token, value.elements)
+    }
+}
+impl From<Tuple> for ExpressionNode {
+    fn from(value: Tuple) -> Self {
+        Self::Tuple(value.token, value.elements)
+    }
+}
+impl From<Index> for ExpressionNode {
+    fn from(value: Index) -> Self {
+        Self::Index(
+            value.token,
+            Box::from(value.collection),
+            Box::from(value.index),
+        )
+    }
+}
+impl From<Hash> for ExpressionNode {
+    fn from(value: Hash) -> Self {
+        Self::Hash(value.token, value.entries)
+    }
+}
+impl From<Assign> for ExpressionNode {
+    fn from(value: Assign) -> Self {
+        Self::Assign(value.token, value.target, Box::from(value.value))
+    }
+}
+impl From<Struct> for ExpressionNode {
+    fn from(value: Struct) -> Self {
+        Self::Struct(value.token, value.name, value.fields)
+    }
+}
+impl From<Field> for ExpressionNode {
+    fn from(value: Field) -> Self {
+        Self::Field(value.token, Box::from(value.value), value.field)
+    }
+}
+impl From<AssignField> for ExpressionNode {
+    fn from(value: AssignField) -> Self {
+        Self::AssignField(value.token, Box::from(value.target), Box::from(value.value))
+    }
+}
+impl From<While> for ExpressionNode {
+    fn from(value: While) -> Self {
+        Self::While(
+            value.token,
+            value.label,
+            Box::from(value.condition),
+            value.body,
+        )
+    }
+}
+impl From<For> for ExpressionNode {
+    fn from(value: For) -> Self {
+        Self::For(
+            value.token,
+            value.label,
+            value.var,
+            Box::from(value.iterable),
+            value.body,
+        )
+    }
+}
+impl From<Match> for ExpressionNode {
+    fn from(value: Match) -> Self {
+        Self::Match(value.token, Box::from(value.value), value.arms)
+    }
+}
+impl ExpressionNode {
+    // the variable `a.b.c` starts from, and the fields that lead from it to the last one
+    // None for the fields of anything but a variable
+    pub fn field_path(&self) -> Option<(Ident, Vec<&Ident>)> {
+        match self {
+            ExpressionNode::Ident(ident) => Some((ident.clone(), vec![])),
+            ExpressionNode::Field(_, value, field) => {
+                let (variable, mut fields) = value.field_path()?;
+                fields.push(field);
+                Some((variable, fields))
+            }
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
-pub struct Ident(Token);
+pub struct Ident(pub(super) Token);
 
 impl Ident {
     pub fn new(inner: Token) -> Self {
         Self(inner)
     }
 
-    pub fn get_string(&self) -> String {
-        match &self.0.r#type {
-            TokenType::Ident(ident) => ident.clone(),
+    pub fn token(&self) -> &Token {
+        &self.0
+    }
+
+    pub fn get_symbol(&self) -> Symbol {
+        match self.0.r#type {
+            TokenType::Ident(ident) => ident,
             _ => panic!("Ident(AST) doesn't contain Ident(token)"),
         }
     }
 }
 
-impl From<Ident> for String {
+impl From<Ident> for Symbol {
     fn from(value: Ident) -> Self {
-        value.get_string()
+        value.get_symbol()
     }
 }
 
@@ -96,6 +229,14 @@ impl Int {
     }
 }
 #[derive(Debug)]
+pub(crate) struct Float(Token);
+
+impl Float {
+    pub fn new(inner: Token) -> Self {
+        Self(inner)
+    }
+}
+#[derive(Debug)]
 pub(crate) struct Bool(Token);
 
 impl Bool {
@@ -104,6 +245,14 @@ impl Bool {
     }
 }
 #[derive(Debug)]
+pub(crate) struct Str(Token);
+
+impl Str {
+    pub fn new(inner: Token) -> Self {
+        Self(inner)
+    }
+}
+#[derive(Debug)]
 pub(crate) struct UnaryOperator {
     token: Token,
     operand: ExpressionNode,
@@ -146,12 +295,39 @@ impl Block {
     }
 }
 
+// what comes after `else`
+// `else if` is kept apart from `else { if ... }` so that it can be printed back the way it was written
+#[derive(Debug, Clone)]
+pub enum Else {
+    Block(Block),
+    // always an ExpressionNode::If
+    If(Box<ExpressionNode>),
+}
+
+// `pattern if guard => body`
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<ExpressionNode>,
+    pub body: ExpressionNode,
+}
+
+// `name`, `name = default` or `...name`
+// a default is evaluated when the function is called, with the params before it in scope
+// the rest param (with the `...`) comes last, it gets the arguments that are left over in an array
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub ident: Ident,
+    pub default: Option<ExpressionNode>,
+    pub rest: Option<Token>,
+}
+
 #[derive(Debug)]
 pub(crate) struct If {
     token: Token,
     condition: ExpressionNode,
     action: Block,
-    alternate: Option<Block>,
+    alternate: Option<Else>,
 }
 
 impl If {
@@ -159,7 +335,7 @@ impl If {
         token: Token,
         condition: ExpressionNode,
         action: Block,
-        alternate: Option<Block>,
+        alternate: Option<Else>,
     ) -> Self {
         Self {
             token,
@@ -173,16 +349,33 @@ impl If {
 #[derive(Debug)]
 pub(crate) struct Function {
     token: Token,
-    args: Vec<Ident>,
+    args: Vec<Param>,
     body: Block,
 }
 
 impl Function {
-    pub fn new(token: Token, args: Vec<Ident>, body: Block) -> Self {
+    pub fn new(token: Token, args: Vec<Param>, body: Block) -> Self {
         Self { token, args, body }
     }
 }
 
+#[derive(Debug)]
+pub(crate) struct Macro {
+    token: Token,
+    params: Vec<Param>,
+    body: Block,
+}
+
+impl Macro {
+    pub fn new(token: Token, params: Vec<Param>, body: Block) -> Self {
+        Self {
+            token,
+            params,
+            body,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct FunctionCall {
     token: Token,
@@ -199,3 +392,183 @@ impl FunctionCall {
         }
     }
 }
+
+#[derive(Debug)]
+pub(crate) struct Array {
+    token: Token,
+    elements: Vec<ExpressionNode>,
+}
+
+impl Array {
+    pub fn new(token: Token, elements: Vec<ExpressionNode>) -> Self {
+        Self { token, elements }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Tuple {
+    token: Token,
+    elements: Vec<ExpressionNode>,
+}
+
+impl Tuple {
+    pub fn new(token: Token, elements: Vec<ExpressionNode>) -> Self {
+        Self { token, elements }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Index {
+    token: Token,
+    collection: ExpressionNode,
+    index: ExpressionNode,
+}
+
+impl Index {
+    pub fn new(token: Token, collection: ExpressionNode, index: ExpressionNode) -> Self {
+        Self {
+            token,
+            collection,
+            index,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Hash {
+    token: Token,
+    entries: Vec<(ExpressionNode, ExpressionNode)>,
+}
+
+impl Hash {
+    pub fn new(token: Token, entries: Vec<(ExpressionNode, ExpressionNode)>) -> Self {
+        Self { token, entries }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Assign {
+    token: Token,
+    target: Ident,
+    value: ExpressionNode,
+}
+
+impl Assign {
+    pub fn new(token: Token, target: Ident, value: ExpressionNode) -> Self {
+        Self {
+            token,
+            target,
+            value,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Struct {
+    token: Token,
+    name: Ident,
+    fields: Vec<(Ident, ExpressionNode)>,
+}
+
+impl Struct {
+    pub fn new(token: Token, name: Ident, fields: Vec<(Ident, ExpressionNode)>) -> Self {
+        Self {
+            token,
+            name,
+            fields,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Field {
+    token: Token,
+    value: ExpressionNode,
+    field: Ident,
+}
+
+impl Field {
+    pub fn new(token: Token, value: ExpressionNode, field: Ident) -> Self {
+        Self {
+            token,
+            value,
+            field,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct AssignField {
+    token: Token,
+    target: ExpressionNode,
+    value: ExpressionNode,
+}
+
+impl AssignField {
+    pub fn new(token: Token, target: ExpressionNode, value: ExpressionNode) -> Self {
+        Self {
+            token,
+            target,
+            value,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct While {
+    token: Token,
+    label: Option<Token>,
+    condition: ExpressionNode,
+    body: Block,
+}
+
+impl While {
+    pub fn new(token: Token, label: Option<Token>, condition: ExpressionNode, body: Block) -> Self {
+        Self {
+            token,
+            label,
+            condition,
+            body,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct For {
+    token: Token,
+    label: Option<Token>,
+    var: Ident,
+    iterable: ExpressionNode,
+    body: Block,
+}
+
+impl For {
+    pub fn new(
+        token: Token,
+        label: Option<Token>,
+        var: Ident,
+        iterable: ExpressionNode,
+        body: Block,
+    ) -> Self {
+        Self {
+            token,
+            label,
+            var,
+            iterable,
+            body,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Match {
+    token: Token,
+    value: ExpressionNode,
+    arms: Vec<MatchArm>,
+}
+
+impl Match {
+    pub fn new(token: Token, value: ExpressionNode, arms: Vec<MatchArm>) -> Self {
+        Self { token, value, arms }
+    }
+}
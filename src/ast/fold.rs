@@ -0,0 +1,323 @@
+use super::{
+    expression::{Block, Else, ExpressionNode, Ident, MatchArm, Param},
+    pattern::Pattern,
+    statement::StatementNode,
+    Program,
+};
+use crate::token::{Span, Token};
+
+// A pass that rebuilds the AST, taking it apart by value and putting it back together
+//
+// Same as the Visitor, every method folds the children of its node by default
+// so a transformation only overrides the nodes it rewrites
+// and calls the matching `fold_*` function for the ones it leaves alone:
+//
+// struct ZeroInts;
+//
+// impl Folder for ZeroInts {
+//     fn fold_expression(&mut self, expr: ExpressionNode) -> ExpressionNode {
+//         match expr {
+//             ExpressionNode::Int(token) => {
+//                 let r#type = TokenType::Int("0".into());
+//                 ExpressionNode::Int(Token { r#type, ..token })
+//             }
+//             expr => fold_expression(self, expr),
+//         }
+//     }
+// }
+//
+pub trait Folder: Sized {
+    fn fold_program(&mut self, program: Program) -> Program {
+        fold_program(self, program)
+    }
+
+    fn fold_statement(&mut self, stmt: StatementNode) -> StatementNode {
+        fold_statement(self, stmt)
+    }
+
+    fn fold_expression(&mut self, expr: ExpressionNode) -> ExpressionNode {
+        fold_expression(self, expr)
+    }
+
+    fn fold_block(&mut self, block: Block) -> Block {
+        fold_block(self, block)
+    }
+
+    fn fold_ident(&mut self, ident: Ident) -> Ident {
+        fold_ident(self, ident)
+    }
+
+    fn fold_pattern(&mut self, pattern: Pattern) -> Pattern {
+        fold_pattern(self, pattern)
+    }
+
+    fn fold_param(&mut self, param: Param) -> Param {
+        fold_param(self, param)
+    }
+
+    fn fold_token(&mut self, token: Token) -> Token {
+        token
+    }
+
+    fn fold_error(&mut self, span: Span) -> Span {
+        span
+    }
+}
+
+pub fn fold_program<F: Folder>(folder: &mut F, program: Program) -> Program {
+    Program {
+        statements: fold_statements(folder, program.statements),
+    }
+}
+
+pub fn fold_statement<F: Folder>(folder: &mut F, stmt: StatementNode) -> StatementNode {
+    match stmt {
+        StatementNode::Let(token, ident, expr) => StatementNode::Let(
+            folder.fold_token(token),
+            folder.fold_ident(ident),
+            folder.fold_expression(expr),
+        ),
+        StatementNode::Destructure(token, pattern, expr) => StatementNode::Destructure(
+            folder.fold_token(token),
+            Box::new(folder.fold_pattern(*pattern)),
+            folder.fold_expression(expr),
+        ),
+        StatementNode::Return(token, expr) => {
+            StatementNode::Return(folder.fold_token(token), folder.fold_expression(expr))
+        }
+        StatementNode::Expression(expr) => StatementNode::Expression(folder.fold_expression(expr)),
+        StatementNode::Function(token, name, params, body) => StatementNode::Function(
+            folder.fold_token(token),
+            folder.fold_ident(name),
+            params
+                .into_iter()
+                .map(|param| folder.fold_param(param))
+                .collect(),
+            folder.fold_block(body),
+        ),
+        StatementNode::Struct(token, name, fields) => StatementNode::Struct(
+            folder.fold_token(token),
+            folder.fold_ident(name),
+            fields
+                .into_iter()
+                .map(|field| folder.fold_ident(field))
+                .collect(),
+        ),
+        StatementNode::Enum(token, name, variants) => StatementNode::Enum(
+            folder.fold_token(token),
+            folder.fold_ident(name),
+            variants
+                .into_iter()
+                .map(|(variant, fields)| {
+                    let variant = folder.fold_ident(variant);
+                    let fields = fields
+                        .into_iter()
+                        .map(|field| folder.fold_ident(field))
+                        .collect();
+                    (variant, fields)
+                })
+                .collect(),
+        ),
+        StatementNode::Break(token, label) => StatementNode::Break(
+            folder.fold_token(token),
+            label.map(|label| folder.fold_token(label)),
+        ),
+        StatementNode::Continue(token, label) => StatementNode::Continue(
+            folder.fold_token(token),
+            label.map(|label| folder.fold_token(label)),
+        ),
+        StatementNode::Import(token, path) => {
+            StatementNode::Import(folder.fold_token(token), folder.fold_token(path))
+        }
+        StatementNode::Export(token, stmt) => StatementNode::Export(
+            folder.fold_token(token),
+            Box::new(folder.fold_statement(*stmt)),
+        ),
+        StatementNode::Error(span) => StatementNode::Error(folder.fold_error(span)),
+    }
+}
+
+pub fn fold_expression<F: Folder>(folder: &mut F, expr: ExpressionNode) -> ExpressionNode {
+    match expr {
+        ExpressionNode::Ident(ident) => ExpressionNode::Ident(folder.fold_ident(ident)),
+        ExpressionNode::Int(token) => ExpressionNode::Int(folder.fold_token(token)),
+        ExpressionNode::Float(token) => ExpressionNode::Float(folder.fold_token(token)),
+        ExpressionNode::Bool(token) => ExpressionNode::Bool(folder.fold_token(token)),
+        ExpressionNode::Str(token) => ExpressionNode::Str(folder.fold_token(token)),
+        ExpressionNode::UnaryOperator(token, operand) => ExpressionNode::UnaryOperator(
+            folder.fold_token(token),
+            Box::new(folder.fold_expression(*operand)),
+        ),
+        ExpressionNode::BinaryOperator(token, lhs, rhs) => {
+            let lhs = folder.fold_expression(*lhs);
+            let token = folder.fold_token(token);
+            let rhs = folder.fold_expression(*rhs);
+            ExpressionNode::BinaryOperator(token, Box::new(lhs), Box::new(rhs))
+        }
+        ExpressionNode::Block(block) => ExpressionNode::Block(folder.fold_block(block)),
+        ExpressionNode::If(token, condition, action, alternate) => ExpressionNode::If(
+            folder.fold_token(token),
+            Box::new(folder.fold_expression(*condition)),
+            folder.fold_block(action),
+            alternate.map(|alternate| match alternate {
+                Else::Block(alternate) => Else::Block(folder.fold_block(alternate)),
+                Else::If(alternate) => Else::If(Box::new(folder.fold_expression(*alternate))),
+            }),
+        ),
+        ExpressionNode::Function(token, args, body) => ExpressionNode::Function(
+            folder.fold_token(token),
+            args.into_iter().map(|arg| folder.fold_param(arg)).collect(),
+            folder.fold_block(body),
+        ),
+        ExpressionNode::Macro(token, params, body) => ExpressionNode::Macro(
+            folder.fold_token(token),
+            params
+                .into_iter()
+                .map(|param| folder.fold_param(param))
+                .collect(),
+            folder.fold_block(body),
+        ),
+        ExpressionNode::FunctionCall(token, function, args) => {
+            let function = folder.fold_expression(*function);
+            let token = folder.fold_token(token);
+            let args = args
+                .into_iter()
+                .map(|arg| folder.fold_expression(arg))
+                .collect();
+            ExpressionNode::FunctionCall(token, Box::new(function), args)
+        }
+        ExpressionNode::Array(token, elements) => ExpressionNode::Array(
+            folder.fold_token(token),
+            elements
+                .into_iter()
+                .map(|elem| folder.fold_expression(elem))
+                .collect(),
+        ),
+        ExpressionNode::Tuple(token, elements) => ExpressionNode::Tuple(
+            folder.fold_token(token),
+            elements
+                .into_iter()
+                .map(|elem| folder.fold_expression(elem))
+                .collect(),
+        ),
+        ExpressionNode::Index(token, collection, index) => {
+            let collection = folder.fold_expression(*collection);
+            let token = folder.fold_token(token);
+            let index = folder.fold_expression(*index);
+            ExpressionNode::Index(token, Box::new(collection), Box::new(index))
+        }
+        ExpressionNode::Hash(token, entries) => ExpressionNode::Hash(
+            folder.fold_token(token),
+            entries
+                .into_iter()
+                .map(|(key, value)| (folder.fold_expression(key), folder.fold_expression(value)))
+                .collect(),
+        ),
+        ExpressionNode::Assign(token, target, value) => {
+            let target = folder.fold_ident(target);
+            let token = folder.fold_token(token);
+            let value = folder.fold_expression(*value);
+            ExpressionNode::Assign(token, target, Box::new(value))
+        }
+        ExpressionNode::Struct(token, name, fields) => {
+            let name = folder.fold_ident(name);
+            let token = folder.fold_token(token);
+            let fields = fields
+                .into_iter()
+                .map(|(field, value)| (folder.fold_ident(field), folder.fold_expression(value)))
+                .collect();
+            ExpressionNode::Struct(token, name, fields)
+        }
+        ExpressionNode::Field(token, value, field) => {
+            let value = folder.fold_expression(*value);
+            let token = folder.fold_token(token);
+            let field = folder.fold_ident(field);
+            ExpressionNode::Field(token, Box::new(value), field)
+        }
+        ExpressionNode::AssignField(token, target, value) => {
+            let target = folder.fold_expression(*target);
+            let token = folder.fold_token(token);
+            let value = folder.fold_expression(*value);
+            ExpressionNode::AssignField(token, Box::new(target), Box::new(value))
+        }
+        ExpressionNode::While(token, label, condition, body) => {
+            let label = label.map(|label| folder.fold_token(label));
+            let token = folder.fold_token(token);
+            let condition = folder.fold_expression(*condition);
+            let body = folder.fold_block(body);
+            ExpressionNode::While(token, label, Box::new(condition), body)
+        }
+        ExpressionNode::For(token, label, var, iterable, body) => {
+            let label = label.map(|label| folder.fold_token(label));
+            let token = folder.fold_token(token);
+            let var = folder.fold_ident(var);
+            let iterable = folder.fold_expression(*iterable);
+            let body = folder.fold_block(body);
+            ExpressionNode::For(token, label, var, Box::new(iterable), body)
+        }
+        ExpressionNode::Match(token, value, arms) => ExpressionNode::Match(
+            folder.fold_token(token),
+            Box::new(folder.fold_expression(*value)),
+            arms.into_iter()
+                .map(|arm| MatchArm {
+                    pattern: folder.fold_pattern(arm.pattern),
+                    guard: arm.guard.map(|guard| folder.fold_expression(guard)),
+                    body: folder.fold_expression(arm.body),
+                })
+                .collect(),
+        ),
+    }
+}
+
+pub fn fold_block<F: Folder>(folder: &mut F, block: Block) -> Block {
+    Block::new(fold_statements(folder, block.statements))
+}
+
+pub fn fold_ident<F: Folder>(folder: &mut F, ident: Ident) -> Ident {
+    Ident::new(folder.fold_token(ident.0))
+}
+
+pub fn fold_param<F: Folder>(folder: &mut F, param: Param) -> Param {
+    Param {
+        rest: param.rest.map(|rest| folder.fold_token(rest)),
+        ident: folder.fold_ident(param.ident),
+        default: param.default.map(|default| folder.fold_expression(default)),
+    }
+}
+
+pub fn fold_pattern<F: Folder>(folder: &mut F, pattern: Pattern) -> Pattern {
+    match pattern {
+        Pattern::Wildcard(token) => Pattern::Wildcard(folder.fold_token(token)),
+        Pattern::Binding(ident) => Pattern::Binding(folder.fold_ident(ident)),
+        Pattern::Literal(literal) => Pattern::Literal(folder.fold_expression(literal)),
+        Pattern::Array(token, patterns) => Pattern::Array(
+            folder.fold_token(token),
+            patterns
+                .into_iter()
+                .map(|pattern| folder.fold_pattern(pattern))
+                .collect(),
+        ),
+        Pattern::Tuple(token, patterns) => Pattern::Tuple(
+            folder.fold_token(token),
+            patterns
+                .into_iter()
+                .map(|pattern| folder.fold_pattern(pattern))
+                .collect(),
+        ),
+        Pattern::Variant(name, variant, patterns) => Pattern::Variant(
+            folder.fold_ident(name),
+            folder.fold_ident(variant),
+            patterns
+                .into_iter()
+                .map(|pattern| folder.fold_pattern(pattern))
+                .collect(),
+        ),
+    }
+}
+
+fn fold_statements<F: Folder>(folder: &mut F, stmts: Vec<StatementNode>) -> Vec<StatementNode> {
+    stmts
+        .into_iter()
+        .map(|stmt| folder.fold_statement(stmt))
+        .collect()
+}
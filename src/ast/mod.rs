@@ -1,7 +1,10 @@
 use self::{expression::ExpressionNode, statement::StatementNode};
 
-pub(crate) mod expression;
-pub(crate) mod statement;
+pub mod expression;
+pub mod fold;
+pub mod pattern;
+pub mod statement;
+pub mod visit;
 
 pub(crate) enum Node {
     Program(Program),
@@ -9,13 +12,13 @@ pub(crate) enum Node {
     Expression(ExpressionNode),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Program {
-    pub(crate) statements: Vec<StatementNode>,
+    pub statements: Vec<StatementNode>,
 }
 
 impl Program {
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
         Self { statements: vec![] }
     }
     pub(crate) fn add_statement(&mut self, stmt: StatementNode) {
@@ -0,0 +1,54 @@
+use super::expression::{ExpressionNode, Ident};
+use crate::token::Token;
+
+// What the arms of a match compare the value against
+// and what a `let` takes a value apart with (with only the patterns that can't fail to match,
+// but for the length of tuples and arrays, so no literals and no variants)
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    // `_`, matches anything and binds nothing
+    Wildcard(Token),
+    // matches anything, and binds it to the name
+    Binding(Ident),
+    // an int (with an optional `-` in front), a string or a bool
+    // only values of the same type match, 1 doesn't match 1.0
+    Literal(ExpressionNode),
+    // the `[`, an array matches if it has exactly as many elements and they match pairwise
+    Array(Token, Vec<Pattern>),
+    // the `(`, same as an array pattern, for tuples
+    Tuple(Token, Vec<Pattern>),
+    // `Shape.Circle(r)`, the enum and the variant, with patterns for its fields
+    // matches the values of that variant whose fields match pairwise
+    // (`Shape.Empty` for a variant without fields)
+    Variant(Ident, Ident, Vec<Pattern>),
+}
+
+impl Pattern {
+    // the first token of the pattern, where diagnostics about it point to
+    pub fn token(&self) -> &Token {
+        match self {
+            Pattern::Wildcard(token) | Pattern::Array(token, _) | Pattern::Tuple(token, _) => token,
+            Pattern::Binding(ident) | Pattern::Variant(ident, ..) => ident.token(),
+            Pattern::Literal(ExpressionNode::UnaryOperator(token, _))
+            | Pattern::Literal(
+                ExpressionNode::Int(token)
+                | ExpressionNode::Str(token)
+                | ExpressionNode::Bool(token),
+            ) => token,
+            Pattern::Literal(_) => unreachable!("the parser only puts literals in here"),
+        }
+    }
+
+    // the names the pattern binds, from left to right
+    pub fn bindings(&self) -> Vec<&Ident> {
+        match self {
+            Pattern::Binding(ident) => vec![ident],
+            Pattern::Array(_, patterns)
+            | Pattern::Tuple(_, patterns)
+            | Pattern::Variant(_, _, patterns) => {
+                patterns.iter().flat_map(Pattern::bindings).collect()
+            }
+            Pattern::Wildcard(_) | Pattern::Literal(_) => vec![],
+        }
+    }
+}
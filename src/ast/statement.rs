@@ -1,11 +1,55 @@
-use super::expression::{ExpressionNode, Ident};
-use crate::token::Token;
+use super::expression::{Block, ExpressionNode, Ident, Param};
+use super::pattern::Pattern;
+use crate::token::{token_type::TokenType, Span, Token};
 
 #[derive(Debug, Clone)]
 pub enum StatementNode {
+    // `let name = value`, or `const name = value` (told apart by the token)
+    // whose name can't be assigned to or declared again in the same scope
     Let(Token, Ident, ExpressionNode),
+    // `let (a, [b, _]) = value`, binds the names in the pattern to the parts of the value
+    // a value of another shape than the pattern is an error, `const` works the same way
+    Destructure(Token, Box<Pattern>, ExpressionNode),
     Return(Token, ExpressionNode),
     Expression(ExpressionNode),
+    // `fn name(params) { body }`, which is there from the start of the block it is declared in
+    Function(Token, Ident, Vec<Param>, Block),
+    // `struct Name { fields }`, there from the start of the block it is declared in too
+    Struct(Token, Ident, Vec<Ident>),
+    // `enum Name { Variant(fields), Other }`, the variants with the names of their fields
+    // a variant without fields is a value of its own, `Name.Other`, the others are constructors
+    // there from the start of the block too
+    Enum(Token, Ident, Vec<(Ident, Vec<Ident>)>),
+    // with the label of the loop they refer to, the innermost one if there's none
+    Break(Token, Option<Token>),
+    Continue(Token, Option<Token>),
+    // `import "path/to/mod"`, with the string literal of the path (see module::Loader)
+    Import(Token, Token),
+    // `export let`, `export const`, `export fn`, `export struct` or `export enum`
+    // which declare the same thing as without the `export`
+    Export(Token, Box<StatementNode>),
+    // a statement that failed to parse, covering the source that was skipped
+    Error(Span),
+}
+
+impl StatementNode {
+    // whether it's a `const`, or a `let` or a `fn`
+    pub fn is_const(&self) -> bool {
+        match self {
+            StatementNode::Let(token, ..) | StatementNode::Destructure(token, ..) => {
+                token.r#type == TokenType::Const
+            }
+            _ => false,
+        }
+    }
+
+    // the declaration an export is made of, any other statement as it is
+    pub fn without_export(&self) -> &StatementNode {
+        match self {
+            StatementNode::Export(_, stmt) => stmt,
+            stmt => stmt,
+        }
+    }
 }
 
 impl From<LetStatement> for StatementNode {
@@ -14,6 +58,12 @@ impl From<LetStatement> for StatementNode {
     }
 }
 
+impl From<DestructureStatement> for StatementNode {
+    fn from(value: DestructureStatement) -> Self {
+        Self::Destructure(value.token, Box::new(value.pattern), value.assign_val)
+    }
+}
+
 impl From<ReturnStatement> for StatementNode {
     fn from(value: ReturnStatement) -> Self {
         Self::Return(value.token, value.ret_val)
@@ -26,6 +76,45 @@ impl From<ExpressionStatement> for StatementNode {
     }
 }
 
+impl From<FunctionStatement> for StatementNode {
+    fn from(value: FunctionStatement) -> Self {
+        Self::Function(value.token, value.name, value.params, value.body)
+    }
+}
+
+impl From<StructStatement> for StatementNode {
+    fn from(value: StructStatement) -> Self {
+        Self::Struct(value.token, value.name, value.fields)
+    }
+}
+
+impl From<EnumStatement> for StatementNode {
+    fn from(value: EnumStatement) -> Self {
+        Self::Enum(value.token, value.name, value.variants)
+    }
+}
+
+impl From<ImportStatement> for StatementNode {
+    fn from(value: ImportStatement) -> Self {
+        Self::Import(value.token, value.path)
+    }
+}
+
+impl From<ExportStatement> for StatementNode {
+    fn from(value: ExportStatement) -> Self {
+        Self::Export(value.token, Box::new(value.stmt))
+    }
+}
+
+impl From<BreakStatement> for StatementNode {
+    fn from(value: BreakStatement) -> Self {
+        match value.token.r#type {
+            TokenType::Continue => Self::Continue(value.token, value.label),
+            _ => Self::Break(value.token, value.label),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct LetStatement {
     token: Token,
@@ -43,6 +132,23 @@ impl LetStatement {
     }
 }
 
+#[derive(Debug)]
+pub(crate) struct DestructureStatement {
+    token: Token,
+    pattern: Pattern,
+    assign_val: ExpressionNode,
+}
+
+impl DestructureStatement {
+    pub(crate) fn new(token: Token, pattern: Pattern, assign_val: ExpressionNode) -> Self {
+        Self {
+            token,
+            pattern,
+            assign_val,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct ReturnStatement {
     token: Token,
@@ -55,6 +161,59 @@ impl ReturnStatement {
     }
 }
 
+#[derive(Debug)]
+pub(crate) struct FunctionStatement {
+    token: Token,
+    name: Ident,
+    params: Vec<Param>,
+    body: Block,
+}
+
+impl FunctionStatement {
+    pub(crate) fn new(token: Token, name: Ident, params: Vec<Param>, body: Block) -> Self {
+        Self {
+            token,
+            name,
+            params,
+            body,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct StructStatement {
+    token: Token,
+    name: Ident,
+    fields: Vec<Ident>,
+}
+
+impl StructStatement {
+    pub(crate) fn new(token: Token, name: Ident, fields: Vec<Ident>) -> Self {
+        Self {
+            token,
+            name,
+            fields,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct EnumStatement {
+    token: Token,
+    name: Ident,
+    variants: Vec<(Ident, Vec<Ident>)>,
+}
+
+impl EnumStatement {
+    pub(crate) fn new(token: Token, name: Ident, variants: Vec<(Ident, Vec<Ident>)>) -> Self {
+        Self {
+            token,
+            name,
+            variants,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct ExpressionStatement(ExpressionNode);
 
@@ -63,3 +222,40 @@ impl ExpressionStatement {
         Self(expr)
     }
 }
+
+// `break` and `continue`, told apart by the token
+#[derive(Debug)]
+pub(crate) struct BreakStatement {
+    token: Token,
+    label: Option<Token>,
+}
+
+impl BreakStatement {
+    pub(crate) fn new(token: Token, label: Option<Token>) -> Self {
+        Self { token, label }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct ImportStatement {
+    token: Token,
+    path: Token,
+}
+
+impl ImportStatement {
+    pub(crate) fn new(token: Token, path: Token) -> Self {
+        Self { token, path }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct ExportStatement {
+    token: Token,
+    stmt: StatementNode,
+}
+
+impl ExportStatement {
+    pub(crate) fn new(token: Token, stmt: StatementNode) -> Self {
+        Self { token, stmt }
+    }
+}
@@ -0,0 +1,264 @@
+use super::{
+    expression::{Block, Else, ExpressionNode, Ident, Param},
+    pattern::Pattern,
+    statement::StatementNode,
+    Program,
+};
+use crate::token::{Span, Token};
+
+// A read-only pass over the AST
+//
+// Every method walks into the children of its node by default,
+// so a pass only overrides the nodes it cares about
+// and calls the matching `walk_*` function to keep going below them:
+//
+// struct CountCalls(usize);
+//
+// impl Visitor for CountCalls {
+//     fn visit_expression(&mut self, expr: &ExpressionNode) {
+//         if let ExpressionNode::FunctionCall(..) = expr {
+//             self.0 += 1;
+//         }
+//         walk_expression(self, expr);
+//     }
+// }
+//
+pub trait Visitor: Sized {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program);
+    }
+
+    fn visit_statement(&mut self, stmt: &StatementNode) {
+        walk_statement(self, stmt);
+    }
+
+    fn visit_expression(&mut self, expr: &ExpressionNode) {
+        walk_expression(self, expr);
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        walk_block(self, block);
+    }
+
+    fn visit_ident(&mut self, ident: &Ident) {
+        walk_ident(self, ident);
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        walk_pattern(self, pattern);
+    }
+
+    fn visit_param(&mut self, param: &Param) {
+        walk_param(self, param);
+    }
+
+    // every token in the tree ends up here, operators and keywords included
+    fn visit_token(&mut self, _token: &Token) {}
+
+    // the source skipped over by a statement that failed to parse
+    fn visit_error(&mut self, _span: Span) {}
+}
+
+pub fn walk_program<V: Visitor>(visitor: &mut V, program: &Program) {
+    program
+        .statements
+        .iter()
+        .for_each(|stmt| visitor.visit_statement(stmt));
+}
+
+pub fn walk_statement<V: Visitor>(visitor: &mut V, stmt: &StatementNode) {
+    match stmt {
+        StatementNode::Let(token, ident, expr) => {
+            visitor.visit_token(token);
+            visitor.visit_ident(ident);
+            visitor.visit_expression(expr);
+        }
+        StatementNode::Destructure(token, pattern, expr) => {
+            visitor.visit_token(token);
+            visitor.visit_pattern(pattern);
+            visitor.visit_expression(expr);
+        }
+        StatementNode::Return(token, expr) => {
+            visitor.visit_token(token);
+            visitor.visit_expression(expr);
+        }
+        StatementNode::Expression(expr) => visitor.visit_expression(expr),
+        StatementNode::Function(token, name, params, body) => {
+            visitor.visit_token(token);
+            visitor.visit_ident(name);
+            params.iter().for_each(|param| visitor.visit_param(param));
+            visitor.visit_block(body);
+        }
+        StatementNode::Struct(token, name, fields) => {
+            visitor.visit_token(token);
+            visitor.visit_ident(name);
+            fields.iter().for_each(|field| visitor.visit_ident(field));
+        }
+        StatementNode::Enum(token, name, variants) => {
+            visitor.visit_token(token);
+            visitor.visit_ident(name);
+            for (variant, fields) in variants {
+                visitor.visit_ident(variant);
+                fields.iter().for_each(|field| visitor.visit_ident(field));
+            }
+        }
+        StatementNode::Break(token, label) | StatementNode::Continue(token, label) => {
+            visitor.visit_token(token);
+            label.iter().for_each(|label| visitor.visit_token(label));
+        }
+        StatementNode::Import(token, path) => {
+            visitor.visit_token(token);
+            visitor.visit_token(path);
+        }
+        StatementNode::Export(token, stmt) => {
+            visitor.visit_token(token);
+            visitor.visit_statement(stmt);
+        }
+        StatementNode::Error(span) => visitor.visit_error(*span),
+    }
+}
+
+pub fn walk_expression<V: Visitor>(visitor: &mut V, expr: &ExpressionNode) {
+    match expr {
+        ExpressionNode::Ident(ident) => visitor.visit_ident(ident),
+        ExpressionNode::Int(token)
+        | ExpressionNode::Float(token)
+        | ExpressionNode::Bool(token)
+        | ExpressionNode::Str(token) => visitor.visit_token(token),
+        ExpressionNode::UnaryOperator(token, operand) => {
+            visitor.visit_token(token);
+            visitor.visit_expression(operand);
+        }
+        ExpressionNode::BinaryOperator(token, lhs, rhs) => {
+            visitor.visit_expression(lhs);
+            visitor.visit_token(token);
+            visitor.visit_expression(rhs);
+        }
+        ExpressionNode::Block(block) => visitor.visit_block(block),
+        ExpressionNode::If(token, condition, action, alternate) => {
+            visitor.visit_token(token);
+            visitor.visit_expression(condition);
+            visitor.visit_block(action);
+            match alternate {
+                Some(Else::Block(alternate)) => visitor.visit_block(alternate),
+                Some(Else::If(alternate)) => visitor.visit_expression(alternate),
+                None => {}
+            }
+        }
+        ExpressionNode::Function(token, args, body) | ExpressionNode::Macro(token, args, body) => {
+            visitor.visit_token(token);
+            args.iter().for_each(|arg| visitor.visit_param(arg));
+            visitor.visit_block(body);
+        }
+        ExpressionNode::FunctionCall(token, function, args) => {
+            visitor.visit_expression(function);
+            visitor.visit_token(token);
+            args.iter().for_each(|arg| visitor.visit_expression(arg));
+        }
+        ExpressionNode::Array(token, elements) | ExpressionNode::Tuple(token, elements) => {
+            visitor.visit_token(token);
+            elements
+                .iter()
+                .for_each(|elem| visitor.visit_expression(elem));
+        }
+        ExpressionNode::Index(token, collection, index) => {
+            visitor.visit_expression(collection);
+            visitor.visit_token(token);
+            visitor.visit_expression(index);
+        }
+        ExpressionNode::Hash(token, entries) => {
+            visitor.visit_token(token);
+            for (key, value) in entries {
+                visitor.visit_expression(key);
+                visitor.visit_expression(value);
+            }
+        }
+        ExpressionNode::Assign(token, target, value) => {
+            visitor.visit_ident(target);
+            visitor.visit_token(token);
+            visitor.visit_expression(value);
+        }
+        ExpressionNode::Struct(token, name, fields) => {
+            visitor.visit_ident(name);
+            visitor.visit_token(token);
+            for (field, value) in fields {
+                visitor.visit_ident(field);
+                visitor.visit_expression(value);
+            }
+        }
+        ExpressionNode::Field(token, value, field) => {
+            visitor.visit_expression(value);
+            visitor.visit_token(token);
+            visitor.visit_ident(field);
+        }
+        ExpressionNode::AssignField(token, target, value) => {
+            visitor.visit_expression(target);
+            visitor.visit_token(token);
+            visitor.visit_expression(value);
+        }
+        ExpressionNode::While(token, label, condition, body) => {
+            label.iter().for_each(|label| visitor.visit_token(label));
+            visitor.visit_token(token);
+            visitor.visit_expression(condition);
+            visitor.visit_block(body);
+        }
+        ExpressionNode::For(token, label, var, iterable, body) => {
+            label.iter().for_each(|label| visitor.visit_token(label));
+            visitor.visit_token(token);
+            visitor.visit_ident(var);
+            visitor.visit_expression(iterable);
+            visitor.visit_block(body);
+        }
+        ExpressionNode::Match(token, value, arms) => {
+            visitor.visit_token(token);
+            visitor.visit_expression(value);
+            for arm in arms {
+                visitor.visit_pattern(&arm.pattern);
+                if let Some(guard) = &arm.guard {
+                    visitor.visit_expression(guard);
+                }
+                visitor.visit_expression(&arm.body);
+            }
+        }
+    }
+}
+
+pub fn walk_block<V: Visitor>(visitor: &mut V, block: &Block) {
+    block
+        .statements
+        .iter()
+        .for_each(|stmt| visitor.visit_statement(stmt));
+}
+
+pub fn walk_ident<V: Visitor>(visitor: &mut V, ident: &Ident) {
+    visitor.visit_token(&ident.0);
+}
+
+pub fn walk_param<V: Visitor>(visitor: &mut V, param: &Param) {
+    param.rest.iter().for_each(|rest| visitor.visit_token(rest));
+    visitor.visit_ident(&param.ident);
+    if let Some(default) = &param.default {
+        visitor.visit_expression(default);
+    }
+}
+
+pub fn walk_pattern<V: Visitor>(visitor: &mut V, pattern: &Pattern) {
+    match pattern {
+        Pattern::Wildcard(token) => visitor.visit_token(token),
+        Pattern::Binding(ident) => visitor.visit_ident(ident),
+        Pattern::Literal(literal) => visitor.visit_expression(literal),
+        Pattern::Array(token, patterns) | Pattern::Tuple(token, patterns) => {
+            visitor.visit_token(token);
+            patterns
+                .iter()
+                .for_each(|pattern| visitor.visit_pattern(pattern));
+        }
+        Pattern::Variant(name, variant, patterns) => {
+            visitor.visit_ident(name);
+            visitor.visit_ident(variant);
+            patterns
+                .iter()
+                .for_each(|pattern| visitor.visit_pattern(pattern));
+        }
+    }
+}
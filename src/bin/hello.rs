@@ -1,9 +1,19 @@
 use interpreter::emitter::Emitter;
-use interpreter::evaluation::eval_program;
+use interpreter::evaluation::{eval_modules, eval_program};
 use interpreter::lexer::Lexer;
+use interpreter::module::Loader;
 use interpreter::parser::Parser;
+use interpreter::printer::format_source;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "fmt") {
+        std::process::exit(fmt(&args[1..]));
+    }
+    if args.first().is_some_and(|arg| arg == "run") {
+        std::process::exit(run(&args[1..]));
+    }
+
     // let l = Lexer::from_string(
     //     "
     //     let five = 5;
@@ -132,8 +142,7 @@ fn main() {
     // "#
     //     .into(),
     // );
-    let l = Lexer::from_string(
-        r#"
+    let src = r#"
         let x = true;
         if x {
             2;
@@ -142,11 +151,16 @@ fn main() {
         }
         let y = fn() { return 5 + 10; }
         let z = y();
-        "#
-        .into(),
-    );
+        "#;
+    let l = Lexer::from_string(src.into());
     let mut p = Parser::new(l);
-    let ast = p.parse_program().unwrap();
+    let (ast, errors) = p.parse_program();
+    if !errors.is_empty() {
+        for err in errors {
+            eprintln!("{}", err.diagnostic().render(src, "<input>"));
+        }
+        return;
+    }
     println!("{:#?}", ast);
     // let object = eval_program(ast);
     // println!("{:#?}", object);
@@ -155,3 +169,81 @@ fn main() {
     let b = e.emit();
     println!("{:#?}", b);
 }
+
+// hello fmt [--check] <files>...
+// rewrites the files in place with the canonical formatting
+// with --check, nothing is written, the files that aren't formatted are listed instead
+fn fmt(args: &[String]) -> i32 {
+    let check = args.iter().any(|arg| arg == "--check");
+    let mut status = 0;
+    for path in args.iter().filter(|arg| *arg != "--check") {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("error: couldn't read {path}: {err}");
+                status = 1;
+                continue;
+            }
+        };
+        match format_source(&source) {
+            Ok(formatted) if formatted == source => {}
+            Ok(_) if check => {
+                println!("{path} is not formatted");
+                status = 1;
+            }
+            Ok(formatted) => {
+                if let Err(err) = std::fs::write(path, formatted) {
+                    eprintln!("error: couldn't write {path}: {err}");
+                    status = 1;
+                }
+            }
+            Err(errors) => {
+                for err in errors {
+                    eprintln!("{}", err.diagnostic().render(&source, path));
+                }
+                status = 1;
+            }
+        }
+    }
+    status
+}
+
+// hello run [-I <dir>]... <file>
+// evaluates the file, with the modules it imports
+// the imports that aren't next to the file importing them are looked up in the -I dirs, in order
+fn run(args: &[String]) -> i32 {
+    let mut search_path = vec![];
+    let mut entry = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-I" => match args.next() {
+                Some(dir) => search_path.push(dir.into()),
+                None => {
+                    eprintln!("error: -I needs a directory");
+                    return 1;
+                }
+            },
+            _ => entry = Some(arg),
+        }
+    }
+    let Some(entry) = entry else {
+        eprintln!("error: no file to run");
+        return 1;
+    };
+
+    let result = Loader::new()
+        .with_search_path(search_path)
+        .load(entry.as_ref())
+        .and_then(|modules| eval_modules(&modules));
+    match result {
+        Ok(value) => {
+            println!("{value}");
+            0
+        }
+        Err(err) => {
+            eprintln!("{}", err.render());
+            1
+        }
+    }
+}
@@ -20,6 +20,42 @@ const OP_RET_VAL: u8 = 17;
 const OP_CALL: u8 = 18;
 const OP_SET_GLOBAL: u8 = 19;
 const OP_GET_GLOBAL: u8 = 20;
+const OP_ARRAY: u8 = 21;
+const OP_INDEX: u8 = 22;
+const OP_GET_BUILTIN: u8 = 23;
+const OP_HASH: u8 = 24;
+const OP_GET_LOCAL: u8 = 25;
+const OP_SET_LOCAL: u8 = 26;
+const OP_GET_FREE: u8 = 27;
+const OP_SET_FREE: u8 = 28;
+const OP_CLOSURE: u8 = 29;
+const OP_NULL: u8 = 30;
+const OP_ITERATE: u8 = 31;
+const OP_MOD: u8 = 32;
+const OP_POW: u8 = 33;
+const OP_GTE: u8 = 34;
+const OP_LTE: u8 = 35;
+const OP_BIT_AND: u8 = 36;
+const OP_BIT_OR: u8 = 37;
+const OP_BIT_XOR: u8 = 38;
+const OP_SHL: u8 = 39;
+const OP_SHR: u8 = 40;
+const OP_JUMP_TABLE: u8 = 41;
+const OP_MATCH_LITERAL: u8 = 42;
+const OP_MATCH_ARRAY: u8 = 43;
+const OP_JUMP_IF_PASSED: u8 = 45;
+const OP_TUPLE: u8 = 46;
+const OP_MATCH_TUPLE: u8 = 47;
+const OP_EXPECT_ARRAY: u8 = 48;
+const OP_EXPECT_TUPLE: u8 = 49;
+const OP_STRUCT: u8 = 50;
+const OP_GET_FIELD: u8 = 51;
+const OP_SET_FIELD: u8 = 52;
+const OP_MATCH_VARIANT: u8 = 53;
+const OP_PAYLOAD: u8 = 54;
+const OP_CAPTURE_LOCAL: u8 = 55;
+const OP_CAPTURE_FREE: u8 = 56;
+const OP_NEW_LOCAL: u8 = 57;
 
 #[derive(Debug, Clone, Copy)]
 pub enum Instruction {
@@ -40,9 +76,80 @@ pub enum Instruction {
     JumpNotTruthy(usize),
     Return,
     ReturnValue,
-    Call,
+    // number of arguments, which are on the stack above the function
+    Call(u8),
     SetGlobal(u16),
     GetGlobal(u16),
+    // number of elements, taken off the stack
+    Array(u16),
+    Index,
+    GetBuiltin(u8),
+    // number of key value pairs, the stack has 2 objects for every one of them
+    Hash(u16),
+    GetLocal(u8),
+    SetLocal(u8),
+    // the variables a closure captured from the functions enclosing it
+    GetFree(u8),
+    SetFree(u8),
+    // index of the CompiledFunction constant, number of variables captured right before it
+    Closure(u16, u8),
+    Null,
+    // replaces the value on the stack with an array of what a for loop goes over
+    // (see Object::into_iterable)
+    Iterate,
+    Mod,
+    Pow,
+    GTEq,
+    LTEq,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    // index of a hash constant, that maps values to where to jump to when the value on the stack is one of them
+    // the value is taken off the stack, and if it isn't in there the next instruction follows
+    JumpTable(u16),
+    // take the value off the stack, and push whether it is the constant (an int, string or bool)
+    // values of another type never are, 1.0 isn't 1
+    MatchLiteral(u16),
+    // take the value off the stack, and push whether it is an array of exactly that length
+    MatchArray(u16),
+    // jump if the function that's running was called with an argument for the param at that index
+    // the params with a default start with it, to skip computing the default
+    JumpIfPassed(u8, usize),
+    // number of elements, taken off the stack
+    Tuple(u16),
+    // take the value off the stack, and push whether it is a tuple of exactly that length
+    MatchTuple(u16),
+    // stop with an error unless the value on top of the stack is an array of exactly that length
+    // (what a destructuring let checks before taking the value apart)
+    ExpectArray(u16),
+    ExpectTuple(u16),
+    // index of the string constant with the name the type is written with (for the errors),
+    // number of fields given, the stack has the struct type
+    // and then 2 objects for every field, its name (a string constant) and its value
+    // it stops with an error unless they are exactly the fields of the type
+    Struct(u16, u16),
+    // index of the string constant with the name of the field
+    // replaces the struct on the stack with the value of that field
+    GetField(u16),
+    // take a value and then a struct off the stack,
+    // and push a copy of the struct with the field (like GetField) set to the value
+    SetField(u16),
+    // index of the string constant with the name the type is written with (like for Struct),
+    // the one with the name of the variant, number of fields in the pattern
+    // take an enum type and then a value off the stack, and push whether the value is that variant
+    // it stops with an error if the type isn't an enum with that variant, with that many fields
+    MatchVariant(u16, u16, u8),
+    // replaces the variant on the stack with the value of its field at that index
+    Payload(u8),
+    // the variable itself rather than its value, for the next Closure to take up
+    // so that the closure and the function it's in share it, and see each other's assignments
+    CaptureLocal(u8),
+    CaptureFree(u8),
+    // a new variable for the local at that index, set to null
+    // the closures that captured the one before keep it
+    NewLocal(u8),
 }
 
 impl Instruction {
@@ -77,7 +184,7 @@ impl Instruction {
             }
             Self::Return => vec![OP_RET],
             Self::ReturnValue => vec![OP_RET_VAL],
-            Self::Call => vec![OP_CALL],
+            Self::Call(args) => vec![OP_CALL, *args],
             Self::SetGlobal(index) => {
                 let mut v = vec![OP_SET_GLOBAL];
                 v.extend(index.to_be_bytes());
@@ -88,6 +195,173 @@ impl Instruction {
                 v.extend(index.to_be_bytes());
                 v
             }
+            Self::Array(len) => {
+                let mut v = vec![OP_ARRAY];
+                v.extend(len.to_be_bytes());
+                v
+            }
+            Self::Index => vec![OP_INDEX],
+            Self::GetBuiltin(index) => vec![OP_GET_BUILTIN, *index],
+            Self::Hash(len) => {
+                let mut v = vec![OP_HASH];
+                v.extend(len.to_be_bytes());
+                v
+            }
+            Self::GetLocal(index) => vec![OP_GET_LOCAL, *index],
+            Self::SetLocal(index) => vec![OP_SET_LOCAL, *index],
+            Self::GetFree(index) => vec![OP_GET_FREE, *index],
+            Self::SetFree(index) => vec![OP_SET_FREE, *index],
+            Self::Closure(index, free) => {
+                let mut v = vec![OP_CLOSURE];
+                v.extend(index.to_be_bytes());
+                v.push(*free);
+                v
+            }
+            Self::Null => vec![OP_NULL],
+            Self::Iterate => vec![OP_ITERATE],
+            Self::Mod => vec![OP_MOD],
+            Self::Pow => vec![OP_POW],
+            Self::GTEq => vec![OP_GTE],
+            Self::LTEq => vec![OP_LTE],
+            Self::BitAnd => vec![OP_BIT_AND],
+            Self::BitOr => vec![OP_BIT_OR],
+            Self::BitXor => vec![OP_BIT_XOR],
+            Self::Shl => vec![OP_SHL],
+            Self::Shr => vec![OP_SHR],
+            Self::JumpTable(index) => {
+                let mut v = vec![OP_JUMP_TABLE];
+                v.extend(index.to_be_bytes());
+                v
+            }
+            Self::MatchLiteral(index) => {
+                let mut v = vec![OP_MATCH_LITERAL];
+                v.extend(index.to_be_bytes());
+                v
+            }
+            Self::MatchArray(len) => {
+                let mut v = vec![OP_MATCH_ARRAY];
+                v.extend(len.to_be_bytes());
+                v
+            }
+            Self::JumpIfPassed(param, loc) => {
+                let mut v = vec![OP_JUMP_IF_PASSED, *param];
+                v.extend(loc.to_be_bytes());
+                v
+            }
+            Self::Tuple(len) => {
+                let mut v = vec![OP_TUPLE];
+                v.extend(len.to_be_bytes());
+                v
+            }
+            Self::MatchTuple(len) => {
+                let mut v = vec![OP_MATCH_TUPLE];
+                v.extend(len.to_be_bytes());
+                v
+            }
+            Self::ExpectArray(len) => {
+                let mut v = vec![OP_EXPECT_ARRAY];
+                v.extend(len.to_be_bytes());
+                v
+            }
+            Self::ExpectTuple(len) => {
+                let mut v = vec![OP_EXPECT_TUPLE];
+                v.extend(len.to_be_bytes());
+                v
+            }
+            Self::Struct(name, len) => {
+                let mut v = vec![OP_STRUCT];
+                v.extend(name.to_be_bytes());
+                v.extend(len.to_be_bytes());
+                v
+            }
+            Self::GetField(index) => {
+                let mut v = vec![OP_GET_FIELD];
+                v.extend(index.to_be_bytes());
+                v
+            }
+            Self::SetField(index) => {
+                let mut v = vec![OP_SET_FIELD];
+                v.extend(index.to_be_bytes());
+                v
+            }
+            Self::MatchVariant(name, index, len) => {
+                let mut v = vec![OP_MATCH_VARIANT];
+                v.extend(name.to_be_bytes());
+                v.extend(index.to_be_bytes());
+                v.push(*len);
+                v
+            }
+            Self::Payload(index) => vec![OP_PAYLOAD, *index],
+            Self::CaptureLocal(index) => vec![OP_CAPTURE_LOCAL, *index],
+            Self::CaptureFree(index) => vec![OP_CAPTURE_FREE, *index],
+            Self::NewLocal(index) => vec![OP_NEW_LOCAL, *index],
+        }
+    }
+
+    // the instruction that starts `bytes` (as to_bytes wrote it), and how many bytes it takes
+    pub fn read(bytes: &[u8]) -> (Instruction, usize) {
+        let byte = |at: usize| bytes[at];
+        let u16 = |at: usize| u16::from_be_bytes([bytes[at], bytes[at + 1]]);
+        const USIZE: usize = std::mem::size_of::<usize>();
+        let usize = |at: usize| usize::from_be_bytes(bytes[at..at + USIZE].try_into().unwrap());
+        match bytes[0] {
+            OP_CONSTANT => (Self::Constant(u16(1)), 3),
+            OP_POP => (Self::Pop, 1),
+            OP_NOT => (Self::Not, 1),
+            OP_UPLUS => (Self::UnaryPlus, 1),
+            OP_UMIN => (Self::UnaryMinus, 1),
+            OP_ADD => (Self::Add, 1),
+            OP_SUB => (Self::Sub, 1),
+            OP_MUL => (Self::Mul, 1),
+            OP_DIV => (Self::Div, 1),
+            OP_EQ => (Self::Eq, 1),
+            OP_NEQ => (Self::NotEq, 1),
+            OP_GT => (Self::GT, 1),
+            OP_LT => (Self::LT, 1),
+            OP_J => (Self::Jump(usize(1)), 1 + USIZE),
+            OP_JNT => (Self::JumpNotTruthy(usize(1)), 1 + USIZE),
+            OP_RET => (Self::Return, 1),
+            OP_RET_VAL => (Self::ReturnValue, 1),
+            OP_CALL => (Self::Call(byte(1)), 2),
+            OP_SET_GLOBAL => (Self::SetGlobal(u16(1)), 3),
+            OP_GET_GLOBAL => (Self::GetGlobal(u16(1)), 3),
+            OP_ARRAY => (Self::Array(u16(1)), 3),
+            OP_INDEX => (Self::Index, 1),
+            OP_GET_BUILTIN => (Self::GetBuiltin(byte(1)), 2),
+            OP_HASH => (Self::Hash(u16(1)), 3),
+            OP_GET_LOCAL => (Self::GetLocal(byte(1)), 2),
+            OP_SET_LOCAL => (Self::SetLocal(byte(1)), 2),
+            OP_GET_FREE => (Self::GetFree(byte(1)), 2),
+            OP_SET_FREE => (Self::SetFree(byte(1)), 2),
+            OP_CLOSURE => (Self::Closure(u16(1), byte(3)), 4),
+            OP_NULL => (Self::Null, 1),
+            OP_ITERATE => (Self::Iterate, 1),
+            OP_MOD => (Self::Mod, 1),
+            OP_POW => (Self::Pow, 1),
+            OP_GTE => (Self::GTEq, 1),
+            OP_LTE => (Self::LTEq, 1),
+            OP_BIT_AND => (Self::BitAnd, 1),
+            OP_BIT_OR => (Self::BitOr, 1),
+            OP_BIT_XOR => (Self::BitXor, 1),
+            OP_SHL => (Self::Shl, 1),
+            OP_SHR => (Self::Shr, 1),
+            OP_JUMP_TABLE => (Self::JumpTable(u16(1)), 3),
+            OP_MATCH_LITERAL => (Self::MatchLiteral(u16(1)), 3),
+            OP_MATCH_ARRAY => (Self::MatchArray(u16(1)), 3),
+            OP_JUMP_IF_PASSED => (Self::JumpIfPassed(byte(1), usize(2)), 2 + USIZE),
+            OP_TUPLE => (Self::Tuple(u16(1)), 3),
+            OP_MATCH_TUPLE => (Self::MatchTuple(u16(1)), 3),
+            OP_EXPECT_ARRAY => (Self::ExpectArray(u16(1)), 3),
+            OP_EXPECT_TUPLE => (Self::ExpectTuple(u16(1)), 3),
+            OP_STRUCT => (Self::Struct(u16(1), u16(3)), 5),
+            OP_GET_FIELD => (Self::GetField(u16(1)), 3),
+            OP_SET_FIELD => (Self::SetField(u16(1)), 3),
+            OP_MATCH_VARIANT => (Self::MatchVariant(u16(1), u16(3), byte(5)), 6),
+            OP_PAYLOAD => (Self::Payload(byte(1)), 2),
+            OP_CAPTURE_LOCAL => (Self::CaptureLocal(byte(1)), 2),
+            OP_CAPTURE_FREE => (Self::CaptureFree(byte(1)), 2),
+            OP_NEW_LOCAL => (Self::NewLocal(byte(1)), 2),
+            op => unreachable!("no instruction has the opcode {op}"),
         }
     }
 }
@@ -0,0 +1,192 @@
+use std::fmt;
+
+use crate::token::{Pos, Span};
+
+// Errors (and warnings) from every stage of the interpreter end up as a Diagnostic
+// which can be rendered rustc-style against the source it refers to:
+//
+// error: Expected an expression, found `;`
+//  --> main.si:2:13
+//   |
+// 2 | let x = 5 + ;
+//   |             ^ an expression can't start here
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        })
+    }
+}
+
+// A span of the source with some text to print next to it
+// the text can be empty, the span is still underlined
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+impl Label {
+    pub fn new(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    // underlined with ^^^
+    pub primary: Label,
+    // underlined with ---, things like "function defined here"
+    pub secondary: Vec<Label>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>, span: Span) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            primary: Label::new(span, ""),
+            secondary: vec![],
+        }
+    }
+
+    pub fn error(message: impl Into<String>, span: Span) -> Self {
+        Self::new(Severity::Error, message, span)
+    }
+
+    pub fn warning(message: impl Into<String>, span: Span) -> Self {
+        Self::new(Severity::Warning, message, span)
+    }
+
+    pub fn with_primary_message(mut self, message: impl Into<String>) -> Self {
+        self.primary.message = message.into();
+        self
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.secondary.push(Label::new(span, message));
+        self
+    }
+
+    // `source` has to be the text the spans point into
+    pub fn render(&self, source: &str, filename: &str) -> String {
+        let lines = SourceLines::new(source);
+        let (line, col) = lines.line_col(self.primary.span.0);
+
+        // (line index, label, is primary)
+        let mut annotations: Vec<_> = std::iter::once((&self.primary, true))
+            .chain(self.secondary.iter().map(|label| (label, false)))
+            .map(|(label, primary)| (lines.line_col(label.span.0).0, label, primary))
+            .collect();
+        annotations.sort_by_key(|(line, _, primary)| (*line, !*primary));
+
+        let last_line = annotations
+            .iter()
+            .map(|(line, ..)| line + 1)
+            .max()
+            .unwrap_or(1);
+        let width = last_line.to_string().len();
+        let pad = " ".repeat(width);
+
+        let mut out = format!("{}: {}\n", self.severity, self.message);
+        out += &format!("{pad}--> {filename}:{}:{}\n", line + 1, col + 1);
+        out += &format!("{pad} |\n");
+
+        let mut prev_line = None;
+        for (line, label, primary) in annotations {
+            if prev_line != Some(line) {
+                if prev_line.is_some_and(|prev| line > prev + 1) {
+                    out += "...\n";
+                }
+                out += &format!("{:>width$} | {}\n", line + 1, lines.text(line));
+            }
+            prev_line = Some(line);
+
+            let (start, len) = lines.underline(line, label.span);
+            let marker = if primary { "^" } else { "-" };
+            let underline = format!("{}{}", " ".repeat(start), marker.repeat(len));
+            let underline = format!("{underline} {}", label.message);
+            out += &format!("{pad} | {}\n", underline.trim_end());
+        }
+        out
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    // without the source only the header can be shown
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+// the source broken up into lines, with tabs expanded so the underlines line up
+struct SourceLines<'a> {
+    source: &'a str,
+    // offset of the first byte of every line
+    starts: Vec<Pos>,
+}
+
+const TAB: &str = "    ";
+
+impl<'a> SourceLines<'a> {
+    fn new(source: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { source, starts }
+    }
+
+    // 0 based line and column (in chars) of the byte offset `pos`
+    fn line_col(&self, pos: Pos) -> (usize, usize) {
+        let pos = pos.min(self.source.len());
+        let line = self.starts.partition_point(|&start| start <= pos) - 1;
+        let col = self.source[self.starts[line]..pos].chars().count();
+        (line, col)
+    }
+
+    fn raw(&self, line: usize) -> &'a str {
+        let start = self.starts[line];
+        let end = self
+            .starts
+            .get(line + 1)
+            .map_or(self.source.len(), |next| next - 1);
+        self.source[start..end].trim_end_matches('\r')
+    }
+
+    fn text(&self, line: usize) -> String {
+        self.raw(line).replace('\t', TAB)
+    }
+
+    // where the underline for `span` starts on the printed line and how long it is
+    // spans running past the end of the line are cut there
+    // empty spans (like the end of input) still get a single marker
+    fn underline(&self, line: usize, span: Span) -> (usize, usize) {
+        let raw = self.raw(line);
+        let line_start = self.starts[line];
+        let clamp = |pos: Pos| pos.clamp(line_start, line_start + raw.len()) - line_start;
+        let (start, end) = (clamp(span.0), clamp(span.1.max(span.0)));
+        let width = |s: &str| {
+            s.chars()
+                .map(|c| if c == '\t' { TAB.len() } else { 1 })
+                .sum()
+        };
+        let col = width(&raw[..start]);
+        let len: usize = width(&raw[start..end]);
+        (col, len.max(1))
+    }
+}
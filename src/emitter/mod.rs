@@ -1,66 +1,174 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use crate::ast::{expression::ExpressionNode, statement::StatementNode, Node, Program};
+use crate::ast::{
+    expression::{Block, Else, ExpressionNode, Ident, MatchArm, Param},
+    pattern::Pattern,
+    statement::StatementNode,
+    Node, Program,
+};
 use crate::bytecode::{Bytecode, Instruction};
-use crate::object::Object;
-use crate::token::token_type::TokenType;
+use crate::diagnostics::Diagnostic;
+use crate::evaluation;
+use crate::module::{self, Module, ModuleError};
+use crate::object::{builtins, Arity, Hashable, Object};
+use crate::token::{
+    float_literal_value, int_literal_value, string_literal_value, symbol, token_type::TokenType,
+    Span, Token,
+};
 
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 enum Scope {
     #[default]
     Global,
+    // params and lets of the function being compiled
+    Local,
+    // a local of an enclosing function, captured by the closure
+    Free,
 }
 
 #[derive(Debug, Default, Clone)]
 struct Symbol {
-    name: String,
+    name: symbol::Symbol,
     scope: Scope,
     index: u16,
 }
 
+// one per function being compiled, the outermost one holds the globals
 #[derive(Debug, Default)]
 struct SymbolTable {
-    store: HashMap<String, Symbol>,
+    store: HashMap<symbol::Symbol, Symbol>,
     num_definitions: u16,
+    outer: Option<Box<SymbolTable>>,
+    // the symbols of the enclosing functions this one captured, in the order of their Free index
+    free_symbols: Vec<Symbol>,
+    // the stores of the scopes around the match arm being compiled, to go back to after it
+    enclosing: Vec<HashMap<symbol::Symbol, Symbol>>,
 }
 
 impl SymbolTable {
-    fn define(&mut self, name: String) -> u16 {
-        let index = self.num_definitions;
+    fn enclosed(outer: SymbolTable) -> Self {
+        Self {
+            outer: Some(Box::new(outer)),
+            ..Default::default()
+        }
+    }
+
+    fn define(&mut self, name: symbol::Symbol) -> Symbol {
+        let symbol = self.define_hidden();
+        let symbol = Symbol { name, ..symbol };
+        self.store.insert(name, symbol.clone());
+        symbol
+    }
+
+    // a slot for a value the compiler keeps around, which no name refers to
+    fn define_hidden(&mut self) -> Symbol {
+        let scope = match self.outer {
+            Some(_) => Scope::Local,
+            None => Scope::Global,
+        };
         let symbol = Symbol {
-            name: name.clone(),
-            scope: Scope::Global,
-            index,
+            name: symbol::Symbol::EMPTY,
+            scope,
+            index: self.num_definitions,
         };
-        self.store.insert(name, symbol);
         self.num_definitions += 1;
-        index
+        symbol
     }
 
-    fn resolve(&self, name: &String) -> Option<&Symbol> {
-        self.store.get(name)
+    // the names defined in a match arm are only there until the arm ends
+    // (their slots stay taken, the frame of the function has them all)
+    fn start_scope(&mut self) {
+        self.enclosing.push(self.store.clone());
+    }
+
+    // the variable `name` has in the innermost scope, the function or the match arm being compiled
+    fn declared(&self, name: symbol::Symbol) -> Option<Symbol> {
+        let symbol = self.store.get(&name)?;
+        let outer = self.enclosing.last().and_then(|outer| outer.get(&name));
+        let inherited =
+            outer.is_some_and(|outer| outer.scope == symbol.scope && outer.index == symbol.index);
+        (symbol.scope != Scope::Free && !inherited).then(|| symbol.clone())
+    }
+
+    fn end_scope(&mut self) {
+        let outer = self.enclosing.pop().unwrap();
+        let inner = std::mem::replace(&mut self.store, outer);
+        // what the arm captured stays captured, with the same Free index
+        for (name, symbol) in inner {
+            if symbol.scope == Scope::Free {
+                self.store.entry(name).or_insert(symbol);
+            }
+        }
+    }
+
+    fn define_free(&mut self, original: Symbol) -> Symbol {
+        let symbol = Symbol {
+            name: original.name,
+            scope: Scope::Free,
+            index: self.free_symbols.len() as u16,
+        };
+        self.free_symbols.push(original);
+        self.store.insert(symbol.name, symbol.clone());
+        symbol
+    }
+
+    // globals are reachable from everywhere
+    // the locals of an enclosing function have to be captured first
+    fn resolve(&mut self, name: symbol::Symbol) -> Option<Symbol> {
+        if let Some(symbol) = self.store.get(&name) {
+            return Some(symbol.clone());
+        }
+        let symbol = self.outer.as_mut()?.resolve(name)?;
+        match symbol.scope {
+            Scope::Global => Some(symbol),
+            Scope::Local | Scope::Free => Some(self.define_free(symbol)),
+        }
     }
 }
 
+// a loop being compiled, for the breaks and continues inside it to find their way out
+#[derive(Debug, Default)]
+struct Loop {
+    label: Option<symbol::Symbol>,
+    // the jumps of the breaks and continues, patched once the end of the loop is known
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
 #[derive(Debug, Default)]
 struct CompilationScope {
+    // the jumps in here refer to the index of the instruction they jump to
+    // they are turned into byte offsets when emitting
     instructions: Vec<Instruction>,
+    loops: Vec<Loop>,
 }
 
 impl CompilationScope {
-    fn emit(self) -> Vec<u8> {
+    // the targets in the jump tables (in `constants`) are turned into byte offsets too
+    fn emit(self, constants: &mut [Object]) -> Vec<u8> {
         let mut instructions: Vec<_> = self.instructions.iter().map(|i| i.to_bytes()).collect();
-        let lengths: Vec<_> = instructions
-            .iter()
-            .map(|v| v.len())
-            .scan(0, |acc, x| {
+        // the offset every instruction starts at, and the one the last one ends at
+        let offsets: Vec<_> = std::iter::once(0)
+            .chain(instructions.iter().map(|v| v.len()).scan(0, |acc, x| {
                 *acc += x;
                 Some(*acc)
-            })
+            }))
             .collect();
         for (i, inst) in self.instructions.iter().enumerate() {
             let index = match inst {
-                Instruction::Jump(index) | Instruction::JumpNotTruthy(index) => lengths[*index],
+                Instruction::Jump(index)
+                | Instruction::JumpNotTruthy(index)
+                | Instruction::JumpIfPassed(_, index) => offsets[*index],
+                Instruction::JumpTable(table) => {
+                    if let Object::Hash(table) = &mut constants[*table as usize] {
+                        for target in table.values_mut() {
+                            if let Object::Int(index) = target {
+                                *index = offsets[*index as usize] as isize;
+                            }
+                        }
+                    }
+                    continue;
+                }
                 _ => continue,
             };
             instructions[i] =
@@ -78,6 +186,8 @@ pub struct Emitter {
     // instructions: Vec<Instruction>,
     constants: Vec<Object>,
     symbol_table: SymbolTable,
+    errors: Vec<Diagnostic>,
+    warnings: Vec<Diagnostic>,
 }
 
 impl Emitter {
@@ -90,22 +200,143 @@ impl Emitter {
 
     fn new_scope(&mut self) {
         self.scopes.push(Default::default());
+        let outer = std::mem::take(&mut self.symbol_table);
+        self.symbol_table = SymbolTable::enclosed(outer);
     }
 
-    fn pop_scope(&mut self) -> CompilationScope{
-        self.scopes.pop().unwrap()
+    // the instructions of the scope, and the symbols it captured
+    fn pop_scope(&mut self) -> (CompilationScope, Vec<Symbol>) {
+        let outer = self.symbol_table.outer.take().unwrap();
+        let inner = std::mem::replace(&mut self.symbol_table, *outer);
+        (self.scopes.pop().unwrap(), inner.free_symbols)
     }
 
     pub fn compile_program(&mut self, prog: Program) {
+        if let Err(err) = module::check_standalone(&prog) {
+            return self.errors.push(err);
+        }
+        self.expand_and_compile(prog, &[]);
+    }
+
+    // compiles the modules the loader gave back in order, into one program
+    // every module gets globals of its own, that start out with what the modules it imports export
+    // (copied into them, like the evaluator does)
+    // and stops at the first module with errors
+    pub fn compile_modules(&mut self, modules: &[Module]) -> Result<(), ModuleError> {
+        // the globals each module exports, by name
+        let mut exports: Vec<Vec<Symbol>> = vec![];
+        for (i, module) in modules.iter().enumerate() {
+            // only the value of the last one, the entry, stays
+            if i > 0 {
+                self.push_instruction(Instruction::Pop);
+            }
+            // the global slots are shared by the modules, so the numbering goes on
+            self.symbol_table = SymbolTable {
+                num_definitions: self.symbol_table.num_definitions,
+                ..Default::default()
+            };
+            for &import in &module.imports {
+                for exported in &exports[import] {
+                    let symbol = self.symbol_table.define(exported.name);
+                    self.load_symbol(exported);
+                    self.store_symbol(&symbol);
+                }
+            }
+            let program = Program {
+                statements: module.program.statements.clone(),
+            };
+            self.expand_and_compile(program, &module.imported_consts(modules));
+            if !self.errors.is_empty() {
+                let diagnostics = std::mem::take(&mut self.errors)
+                    .into_iter()
+                    .flat_map(|err| module::locate(modules, err).diagnostics)
+                    .collect();
+                return Err(ModuleError { diagnostics });
+            }
+            let globals = &mut self.symbol_table;
+            exports.push(
+                module
+                    .exports
+                    .iter()
+                    .filter_map(|&name| globals.resolve(name))
+                    .collect(),
+            );
+        }
+        Ok(())
+    }
+
+    // `imported` are the constants the program starts out with
+    fn expand_and_compile(&mut self, prog: Program, imported: &[(symbol::Symbol, Span)]) {
+        let prog = match evaluation::expand_macros(prog) {
+            Ok(prog) => prog,
+            Err(err) => return self.errors.push(err),
+        };
+        let errors = evaluation::check_consts(&prog, imported);
+        if !errors.is_empty() {
+            return self.errors.extend(errors);
+        }
         let prog = Node::Program(prog);
         self.compile(&prog);
     }
 
+    // the programs that the evaluator would reject at runtime, found while compiling
+    pub fn errors(&self) -> &[Diagnostic] {
+        &self.errors
+    }
+
+    // things that compile fine but are most likely a mistake
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    // a variable for `name` in the function being compiled
+    // which is a new one every time a match arm defines it, like the env of the arm in the
+    // evaluator, so the closures made in the arm before keep theirs
+    fn define(&mut self, name: symbol::Symbol) -> Symbol {
+        let symbol = self.symbol_table.define(name);
+        if symbol.scope == Scope::Local && !self.symbol_table.enclosing.is_empty() {
+            self.push_instruction(Instruction::NewLocal(symbol.index as u8));
+        }
+        symbol
+    }
+
+    // the variable a declaration of `name` sets
+    // the one it has in the scope already if there's one, as the evaluator sets the same binding
+    // again, or a new one
+    fn declare(&mut self, name: symbol::Symbol) -> Symbol {
+        match self.symbol_table.declared(name) {
+            Some(symbol) => symbol,
+            None => self.define(name),
+        }
+    }
+
+    fn load_symbol(&mut self, symbol: &Symbol) {
+        let instruction = match symbol.scope {
+            Scope::Global => Instruction::GetGlobal(symbol.index),
+            Scope::Local => Instruction::GetLocal(symbol.index as u8),
+            Scope::Free => Instruction::GetFree(symbol.index as u8),
+        };
+        self.push_instruction(instruction);
+    }
+
+    fn store_symbol(&mut self, symbol: &Symbol) {
+        let instruction = match symbol.scope {
+            Scope::Global => Instruction::SetGlobal(symbol.index),
+            Scope::Local => Instruction::SetLocal(symbol.index as u8),
+            Scope::Free => Instruction::SetFree(symbol.index as u8),
+        };
+        self.push_instruction(instruction);
+    }
+
     fn current_instructions(&mut self) -> &mut Vec<Instruction> {
         let curr_scope_index = self.scopes.len() - 1;
         &mut self.scopes[curr_scope_index].instructions
     }
 
+    fn current_loops(&mut self) -> &mut Vec<Loop> {
+        &mut self.scopes.last_mut().unwrap().loops
+    }
+
     fn push_instruction(&mut self, instr: Instruction) {
         self.current_instructions().push(instr);
     }
@@ -115,18 +346,13 @@ impl Emitter {
     }
 
     fn get_last_instruction(&mut self) -> Option<&mut Instruction> {
-        let cur_insts = self.current_instructions();
-        let len = cur_insts.len(); 
-        cur_insts.get_mut(len - 1)
+        self.current_instructions().last_mut()
     }
 
     fn compile(&mut self, root: &Node) {
         match root {
-            Node::Program(prog) => {
-                for stmt in &prog.statements {
-                    self.compile_statement(stmt);
-                }
-            }
+            // the value of the program is left on the stack, the same as the one of a block
+            Node::Program(prog) => self.compile_block(&prog.statements),
             Node::Statement(stmt) => {
                 self.compile_statement(stmt);
             }
@@ -141,9 +367,19 @@ impl Emitter {
     fn compile_statement(&mut self, stmt: &StatementNode) {
         match stmt {
             StatementNode::Let(_, ident, expr) => {
+                let name = ident.get_symbol();
+                // a function can call itself by the name it's given
+                // it captures the variable before the let sets it
+                if let ExpressionNode::Function(..) = expr {
+                    self.declare(name);
+                }
                 self.compile_expression(expr);
-                let index = self.symbol_table.define(ident.get_string());
-                self.push_instruction(Instruction::SetGlobal(index));
+                self.store_let(name);
+            }
+            StatementNode::Destructure(_, pattern, expr) => {
+                self.check_destructure(pattern, expr);
+                self.compile_expression(expr);
+                self.compile_destructure(pattern);
             }
             StatementNode::Return(_, expr) => {
                 self.compile_expression(expr);
@@ -153,25 +389,72 @@ impl Emitter {
                 self.compile_expression(expr);
                 self.push_instruction(Instruction::Pop);
             }
+            // compiled by hoist_functions when the block started
+            StatementNode::Function(..) | StatementNode::Struct(..) | StatementNode::Enum(..) => {}
+            // the names a module imports are there before it runs (see compile_modules)
+            StatementNode::Import(..) => {}
+            StatementNode::Export(_, stmt) => self.compile_statement(stmt),
+            StatementNode::Break(_, label) | StatementNode::Continue(_, label) => {
+                let label = label.as_ref().and_then(|label| label.get_label_name());
+                let jump = self.current_instructions().len();
+                self.push_instruction(Instruction::Jump(0));
+                // the parser made sure that there is such a loop
+                let target = self
+                    .current_loops()
+                    .iter_mut()
+                    .rev()
+                    .find(|l| label.is_none() || l.label == label)
+                    .unwrap();
+                match stmt {
+                    StatementNode::Break(..) => target.breaks.push(jump),
+                    _ => target.continues.push(jump),
+                }
+            }
+            StatementNode::Error(_) => panic!("can't compile a program that failed to parse"),
         }
     }
 
     fn compile_expression(&mut self, expr: &ExpressionNode) {
         match expr {
-            ExpressionNode::Ident(token) => {
-                let name = token.clone().get_ident_name().unwrap();
-                if let Some(sym) = self.symbol_table.resolve(&name) {
-                    self.push_instruction(Instruction::GetGlobal(sym.index));
+            ExpressionNode::Ident(ident) => {
+                let name = ident.get_symbol();
+                if let Some(sym) = self.symbol_table.resolve(name) {
+                    self.load_symbol(&sym);
+                } else if let Some((index, _)) = builtins::lookup(name.as_str()) {
+                    self.push_instruction(Instruction::GetBuiltin(index as u8));
+                } else {
+                    self.errors.push(Diagnostic::error(
+                        format!("Can't find `{name}`, it was never declared"),
+                        ident.token().span,
+                    ));
                 }
             }
             ExpressionNode::Int(token) => {
-                if let TokenType::Int(ref int) = token.r#type {
-                    let int = int.parse::<isize>().unwrap();
+                if let TokenType::Int(int) = &token.r#type {
+                    // the parser has already rejected the invalid literals
+                    let int = int_literal_value(int).unwrap();
                     let i = Object::Int(int);
                     self.constants.push(i);
                     self.push_instruction(Instruction::Constant(self.constants.len() as u16 - 1));
                 }
             }
+            ExpressionNode::Float(token) => {
+                if let TokenType::Float(float) = &token.r#type {
+                    // the parser has already rejected the invalid literals
+                    let float = float_literal_value(float).unwrap();
+                    let f = Object::Float(float);
+                    self.constants.push(f);
+                    self.push_instruction(Instruction::Constant(self.constants.len() as u16 - 1));
+                }
+            }
+            ExpressionNode::Str(token) => {
+                if let TokenType::Str(str) = &token.r#type {
+                    // the parser has already rejected the invalid literals
+                    let str = string_literal_value(str).unwrap();
+                    self.constants.push(Object::Str(str));
+                    self.push_instruction(Instruction::Constant(self.constants.len() as u16 - 1));
+                }
+            }
             ExpressionNode::Bool(token) => {
                 let o = match token.r#type {
                     TokenType::True => Object::Bool(true),
@@ -191,80 +474,721 @@ impl Emitter {
                 };
                 self.push_instruction(operator);
             }
-            ExpressionNode::BinaryOperator(operator, left, right) => {
+            // a && b: a; JumpNotTruthy(F); b; JumpNotTruthy(F); T: true; Jump(E); F: false; E:
+            // a || b: a; JumpNotTruthy(R); Jump(T); R: b; JumpNotTruthy(F); T: ...
+            ExpressionNode::BinaryOperator(operator, left, right)
+                if matches!(operator.r#type, TokenType::And | TokenType::Or) =>
+            {
                 self.compile_expression(left);
+                let left_jump = self.current_instructions().len();
+                self.push_instruction(Instruction::JumpNotTruthy(0));
+                let short_circuit = self.current_instructions().len();
+                if operator.r#type == TokenType::Or {
+                    self.push_instruction(Instruction::Jump(0));
+                    let a_right = self.current_instructions().len();
+                    self.current_instructions()[left_jump] = Instruction::JumpNotTruthy(a_right);
+                }
                 self.compile_expression(right);
-                let operator = match operator.r#type {
-                    TokenType::Plus => Instruction::Add,
-                    TokenType::Minus => Instruction::Sub,
-                    TokenType::Asterisk => Instruction::Mul,
-                    TokenType::Slash => Instruction::Div,
-                    TokenType::Eq => Instruction::Eq,
-                    TokenType::NotEq => Instruction::NotEq,
-                    TokenType::GT => Instruction::GT,
-                    TokenType::LT => Instruction::LT,
-                    _ => unreachable!(),
-                };
-                self.push_instruction(operator);
-            }
-            ExpressionNode::Block(stmts) => {
-                for stmt in stmts {
-                    self.compile_statement(stmt);
+                let right_jump = self.current_instructions().len();
+                self.push_instruction(Instruction::JumpNotTruthy(0));
+
+                let a_true = self.current_instructions().len();
+                self.constants.push(Object::Bool(true));
+                self.push_instruction(Instruction::Constant(self.constants.len() as u16 - 1));
+                self.push_instruction(Instruction::Jump(0));
+                let a_false = self.current_instructions().len();
+                self.constants.push(Object::Bool(false));
+                self.push_instruction(Instruction::Constant(self.constants.len() as u16 - 1));
+                let a_end = self.current_instructions().len();
+
+                let instructions = self.current_instructions();
+                instructions[right_jump] = Instruction::JumpNotTruthy(a_false);
+                instructions[a_false - 1] = Instruction::Jump(a_end);
+                match operator.r#type {
+                    TokenType::And => instructions[left_jump] = Instruction::JumpNotTruthy(a_false),
+                    _ => instructions[short_circuit] = Instruction::Jump(a_true),
                 }
             }
+            ExpressionNode::BinaryOperator(operator, left, right) => {
+                self.compile_expression(left);
+                self.compile_expression(right);
+                self.push_instruction(Self::binary_instruction(operator.r#type.clone()));
+            }
+            ExpressionNode::Block(block) => self.compile_block(&block.statements),
+            // cond; JumpNotTruthy(alt); action; Jump(end); alt: alternate (or Null); end:
             ExpressionNode::If(_, cond, action, alternate) => {
                 self.compile_expression(cond);
                 self.push_instruction(Instruction::JumpNotTruthy(0));
                 let a_cond = self.current_instructions().len();
 
-                self.compile_expression(&ExpressionNode::Block(action.statements.clone()));
-                if matches!(self.get_last_instruction(), Some(Instruction::Pop)) {
-                    self.pop_instruction();
-                }
+                self.compile_block(&action.statements);
+                self.push_instruction(Instruction::Jump(0));
                 let a_act = self.current_instructions().len();
-                self.current_instructions()[a_cond - 1] = Instruction::JumpNotTruthy(a_act - 1);
+                self.current_instructions()[a_cond - 1] = Instruction::JumpNotTruthy(a_act);
+
+                match alternate {
+                    Some(Else::Block(alternate)) => self.compile_block(&alternate.statements),
+                    // the nested if leaves its value on the stack already
+                    Some(Else::If(alternate)) => self.compile_expression(alternate),
+                    // an if without else is null when the condition doesn't hold
+                    None => self.push_instruction(Instruction::Null),
+                }
+                let a_alt = self.current_instructions().len();
+                self.current_instructions()[a_act - 1] = Instruction::Jump(a_alt);
+            }
+            // start: condition; JumpNotTruthy(end); body; Jump(start); end: Null
+            ExpressionNode::While(_, label, condition, body) => {
+                let start = self.current_instructions().len();
+                self.compile_expression(condition);
+                let exit = self.current_instructions().len();
+                self.push_instruction(Instruction::JumpNotTruthy(0));
+                self.compile_loop_body(label.as_ref(), &body.statements, start, exit);
+            }
+            // goes over the iterable by index, with the array and the index kept in hidden slots
+            // the step that bumps the index is where a continue goes
+            ExpressionNode::For(_, label, var, iterable, body) => {
+                self.compile_expression(iterable);
+                self.push_instruction(Instruction::Iterate);
+                let items = self.symbol_table.define_hidden();
+                self.store_symbol(&items);
+                self.constants.push(Object::Int(0));
+                self.push_instruction(Instruction::Constant(self.constants.len() as u16 - 1));
+                let index = self.symbol_table.define_hidden();
+                self.store_symbol(&index);
+                let var = self.declare(var.get_symbol());
+
+                // index < len(items)
+                let start = self.current_instructions().len();
+                self.load_symbol(&index);
+                let (len, _) = builtins::lookup("len").unwrap();
+                self.push_instruction(Instruction::GetBuiltin(len as u8));
+                self.load_symbol(&items);
+                self.push_instruction(Instruction::Call(1));
+                self.push_instruction(Instruction::LT);
+                let exit = self.current_instructions().len();
+                self.push_instruction(Instruction::JumpNotTruthy(0));
+
+                // var = items[index]
+                self.load_symbol(&items);
+                self.load_symbol(&index);
+                self.push_instruction(Instruction::Index);
+                self.store_symbol(&var);
+
+                self.current_loops().push(Loop {
+                    label: label.as_ref().and_then(|label| label.get_label_name()),
+                    ..Default::default()
+                });
+                self.compile_statements(&body.statements);
+                let step = self.current_instructions().len();
+                self.load_symbol(&index);
+                self.constants.push(Object::Int(1));
+                self.push_instruction(Instruction::Constant(self.constants.len() as u16 - 1));
+                self.push_instruction(Instruction::Add);
+                self.store_symbol(&index);
+                self.finish_loop(start, step, exit);
+            }
+            ExpressionNode::Match(_, value, arms) => self.compile_match(value, arms),
+            ExpressionNode::Function(_, params, body) => {
+                self.compile_function(None, params, body);
+            }
+            ExpressionNode::Macro(token, ..) => self.errors.push(Diagnostic::error(
+                "Macros can only be defined by a `let` at the top level of the program",
+                token.span,
+            )),
+            // the code a quote gives back only exists in the evaluator, which is where macros run
+            ExpressionNode::FunctionCall(token, func, _)
+                if ["quote", "unquote"]
+                    .iter()
+                    .any(|name| evaluation::is_call_to(func, name)) =>
+            {
+                self.errors.push(Diagnostic::error(
+                    "`quote` and `unquote` can only be used in macros when the program is compiled",
+                    token.span,
+                ))
+            }
+            ExpressionNode::FunctionCall(_, func, args) => {
+                self.compile_expression(func);
+                for arg in args {
+                    self.compile_expression(arg);
+                }
+                self.push_instruction(Instruction::Call(args.len() as u8))
+            }
+            ExpressionNode::Array(_, elements) => {
+                for elem in elements {
+                    self.compile_expression(elem);
+                }
+                self.push_instruction(Instruction::Array(elements.len() as u16));
+            }
+            ExpressionNode::Tuple(_, elements) => {
+                for elem in elements {
+                    self.compile_expression(elem);
+                }
+                self.push_instruction(Instruction::Tuple(elements.len() as u16));
+            }
+            ExpressionNode::Index(_, collection, index) => {
+                self.compile_expression(collection);
+                self.compile_expression(index);
+                self.push_instruction(Instruction::Index);
+            }
+            ExpressionNode::Hash(_, entries) => {
+                for (key, value) in entries {
+                    self.compile_expression(key);
+                    self.compile_expression(value);
+                }
+                self.push_instruction(Instruction::Hash(entries.len() as u16));
+            }
+            ExpressionNode::Assign(operator, target, value) => {
+                let Some(symbol) = self.symbol_table.resolve(target.get_symbol()) else {
+                    self.errors.push(Diagnostic::error(
+                        format!(
+                            "Can't assign to `{}`, it was never declared (use `let` to declare it)",
+                            target.get_symbol()
+                        ),
+                        target.token().span,
+                    ));
+                    return;
+                };
+                if let Some(operator) = operator.r#type.compound_operator() {
+                    self.load_symbol(&symbol);
+                    self.compile_expression(value);
+                    self.push_instruction(Self::binary_instruction(operator));
+                } else {
+                    self.compile_expression(value);
+                }
+                self.store_symbol(&symbol);
+                // the assignment is an expression, its value is the one assigned
+                self.load_symbol(&symbol);
+            }
+            // the type, then the name and the value of every field given
+            ExpressionNode::Struct(_, name, fields) => {
+                self.compile_expression(&ExpressionNode::Ident(name.clone()));
+                for (field, value) in fields {
+                    let field = self.field_name(field);
+                    self.push_instruction(Instruction::Constant(field));
+                    self.compile_expression(value);
+                }
+                let name = self.field_name(name);
+                self.push_instruction(Instruction::Struct(name, fields.len() as u16));
+            }
+            ExpressionNode::Field(_, value, field) => {
+                self.compile_expression(value);
+                let field = self.field_name(field);
+                self.push_instruction(Instruction::GetField(field));
+            }
+            ExpressionNode::AssignField(operator, target, value) => {
+                self.compile_assign_field(operator, target, value)
+            }
+        }
+    }
+
+    // the index of a string constant with the name of `field` (or of a type or a variant)
+    fn field_name(&mut self, field: &Ident) -> u16 {
+        self.constants
+            .push(Object::Str(field.get_symbol().as_str().into()));
+        self.constants.len() as u16 - 1
+    }
+
+    // `a.b.c = value`, which sets `a` to a copy of it with the field replaced
+    // value; SetX(assigned); then the structs along the way, a and a.b, on the stack
+    // then the value to assign (computed from a.b.c for a compound operator); SetX(assigned)
+    // and SetField(c); SetField(b) to put it back together; SetX(a); GetX(assigned)
+    fn compile_assign_field(
+        &mut self,
+        operator: &Token,
+        target: &ExpressionNode,
+        value: &ExpressionNode,
+    ) {
+        // the parser only takes fields of variables as targets
+        let (variable, path) = target.field_path().unwrap();
+        let Some(symbol) = self.symbol_table.resolve(variable.get_symbol()) else {
+            self.errors.push(Diagnostic::error(
+                format!(
+                    "Can't assign to a field of `{}`, it was never declared",
+                    variable.get_symbol()
+                ),
+                variable.token().span,
+            ));
+            return;
+        };
+        let fields: Vec<_> = path.iter().map(|field| self.field_name(field)).collect();
+        let get_fields = |emitter: &mut Self, fields: &[u16]| {
+            emitter.load_symbol(&symbol);
+            for &field in fields {
+                emitter.push_instruction(Instruction::GetField(field));
+            }
+        };
+
+        self.compile_expression(value);
+        let assigned = self.symbol_table.define_hidden();
+        self.store_symbol(&assigned);
+        for i in 0..fields.len() {
+            get_fields(self, &fields[..i]);
+        }
+        if let Some(operator) = operator.r#type.compound_operator() {
+            get_fields(self, &fields);
+            self.load_symbol(&assigned);
+            self.push_instruction(Self::binary_instruction(operator));
+            self.store_symbol(&assigned);
+        }
+        self.load_symbol(&assigned);
+        for &field in fields.iter().rev() {
+            self.push_instruction(Instruction::SetField(field));
+        }
+        self.store_symbol(&symbol);
+        self.load_symbol(&assigned);
+    }
+
+    // leaves the closure on the stack
+    // the params are the first locals, a rest param gets an array of the arguments left over
+    // and the defaults of the ones that weren't passed are computed before the body:
+    // JumpIfPassed(param, next); default; SetLocal(param); next: for each of them
+    fn compile_function(&mut self, name: Option<symbol::Symbol>, params: &[Param], body: &Block) {
+        self.new_scope();
+        let symbols: Vec<_> = params
+            .iter()
+            .map(|param| self.symbol_table.define(param.ident.get_symbol()))
+            .collect();
+        for (i, (param, symbol)) in params.iter().zip(&symbols).enumerate() {
+            if let Some(default) = &param.default {
+                self.push_instruction(Instruction::JumpIfPassed(i as u8, 0));
+                let jump = self.current_instructions().len() - 1;
+                self.compile_expression(default);
+                self.store_symbol(symbol);
+                let next = self.current_instructions().len();
+                self.current_instructions()[jump] = Instruction::JumpIfPassed(i as u8, next);
+            }
+        }
+        self.compile_statements(&body.statements);
+        if matches!(self.get_last_instruction(), Some(Instruction::Pop)) {
+            self.pop_instruction();
+            self.push_instruction(Instruction::ReturnValue);
+        }
+        if !matches!(self.get_last_instruction(), Some(Instruction::ReturnValue)) {
+            self.push_instruction(Instruction::Return);
+        }
+        let num_locals = self.symbol_table.num_definitions;
+        let (scope, free_symbols) = self.pop_scope();
+
+        // the captured variables themselves, for the closure to share them
+        for symbol in &free_symbols {
+            self.push_instruction(match symbol.scope {
+                Scope::Local => Instruction::CaptureLocal(symbol.index as u8),
+                Scope::Free => Instruction::CaptureFree(symbol.index as u8),
+                Scope::Global => unreachable!("globals are never captured"),
+            });
+        }
+        let func = Object::CompiledFunction(
+            scope.emit(&mut self.constants),
+            num_locals,
+            params.len() as u8,
+            Arity::of(params),
+            name,
+        );
+        self.constants.push(func);
+        self.push_instruction(Instruction::Closure(
+            self.constants.len() as u16 - 1,
+            free_symbols.len() as u8,
+        ));
+    }
+
+    // the statements of a program or a block
+    // with the functions (and struct types) declared in it hoisted to its start
+    fn compile_statements(&mut self, stmts: &[StatementNode]) {
+        self.hoist_functions(stmts);
+        for stmt in stmts {
+            self.compile_statement(stmt);
+        }
+    }
+
+    // the statements of a block, leaving the value of the last one on the stack
+    // or null when the block doesn't end with an expression
+    fn compile_block(&mut self, stmts: &[StatementNode]) {
+        self.compile_statements(stmts);
+        match stmts.last() {
+            Some(StatementNode::Expression(_)) => self.pop_instruction(),
+            _ => self.push_instruction(Instruction::Null),
+        }
+    }
+
+    // every declared function is defined before any of them is compiled, along with the lets
+    // next to them, so that they can refer to each other
+    // a local one can capture the others before they are set, as a closure shares the variables
+    // it captures (see Instruction::CaptureLocal)
+    // the struct and enum types are constants, set before any function so they can use them
+    fn hoist_functions(&mut self, stmts: &[StatementNode]) {
+        for stmt in stmts {
+            let (name, r#type) = match stmt.without_export() {
+                StatementNode::Struct(_, name, fields) => {
+                    let fields = fields.iter().map(Ident::get_symbol).collect();
+                    (name, Object::StructType(name.get_symbol(), fields))
+                }
+                StatementNode::Enum(_, name, variants) => {
+                    let variants = variants
+                        .iter()
+                        .map(|(variant, fields)| {
+                            let fields = fields.iter().map(Ident::get_symbol).collect();
+                            (variant.get_symbol(), fields)
+                        })
+                        .collect();
+                    (name, Object::EnumType(name.get_symbol(), variants))
+                }
+                _ => continue,
+            };
+            self.constants.push(r#type);
+            self.push_instruction(Instruction::Constant(self.constants.len() as u16 - 1));
+            let symbol = self.declare(name.get_symbol());
+            self.store_symbol(&symbol);
+        }
+        let declared: Vec<_> = stmts
+            .iter()
+            .filter_map(|stmt| match stmt.without_export() {
+                StatementNode::Function(_, name, params, body) => Some((name, params, body)),
+                _ => None,
+            })
+            .collect();
+        if declared.is_empty() {
+            return;
+        }
+        let symbols: Vec<_> = declared
+            .iter()
+            .map(|(name, ..)| self.declare(name.get_symbol()))
+            .collect();
+        for stmt in stmts {
+            let names = match stmt.without_export() {
+                StatementNode::Let(_, name, _) => vec![name],
+                StatementNode::Destructure(_, pattern, _) => pattern.bindings(),
+                _ => continue,
+            };
+            for name in names {
+                self.declare(name.get_symbol());
+            }
+        }
+        for ((name, params, body), symbol) in declared.into_iter().zip(&symbols) {
+            self.compile_function(Some(name.get_symbol()), params, body);
+            self.store_symbol(symbol);
+        }
+    }
 
-                if let Some(alternate) = alternate {
+    // the value goes into a hidden slot, that the arms are tried against one after the other:
+    // pattern tests; guard; JumpNotTruthy(next arm) for each of them; body; Jump(end)
+    // and when no arm matches: Null; end:
+    //
+    // arms in a row that are just a literal (without a guard) share a single JumpTable instead
+    // value; JumpTable(targets); Jump(next arm); body; Jump(end); body; Jump(end); ...
+    //
+    // every arm is a scope of its own, for the names it binds and the ones declared in it
+    fn compile_match(&mut self, value: &ExpressionNode, arms: &[MatchArm]) {
+        self.warn_unreachable_arms(arms);
+        self.compile_expression(value);
+        let subject = self.symbol_table.define_hidden();
+        self.store_symbol(&subject);
+
+        let is_literal =
+            |arm: &MatchArm| arm.guard.is_none() && matches!(arm.pattern, Pattern::Literal(_));
+        let mut end_jumps = vec![];
+        let mut rest = arms;
+        while let Some(arm) = rest.first() {
+            let literals = rest.iter().take_while(|arm| is_literal(arm)).count();
+            if literals > 1 {
+                self.load_symbol(&subject);
+                let table_jump = self.current_instructions().len();
+                self.push_instruction(Instruction::JumpTable(0));
+                self.push_instruction(Instruction::Jump(0));
+                let mut table = BTreeMap::new();
+                for arm in &rest[..literals] {
+                    let Pattern::Literal(literal) = &arm.pattern else {
+                        unreachable!()
+                    };
+                    let target = self.current_instructions().len();
+                    // a value that is there twice goes to the first arm, the others are unreachable
+                    table
+                        .entry(Hashable::from_literal(literal))
+                        .or_insert(Object::Int(target as isize));
+                    self.symbol_table.start_scope();
+                    self.compile_expression(&arm.body);
+                    self.symbol_table.end_scope();
+                    end_jumps.push(self.current_instructions().len());
                     self.push_instruction(Instruction::Jump(0));
-                    let a_act = self.current_instructions().len();
-                    self.current_instructions()[a_cond - 1] = Instruction::JumpNotTruthy(a_act - 1);
+                }
+                self.constants.push(Object::Hash(table));
+                let table = self.constants.len() as u16 - 1;
+                let next = self.current_instructions().len();
+                let instructions = self.current_instructions();
+                instructions[table_jump] = Instruction::JumpTable(table);
+                instructions[table_jump + 1] = Instruction::Jump(next);
+                rest = &rest[literals..];
+                continue;
+            }
+
+            let mut fail_jumps = vec![];
+            self.symbol_table.start_scope();
+            self.compile_pattern(&arm.pattern, &subject, &mut fail_jumps);
+            if let Some(guard) = &arm.guard {
+                self.compile_expression(guard);
+                fail_jumps.push(self.current_instructions().len());
+                self.push_instruction(Instruction::JumpNotTruthy(0));
+            }
+            self.compile_expression(&arm.body);
+            self.symbol_table.end_scope();
+            end_jumps.push(self.current_instructions().len());
+            self.push_instruction(Instruction::Jump(0));
+            let next = self.current_instructions().len();
+            for jump in fail_jumps {
+                self.current_instructions()[jump] = Instruction::JumpNotTruthy(next);
+            }
+            rest = &rest[1..];
+        }
 
-                    self.compile_expression(&ExpressionNode::Block(alternate.statements.clone()));
-                    if matches!(self.get_last_instruction(), Some(Instruction::Pop)) {
-                        self.pop_instruction();
+        self.push_instruction(Instruction::Null);
+        let end = self.current_instructions().len();
+        for jump in end_jumps {
+            self.current_instructions()[jump] = Instruction::Jump(end);
+        }
+    }
+
+    // checks the value in `value` against the pattern, binding the names in it along the way
+    // every check that can fail is a JumpNotTruthy, added to `fail_jumps` for the caller to patch
+    fn compile_pattern(&mut self, pattern: &Pattern, value: &Symbol, fail_jumps: &mut Vec<usize>) {
+        match pattern {
+            Pattern::Wildcard(_) => {}
+            Pattern::Binding(ident) => {
+                let var = self.define(ident.get_symbol());
+                self.load_symbol(value);
+                self.store_symbol(&var);
+            }
+            Pattern::Literal(literal) => {
+                self.load_symbol(value);
+                self.constants.push(Hashable::from_literal(literal).into());
+                self.push_instruction(Instruction::MatchLiteral(self.constants.len() as u16 - 1));
+                fail_jumps.push(self.current_instructions().len());
+                self.push_instruction(Instruction::JumpNotTruthy(0));
+            }
+            Pattern::Array(_, patterns) | Pattern::Tuple(_, patterns) => {
+                self.load_symbol(value);
+                let len = patterns.len() as u16;
+                self.push_instruction(match pattern {
+                    Pattern::Tuple(..) => Instruction::MatchTuple(len),
+                    _ => Instruction::MatchArray(len),
+                });
+                fail_jumps.push(self.current_instructions().len());
+                self.push_instruction(Instruction::JumpNotTruthy(0));
+                for (i, pattern) in patterns.iter().enumerate() {
+                    if let Pattern::Wildcard(_) = pattern {
+                        continue;
                     }
-                    let a_alt = self.current_instructions().len();
-                    self.current_instructions()[a_act - 1] = Instruction::Jump(a_alt - 1);
+                    self.load_symbol(value);
+                    self.constants.push(Object::Int(i as isize));
+                    self.push_instruction(Instruction::Constant(self.constants.len() as u16 - 1));
+                    self.push_instruction(Instruction::Index);
+                    let element = self.symbol_table.define_hidden();
+                    self.store_symbol(&element);
+                    self.compile_pattern(pattern, &element, fail_jumps);
                 }
             }
-            ExpressionNode::Function(_, _, body) => {
-                self.new_scope();
-                self.compile_expression(&ExpressionNode::Block(body.statements.clone()));
-                if matches!(self.get_last_instruction(), Some(Instruction::Pop)) {
-                    self.pop_instruction();
-                    self.push_instruction(Instruction::ReturnValue);
-                }
-                if matches!(self.get_last_instruction(), Some(Instruction::Pop)) {
-                    self.push_instruction(Instruction::Return);
+            // value; the enum type; MatchVariant(variant, len); JumpNotTruthy(fail)
+            // then for each field: value; Payload(i) (with the field checked in turn)
+            Pattern::Variant(name, variant, patterns) => {
+                self.load_symbol(value);
+                self.compile_expression(&ExpressionNode::Ident(name.clone()));
+                let name = self.field_name(name);
+                let variant = self.field_name(variant);
+                let len = patterns.len() as u8;
+                self.push_instruction(Instruction::MatchVariant(name, variant, len));
+                fail_jumps.push(self.current_instructions().len());
+                self.push_instruction(Instruction::JumpNotTruthy(0));
+                for (i, pattern) in patterns.iter().enumerate() {
+                    if let Pattern::Wildcard(_) = pattern {
+                        continue;
+                    }
+                    self.load_symbol(value);
+                    self.push_instruction(Instruction::Payload(i as u8));
+                    let field = self.symbol_table.define_hidden();
+                    self.store_symbol(&field);
+                    self.compile_pattern(pattern, &field, fail_jumps);
                 }
-                let func = self.pop_scope().emit();
+            }
+        }
+    }
 
-                self.constants.push(Object::CompiledFunction(func));
-                self.push_instruction(Instruction::Constant(self.constants.len() as u16 - 1));
+    // the value on the stack goes into the variable that a let of `name` declares
+    fn store_let(&mut self, name: symbol::Symbol) {
+        let symbol = self.declare(name);
+        self.store_symbol(&symbol);
+    }
+
+    // takes the value on the stack apart, into the names in the pattern of a destructuring let
+    // ExpectArray(len) or ExpectTuple(len); SetX(value); then for each part: value; i; Index
+    // (with the part taken apart in turn)
+    fn compile_destructure(&mut self, pattern: &Pattern) {
+        let len = |patterns: &Vec<Pattern>| patterns.len() as u16;
+        let (patterns, expect) = match pattern {
+            Pattern::Wildcard(_) => return self.push_instruction(Instruction::Pop),
+            Pattern::Binding(ident) => return self.store_let(ident.get_symbol()),
+            Pattern::Array(_, patterns) => (patterns, Instruction::ExpectArray(len(patterns))),
+            Pattern::Tuple(_, patterns) => (patterns, Instruction::ExpectTuple(len(patterns))),
+            Pattern::Literal(_) | Pattern::Variant(..) => {
+                unreachable!("the parser doesn't let a let compare values")
             }
-            ExpressionNode::FunctionCall(_, func, args) => {
-                self.compile_expression(func);
-                self.push_instruction(Instruction::Call)
+        };
+        self.push_instruction(expect);
+        let value = self.symbol_table.define_hidden();
+        self.store_symbol(&value);
+        for (i, pattern) in patterns.iter().enumerate() {
+            if let Pattern::Wildcard(_) = pattern {
+                continue;
             }
-            _ => todo!(),
+            self.load_symbol(&value);
+            self.constants.push(Object::Int(i as isize));
+            self.push_instruction(Instruction::Constant(self.constants.len() as u16 - 1));
+            self.push_instruction(Instruction::Index);
+            self.compile_destructure(pattern);
+        }
+    }
+
+    // a tuple or array written out in the let that doesn't fit the pattern
+    // doesn't need to wait for the program to run
+    fn check_destructure(&mut self, pattern: &Pattern, value: &ExpressionNode) {
+        let (token, patterns, kind, value, elements) = match (pattern, value) {
+            (Pattern::Array(token, patterns), ExpressionNode::Array(value, elements)) => {
+                (token, patterns, "an array", value, elements)
+            }
+            (Pattern::Tuple(token, patterns), ExpressionNode::Tuple(value, elements)) => {
+                (token, patterns, "a tuple", value, elements)
+            }
+            (
+                Pattern::Array(token, _) | Pattern::Tuple(token, _),
+                ExpressionNode::Array(value, _) | ExpressionNode::Tuple(value, _),
+            ) => {
+                let (expected, kind, label) = match pattern {
+                    Pattern::Tuple(..) => ("a tuple", "array", "this is an array"),
+                    _ => ("an array", "tuple", "this is a tuple"),
+                };
+                let issue =
+                    format!("Expected {expected} to destructure, got a value of type {kind}");
+                return self
+                    .errors
+                    .push(Diagnostic::error(issue, token.span).with_label(value.span, label));
+            }
+            _ => return,
+        };
+        if patterns.len() != elements.len() {
+            let issue = format!(
+                "Can't destructure {kind} of length {} with a pattern of length {}",
+                elements.len(),
+                patterns.len()
+            );
+            let label = format!("this has {} elements", elements.len());
+            return self
+                .errors
+                .push(Diagnostic::error(issue, token.span).with_label(value.span, label));
+        }
+        for (pattern, element) in patterns.iter().zip(elements) {
+            self.check_destructure(pattern, element);
+        }
+    }
+
+    // an arm can't be reached if an earlier one (without a guard) matches everything it would
+    fn warn_unreachable_arms(&mut self, arms: &[MatchArm]) {
+        for (i, arm) in arms.iter().enumerate() {
+            let covering = arms[..i]
+                .iter()
+                .find(|earlier| earlier.guard.is_none() && covers(&earlier.pattern, &arm.pattern));
+            if let Some(earlier) = covering {
+                self.warnings.push(
+                    Diagnostic::warning("Unreachable match arm", arm.pattern.token().span)
+                        .with_primary_message("this arm is never taken")
+                        .with_label(
+                            earlier.pattern.token().span,
+                            "this arm matches everything it would",
+                        ),
+                );
+            }
+        }
+    }
+
+    // the body of a while loop, and the jumps back to the start and out of the loop
+    fn compile_loop_body(
+        &mut self,
+        label: Option<&Token>,
+        body: &[StatementNode],
+        start: usize,
+        exit: usize,
+    ) {
+        self.current_loops().push(Loop {
+            label: label.and_then(|label| label.get_label_name()),
+            ..Default::default()
+        });
+        self.compile_statements(body);
+        self.finish_loop(start, start, exit);
+    }
+
+    // closes the innermost loop, `next` is where a continue goes
+    // and `exit` the index of the JumpNotTruthy that leaves the loop once the condition fails
+    fn finish_loop(&mut self, start: usize, next: usize, exit: usize) {
+        self.push_instruction(Instruction::Jump(start));
+        let end = self.current_instructions().len();
+        let finished = self.current_loops().pop().unwrap();
+        let instructions = self.current_instructions();
+        instructions[exit] = Instruction::JumpNotTruthy(end);
+        for jump in finished.breaks {
+            instructions[jump] = Instruction::Jump(end);
+        }
+        for jump in finished.continues {
+            instructions[jump] = Instruction::Jump(next);
+        }
+        // loops are expressions too
+        self.push_instruction(Instruction::Null);
+    }
+
+    fn binary_instruction(operator: TokenType) -> Instruction {
+        match operator {
+            TokenType::Plus => Instruction::Add,
+            TokenType::Minus => Instruction::Sub,
+            TokenType::Asterisk => Instruction::Mul,
+            TokenType::Slash => Instruction::Div,
+            TokenType::Eq => Instruction::Eq,
+            TokenType::NotEq => Instruction::NotEq,
+            TokenType::GT => Instruction::GT,
+            TokenType::LT => Instruction::LT,
+            TokenType::Percent => Instruction::Mod,
+            TokenType::DoubleAsterisk => Instruction::Pow,
+            TokenType::GTEq => Instruction::GTEq,
+            TokenType::LTEq => Instruction::LTEq,
+            TokenType::Ampersand => Instruction::BitAnd,
+            TokenType::Pipe => Instruction::BitOr,
+            TokenType::Caret => Instruction::BitXor,
+            TokenType::LShift => Instruction::Shl,
+            TokenType::RShift => Instruction::Shr,
+            _ => unreachable!(),
         }
     }
 
     pub fn emit(mut self) -> Bytecode {
         let main_scope = self.scopes.pop().unwrap();
         Bytecode {
-            instructions: main_scope.emit(),
+            instructions: main_scope.emit(&mut self.constants),
             constants: self.constants.clone(),
         }
     }
 }
+
+// whether every value that matches `other` also matches `pattern`
+fn covers(pattern: &Pattern, other: &Pattern) -> bool {
+    match (pattern, other) {
+        (Pattern::Wildcard(_) | Pattern::Binding(_), _) => true,
+        (Pattern::Literal(one), Pattern::Literal(two)) => {
+            Hashable::from_literal(one) == Hashable::from_literal(two)
+        }
+        (Pattern::Array(_, ones), Pattern::Array(_, twos))
+        | (Pattern::Tuple(_, ones), Pattern::Tuple(_, twos)) => {
+            ones.len() == twos.len() && ones.iter().zip(twos).all(|(one, two)| covers(one, two))
+        }
+        (Pattern::Variant(name, variant, ones), Pattern::Variant(other, other_variant, twos)) => {
+            name.get_symbol() == other.get_symbol()
+                && variant.get_symbol() == other_variant.get_symbol()
+                && ones.len() == twos.len()
+                && ones.iter().zip(twos).all(|(one, two)| covers(one, two))
+        }
+        _ => false,
+    }
+}
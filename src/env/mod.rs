@@ -1,11 +1,15 @@
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::token::{symbol::Symbol, Span};
 // use crate::object::Object;
 
 // pub(crate) type Env = Rc<RefCell<InnerEnv>>;
 
 #[derive(Debug, Clone)]
 pub struct Env<T> {
-    store: HashMap<String, T>,
+    store: HashMap<Symbol, T>,
+    // the bindings that are constants, with the span of the name they are declared with
+    consts: HashMap<Symbol, Span>,
     outer: Option<Rc<RefCell<Env<T>>>>,
 }
 
@@ -13,6 +17,7 @@ impl<T> Env<T> {
     pub fn new() -> Rc<RefCell<Self>> {
         let env = Self {
             store: HashMap::new(),
+            consts: HashMap::new(),
             outer: None,
         };
         Rc::new(RefCell::new(env))
@@ -21,13 +26,45 @@ impl<T> Env<T> {
     pub fn extend(older: Rc<RefCell<Self>>) -> Rc<RefCell<Self>> {
         let env = Self {
             store: HashMap::new(),
+            consts: HashMap::new(),
             outer: Some(older),
         };
         Rc::new(RefCell::new(env))
     }
 
-    pub fn set(&mut self, key: String, val: T) {
+    pub fn set(&mut self, key: Symbol, val: T) {
+        self.store.insert(key, val);
+    }
+
+    // same as `set`, but a constant (declared at `decl`) if there's a `decl`
+    // a constant can't be bound again, only its own declaration can do that (when it's in a loop)
+    // so the span of its declaration is given back for anything else
+    pub fn declare(&mut self, key: Symbol, val: T, decl: Option<Span>) -> Result<(), Span> {
+        match self.consts.get(&key) {
+            Some(&span) if decl != Some(span) => return Err(span),
+            _ => {}
+        }
+        if let Some(span) = decl {
+            self.consts.insert(key, span);
+        }
         self.store.insert(key, val);
+        Ok(())
+    }
+
+    // updates the nearest existing binding of `key`, going out through the enclosing scopes
+    // unlike `set`, this never creates a binding
+    pub fn assign(&mut self, key: Symbol, val: T) -> Result<(), AssignError> {
+        if let Some(slot) = self.store.get_mut(&key) {
+            if let Some(&span) = self.consts.get(&key) {
+                return Err(AssignError::Const(span));
+            }
+            *slot = val;
+            return Ok(());
+        }
+        match &self.outer {
+            Some(outer) => outer.borrow_mut().assign(key, val),
+            None => Err(AssignError::Undeclared),
+        }
     }
 
     // pub fn add_new_context(&mut self) {
@@ -48,9 +85,18 @@ impl<T> Env<T> {
     // }
 }
 
+// why `Env::assign` didn't update a binding
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssignError {
+    // there's none to update
+    Undeclared,
+    // it's a constant, declared at this span
+    Const(Span),
+}
+
 impl<T: Clone> Env<T> {
-    pub fn get(&self, key: &String) -> Option<T> {
-        self.store.get(key).cloned().or(self
+    pub fn get(&self, key: Symbol) -> Option<T> {
+        self.store.get(&key).cloned().or(self
             .outer
             .as_ref()
             .and_then(|outer| outer.borrow().get(key)))
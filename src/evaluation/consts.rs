@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use crate::ast::{
+    expression::{ExpressionNode, Ident, MatchArm, Param},
+    statement::StatementNode,
+    visit::{self, Visitor},
+    Program,
+};
+use crate::diagnostics::Diagnostic;
+use crate::token::{symbol::Symbol, Span};
+
+// Constants
+//
+// `const name = value` declares a name that can't be assigned to, or declared again
+// (by a let, a const, a fn, a struct, an enum, a param, a for or a match) in the same scope
+// a scope is the top level of a program, the body of a function or an arm of a match
+// the blocks in it share it the same way they share the env of the evaluator
+//
+// this is checked here, before anything runs, with every declaration of a scope known from its
+// start so an assignment refers to the binding it will when it runs (a function can be called
+// after the lets that come below it)
+// what the source can't tell is caught by env::Env when it runs, i.e, an assignment that comes
+// before the let of its function, which goes out to a constant of the enclosing scopes
+
+#[derive(Debug, Clone, Copy)]
+struct Declaration {
+    // of the name
+    span: Span,
+    is_const: bool,
+}
+
+// the declarations of a scope by name, in the order they appear
+type Scope = HashMap<Symbol, Vec<Declaration>>;
+
+// the writes to a constant found in the program
+// `imported` are the constants the program starts out with, with the spans of their names
+pub(super) fn check_consts(program: &Program, imported: &[(Symbol, Span)]) -> Vec<Diagnostic> {
+    let mut checker = Checker {
+        scopes: vec![],
+        errors: vec![],
+    };
+    let mut scope = Scope::new();
+    for &(name, span) in imported {
+        let is_const = true;
+        scope
+            .entry(name)
+            .or_default()
+            .push(Declaration { span, is_const });
+    }
+    checker.enter(scope, &[], &program.statements);
+    visit::walk_program(&mut checker, program);
+    checker.errors.sort_by_key(|err| err.primary.span);
+    checker.errors
+}
+
+struct Checker {
+    scopes: Vec<Scope>,
+    errors: Vec<Diagnostic>,
+}
+
+impl Checker {
+    // starts the scope of a function (or of the program), which has to be left with `scopes.pop()`
+    fn enter(&mut self, mut scope: Scope, params: &[Param], body: &[StatementNode]) {
+        let mut declarations = Declarations(&mut scope);
+        for param in params {
+            declarations.declare(&param.ident, false);
+        }
+        body.iter()
+            .for_each(|stmt| declarations.visit_statement(stmt));
+        self.push(scope);
+    }
+
+    // starts the scope of a match arm, with the names its pattern binds
+    fn enter_arm(&mut self, arm: &MatchArm) {
+        let mut scope = Scope::new();
+        let mut declarations = Declarations(&mut scope);
+        for name in arm.pattern.bindings() {
+            declarations.declare(name, false);
+        }
+        if let Some(guard) = &arm.guard {
+            declarations.visit_expression(guard);
+        }
+        declarations.visit_expression(&arm.body);
+        self.push(scope);
+    }
+
+    // checks the declarations of a scope against its constants, and makes it the innermost one
+    fn push(&mut self, scope: Scope) {
+        for (name, declared) in &scope {
+            let Some(constant) = declared.iter().find(|decl| decl.is_const) else {
+                continue;
+            };
+            for other in declared {
+                let error = if other.span == constant.span {
+                    continue;
+                } else if other.span > constant.span {
+                    let issue = format!("`{name}` is a constant, it can't be declared again");
+                    Diagnostic::error(issue, other.span).with_label(
+                        constant.span,
+                        format!("`{name}` is declared as a constant here"),
+                    )
+                } else {
+                    let issue = format!("`{name}` is declared in this scope already");
+                    Diagnostic::error(issue, constant.span)
+                        .with_primary_message("so it can't be made a constant here")
+                        .with_label(other.span, format!("`{name}` is declared here"))
+                };
+                self.errors.push(error);
+            }
+        }
+        self.scopes.push(scope);
+    }
+
+    // `what` is what's assigned to, the variable itself or a field of it
+    fn check_assign(&mut self, target: &Ident, what: &str) {
+        let name = target.get_symbol();
+        let Some(declared) = self.scopes.iter().rev().find_map(|scope| scope.get(&name)) else {
+            return;
+        };
+        if let Some(constant) = declared.iter().find(|decl| decl.is_const) {
+            let issue = format!("Can't assign to {what}`{name}`, it's a constant");
+            self.errors
+                .push(Diagnostic::error(issue, target.token().span).with_label(
+                    constant.span,
+                    format!("`{name}` is declared as a constant here"),
+                ));
+        }
+    }
+}
+
+impl Visitor for Checker {
+    fn visit_statement(&mut self, stmt: &StatementNode) {
+        match stmt {
+            StatementNode::Function(_, _, params, body) => {
+                self.enter(Scope::new(), params, &body.statements);
+                visit::walk_statement(self, stmt);
+                self.scopes.pop();
+            }
+            stmt => visit::walk_statement(self, stmt),
+        }
+    }
+
+    fn visit_expression(&mut self, expr: &ExpressionNode) {
+        match expr {
+            ExpressionNode::Function(_, params, body) | ExpressionNode::Macro(_, params, body) => {
+                self.enter(Scope::new(), params, &body.statements);
+                visit::walk_expression(self, expr);
+                self.scopes.pop();
+            }
+            ExpressionNode::Assign(_, target, _) => {
+                self.check_assign(target, "");
+                visit::walk_expression(self, expr);
+            }
+            ExpressionNode::AssignField(_, target, _) => {
+                // the parser only takes fields of variables as targets
+                let (variable, _) = target.field_path().unwrap();
+                self.check_assign(&variable, "a field of ");
+                visit::walk_expression(self, expr);
+            }
+            ExpressionNode::Match(token, value, arms) => {
+                self.visit_token(token);
+                self.visit_expression(value);
+                for arm in arms {
+                    self.enter_arm(arm);
+                    self.visit_pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.visit_expression(guard);
+                    }
+                    self.visit_expression(&arm.body);
+                    self.scopes.pop();
+                }
+            }
+            expr => visit::walk_expression(self, expr),
+        }
+    }
+}
+
+// the names declared in a scope, without going into the functions in it
+struct Declarations<'a>(&'a mut Scope);
+
+impl Declarations<'_> {
+    fn declare(&mut self, name: &Ident, is_const: bool) {
+        let span = name.token().span;
+        let declaration = Declaration { span, is_const };
+        self.0
+            .entry(name.get_symbol())
+            .or_default()
+            .push(declaration);
+    }
+}
+
+impl Visitor for Declarations<'_> {
+    fn visit_statement(&mut self, stmt: &StatementNode) {
+        match stmt {
+            StatementNode::Let(_, name, expr) => {
+                self.declare(name, stmt.is_const());
+                self.visit_expression(expr);
+            }
+            StatementNode::Destructure(_, pattern, expr) => {
+                for name in pattern.bindings() {
+                    self.declare(name, stmt.is_const());
+                }
+                self.visit_expression(expr);
+            }
+            StatementNode::Function(_, name, ..)
+            | StatementNode::Struct(_, name, _)
+            | StatementNode::Enum(_, name, _) => self.declare(name, false),
+            stmt => visit::walk_statement(self, stmt),
+        }
+    }
+
+    fn visit_expression(&mut self, expr: &ExpressionNode) {
+        match expr {
+            ExpressionNode::Function(..) | ExpressionNode::Macro(..) => {}
+            ExpressionNode::For(_, _, var, ..) => {
+                self.declare(var, false);
+                visit::walk_expression(self, expr);
+            }
+            // the arms are scopes of their own
+            ExpressionNode::Match(_, value, _) => self.visit_expression(value),
+            expr => visit::walk_expression(self, expr),
+        }
+    }
+}
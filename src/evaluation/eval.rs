@@ -1,24 +1,82 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
 use std::rc::Rc;
 
-use crate::ast::{expression::ExpressionNode, statement::StatementNode, Node};
-use crate::env::Env;
-use crate::object::Object;
-use crate::token::{token_type::TokenType, Token};
+use crate::ast::{
+    expression::{Else, ExpressionNode, Ident, MatchArm, Param},
+    pattern::Pattern,
+    statement::StatementNode,
+    Node,
+};
+use crate::diagnostics::{Diagnostic, Label};
+use crate::env::{AssignError, Env};
+use crate::object::{builtins, Arity, Hashable, Object};
+
+use super::macros;
+use crate::token::{
+    float_literal_value, int_literal_value, string_literal_value, symbol::Symbol,
+    token_type::TokenType, Token,
+};
 
-#[allow(dead_code)]
 #[derive(Debug)]
 pub(super) struct EvalError {
     pub issue: String,
     pub token: Token,
+    // other places in the source worth pointing at
+    pub labels: Vec<Label>,
+}
+
+pub(super) fn eval_err(issue: String, token: Token) -> EvalError {
+    EvalError {
+        issue,
+        token,
+        labels: vec![],
+    }
 }
 
-fn eval_err(issue: String, token: Token) -> EvalError {
-    EvalError { issue, token }
+impl From<EvalError> for Diagnostic {
+    fn from(value: EvalError) -> Self {
+        let mut diagnostic = Diagnostic::error(value.issue, value.token.span);
+        diagnostic.secondary = value.labels;
+        diagnostic
+    }
 }
 
 type EvalResult = Result<Object, EvalError>;
 
+// the evaluator recurses on the Rust stack, where a call to a function takes tens of kilobytes
+// (a lot more in a debug build), so a deeper recursion stops with an error instead of
+// overflowing it, the vm keeps its frames on the heap and doesn't need a limit
+const MAX_CALL_DEPTH: usize = 200;
+
+thread_local! {
+    // how many calls to functions are being evaluated on this thread right now
+    static CALL_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+// counts a call to a function for as long as it is being evaluated
+struct CallDepth;
+
+impl CallDepth {
+    fn enter(token: &Token) -> Result<Self, EvalError> {
+        let depth = CALL_DEPTH.get();
+        if depth == MAX_CALL_DEPTH {
+            return Err(eval_err(
+                format!("Maximum call depth of {MAX_CALL_DEPTH} exceeded"),
+                token.clone(),
+            ));
+        }
+        CALL_DEPTH.set(depth + 1);
+        Ok(CallDepth)
+    }
+}
+
+impl Drop for CallDepth {
+    fn drop(&mut self) {
+        CALL_DEPTH.set(CALL_DEPTH.get() - 1);
+    }
+}
+
 pub(super) fn eval(node: Node, env: Rc<RefCell<Env<Object>>>) -> EvalResult {
     match node {
         Node::Program(program) => {
@@ -28,100 +86,460 @@ pub(super) fn eval(node: Node, env: Rc<RefCell<Env<Object>>>) -> EvalResult {
         }
         Node::Statement(stmt) => match stmt {
             StatementNode::Expression(expr) => eval(Node::Expression(expr), env),
+            // already set up by hoist_functions when the block started
+            StatementNode::Function(..) | StatementNode::Struct(..) | StatementNode::Enum(..) => {
+                Ok(Object::Null)
+            }
+            // the names a module imports are there before it runs (see eval_modules)
+            StatementNode::Import(..) => Ok(Object::Null),
+            StatementNode::Export(_, stmt) => eval(Node::Statement(*stmt), env),
             StatementNode::Return(_, expr) => {
                 let ret_val = eval(Node::Expression(expr), env)?;
                 Ok(Object::Return(Box::from(ret_val)))
             }
-            StatementNode::Let(_, ident, val) => {
+            StatementNode::Let(token, ident, val) => {
+                let is_const = token.r#type == TokenType::Const;
+                let val = eval(Node::Expression(val), env.clone())?;
+                // like `let x = if c { return 1 } else { 2 };`
+                if val.is_signal() {
+                    return Ok(val);
+                }
+                declare(&env, ident.token(), val, is_const)?;
+                Ok(Object::Null)
+            }
+            StatementNode::Destructure(token, pattern, val) => {
+                let is_const = token.r#type == TokenType::Const;
                 let val = eval(Node::Expression(val), env.clone())?;
-                env.borrow_mut().set(ident.into(), val); // irrecoverable error: problem with implementation
+                if val.is_signal() {
+                    return Ok(val);
+                }
+                let mut bindings = vec![];
+                destructure(&pattern, val, &mut bindings)?;
+                for (name, value) in bindings {
+                    declare(&env, name.token(), value, is_const)?;
+                }
                 Ok(Object::Null)
             }
+            // the parser made sure that they are inside a loop, which catches the signal
+            StatementNode::Break(_, label) => Ok(Object::Break(
+                label.and_then(|label| label.get_label_name()),
+            )),
+            StatementNode::Continue(_, label) => Ok(Object::Continue(
+                label.and_then(|label| label.get_label_name()),
+            )),
+            StatementNode::Error(span) => Err(eval_err(
+                "Can't evaluate a statement that failed to parse".into(),
+                Token::new(TokenType::Dummy, span),
+            )),
         },
         Node::Expression(expr) => match expr {
-            ExpressionNode::Ident(token) => eval_ident(token, env),
+            ExpressionNode::Ident(ident) => eval_ident(ident, env),
             ExpressionNode::Int(token) => eval_int(token),
+            ExpressionNode::Float(token) => eval_float(token),
             ExpressionNode::Bool(token) => eval_bool(token),
+            ExpressionNode::Str(token) => eval_str(token),
             ExpressionNode::UnaryOperator(operator, operand) => {
                 let operand = eval(Node::Expression(*operand), env)?;
                 eval_unary(operator, operand)
             }
+            ExpressionNode::BinaryOperator(operator, lhs, rhs)
+                if matches!(operator.r#type, TokenType::And | TokenType::Or) =>
+            {
+                eval_logical(operator, *lhs, *rhs, env)
+            }
             ExpressionNode::BinaryOperator(operator, lhs, rhs) => {
                 let lhs = eval(Node::Expression(*lhs), env.clone())?;
                 let rhs = eval(Node::Expression(*rhs), env)?;
                 eval_binary(operator, lhs, rhs)
             }
-            ExpressionNode::Block(block) => eval_block(block, env),
+            ExpressionNode::Block(block) => eval_block(block.statements, env),
             ExpressionNode::If(_, condition, action, alternate) => {
                 let condition: bool = eval(Node::Expression(*condition), env.clone())?.into();
                 if condition {
                     eval_block(action.statements, env)
                 } else {
-                    alternate.map_or(Ok(Object::Null), |alternate| {
-                        eval_block(alternate.statements, env.clone())
-                    })
+                    match alternate {
+                        Some(Else::Block(alternate)) => eval_block(alternate.statements, env),
+                        Some(Else::If(alternate)) => eval(Node::Expression(*alternate), env),
+                        None => Ok(Object::Null),
+                    }
                 }
             }
+            ExpressionNode::Match(_, value, arms) => eval_match(*value, arms, env),
             ExpressionNode::Function(token, args, block) => {
-                Ok(Object::Function(token, args, block, env))
+                Ok(Object::Function(None, token, args, block, env))
+            }
+            // the definitions were taken out of the program, along with the calls
+            ExpressionNode::Macro(token, ..) => Err(eval_err(
+                "Macros can only be defined by a `let` at the top level of the program".into(),
+                token,
+            )),
+            ExpressionNode::FunctionCall(token, function, mut args)
+                if macros::is_call_to(&function, "quote") =>
+            {
+                if args.len() != 1 {
+                    let issue = format!("`quote` takes 1 argument, got {}", args.len());
+                    return Err(eval_err(issue, token));
+                }
+                macros::quote(args.pop().unwrap(), env)
+            }
+            ExpressionNode::FunctionCall(token, function, _)
+                if macros::is_call_to(&function, "unquote") =>
+            {
+                Err(eval_err(
+                    "`unquote` can only be used in a quote".into(),
+                    token,
+                ))
             }
             ExpressionNode::FunctionCall(token, function, args) => {
-                let function = eval(Node::Expression(*function), env.clone())?;
-                let args = args
+                eval_call(token, *function, args, env)
+            }
+            ExpressionNode::Array(_, elements) => {
+                let elements = elements
                     .into_iter()
-                    .map(|arg| eval(Node::Expression(arg), env.clone()))
+                    .map(|elem| eval(Node::Expression(elem), env.clone()))
                     .collect::<Result<Vec<Object>, EvalError>>()?;
-                if let Object::Function(token, params, block, env) = function {
-                    if args.len() != params.len() {
-                        return Err(eval_err(
-                            format!(
-                                "Incorrect number of arguments passed, Got: {}, Expected: {}",
-                                args.len(),
-                                params.len()
-                            ),
-                            token,
-                        ));
+                Ok(Object::Array(elements))
+            }
+            ExpressionNode::Tuple(_, elements) => {
+                let elements = elements
+                    .into_iter()
+                    .map(|elem| eval(Node::Expression(elem), env.clone()))
+                    .collect::<Result<Vec<Object>, EvalError>>()?;
+                Ok(Object::Tuple(elements))
+            }
+            ExpressionNode::Index(token, collection, index) => {
+                let collection = eval(Node::Expression(*collection), env.clone())?;
+                let index = eval(Node::Expression(*index), env)?;
+                eval_index(token, collection, index)
+            }
+            ExpressionNode::Hash(token, entries) => {
+                let mut hash = BTreeMap::new();
+                for (key, value) in entries {
+                    let key = eval(Node::Expression(key), env.clone())?;
+                    let key = key.hash_key().map_err(|err| eval_err(err, token.clone()))?;
+                    let value = eval(Node::Expression(value), env.clone())?;
+                    // the last one wins if a key is repeated
+                    hash.insert(key, value);
+                }
+                Ok(Object::Hash(hash))
+            }
+            ExpressionNode::Assign(operator, target, value) => {
+                let value = eval(Node::Expression(*value), env.clone())?;
+                // like `x = if c { return 1 } else { 2 }`, the return isn't a value to store
+                if value.is_signal() {
+                    return Ok(value);
+                }
+                eval_assign(operator, target, value, env)
+            }
+            ExpressionNode::Struct(token, name, fields) => eval_struct(token, name, fields, env),
+            ExpressionNode::Field(_, value, field) => {
+                let value = eval(Node::Expression(*value), env)?;
+                get_field(value, &field)
+            }
+            ExpressionNode::AssignField(operator, target, value) => {
+                let value = eval(Node::Expression(*value), env.clone())?;
+                if value.is_signal() {
+                    return Ok(value);
+                }
+                eval_assign_field(operator, *target, value, env)
+            }
+            ExpressionNode::While(_, label, condition, body) => {
+                let label = label.and_then(|label| label.get_label_name());
+                loop {
+                    let condition = eval(Node::Expression(*condition.clone()), env.clone())?;
+                    if !<Object as Into<bool>>::into(condition) {
+                        break;
                     }
-
-                    let new_env = Env::extend(env);
-
-                    for (p, a) in params.into_iter().map(|p| p.into()).zip(args.into_iter()) {
-                        new_env.borrow_mut().set(p, a);
+                    let result = eval_block(body.statements.clone(), env.clone())?;
+                    if let Some(signal) = loop_exit(result, label) {
+                        return signal;
                     }
-
-                    let ret_val = eval_block(block.statements, new_env)?;
-                    let ret_val = if let Object::Return(ret_val) = ret_val {
-                        *ret_val
-                    } else {
-                        ret_val
-                    };
-                    Ok(ret_val)
-                } else {
-                    Err(eval_err("Can't call a non function".into(), token))
                 }
+                Ok(Object::Null)
+            }
+            ExpressionNode::For(token, label, var, iterable, body) => {
+                let label = label.and_then(|label| label.get_label_name());
+                let iterable = eval(Node::Expression(*iterable), env.clone())?;
+                let items = iterable
+                    .into_iterable()
+                    .map_err(|err| eval_err(err, token))?;
+                // the loop variable lives in the enclosing scope, like any other let in a block
+                for item in items {
+                    declare(&env, var.token(), item, false)?;
+                    let result = eval_block(body.statements.clone(), env.clone())?;
+                    if let Some(signal) = loop_exit(result, label) {
+                        return signal;
+                    }
+                }
+                Ok(Object::Null)
             }
         },
     }
 }
 
-fn eval_ident(token: Token, env: Rc<RefCell<Env<Object>>>) -> Result<Object, EvalError> {
-    let key = token.clone().get_ident_name().unwrap(); // Ident(AST) contains Ident(Token)
+// `function(args)`, a call to a builtin, a variant constructor or a function
+fn eval_call(
+    token: Token,
+    function: ExpressionNode,
+    args: Vec<ExpressionNode>,
+    env: Rc<RefCell<Env<Object>>>,
+) -> EvalResult {
+    let function = eval(Node::Expression(function), env.clone())?;
+    let args = args
+        .into_iter()
+        .map(|arg| eval(Node::Expression(arg), env.clone()))
+        .collect::<Result<Vec<Object>, EvalError>>()?;
+    if let Object::Builtin(builtin) = function {
+        return (builtin.func)(args).map_err(|err| eval_err(err, token));
+    }
+    if let Object::Constructor(type_name, variant, fields) = function {
+        if args.len() != fields.len() {
+            let issue = format!(
+                "Incorrect number of arguments passed to `{type_name}.{variant}`, \
+                 Got: {}, Expected: {}",
+                args.len(),
+                fields.len()
+            );
+            return Err(eval_err(issue, token));
+        }
+        return Ok(Object::Variant(
+            type_name,
+            variant,
+            fields.into_iter().zip(args).collect(),
+        ));
+    }
+    if let Object::Function(name, fn_token, params, block, env) = function {
+        let arity = Arity::of(&params);
+        if !arity.accepts(args.len()) {
+            return Err(arity_err(name, arity, args.len(), token, fn_token));
+        }
+        let _depth = CallDepth::enter(&token)?;
+
+        let new_env = Env::extend(env);
+        bind_params(params, args, &new_env)?;
+
+        let ret_val = eval_block(block.statements, new_env)?;
+        let ret_val = if let Object::Return(ret_val) = ret_val {
+            *ret_val
+        } else {
+            ret_val
+        };
+        Ok(ret_val)
+    } else {
+        Err(eval_err("Can't call a non function".into(), token))
+    }
+}
+
+fn eval_ident(ident: Ident, env: Rc<RefCell<Env<Object>>>) -> Result<Object, EvalError> {
+    let key = ident.get_symbol();
+    // bindings shadow the builtins
     env.borrow()
-        .get(&key)
-        .ok_or(eval_err("Failed to fetch the identifier".into(), token))
+        .get(key)
+        .or_else(|| builtins::lookup(key.as_str()).map(|(_, builtin)| Object::Builtin(builtin)))
+        .ok_or_else(|| {
+            eval_err(
+                format!("Can't find `{key}`, it was never declared"),
+                ident.token().clone(),
+            )
+        })
+}
+
+// the value of an assignment is the value that was assigned
+fn eval_assign(
+    operator: Token,
+    target: Ident,
+    value: Object,
+    env: Rc<RefCell<Env<Object>>>,
+) -> EvalResult {
+    let name = target.get_symbol();
+    let undeclared = || {
+        eval_err(
+            format!("Can't assign to `{name}`, it was never declared (use `let` to declare it)"),
+            target.token().clone(),
+        )
+    };
+    let constant = |span| EvalError {
+        issue: format!("Can't assign to `{name}`, it's a constant"),
+        token: target.token().clone(),
+        labels: vec![Label::new(
+            span,
+            format!("`{name}` is declared as a constant here"),
+        )],
+    };
+    let value = match operator.r#type.compound_operator() {
+        Some(r#type) => {
+            let current = env.borrow().get(name).ok_or_else(undeclared)?;
+            eval_binary(Token { r#type, ..operator }, current, value)?
+        }
+        None => value,
+    };
+    match env.borrow_mut().assign(name, value.clone()) {
+        Ok(()) => Ok(value),
+        Err(AssignError::Undeclared) => Err(undeclared()),
+        Err(AssignError::Const(span)) => Err(constant(span)),
+    }
+}
+
+// `Name { field: value, ... }`, which has to give every field of the struct type `Name`
+// the fields end up in the order of the type, whatever the order they are given in
+fn eval_struct(
+    token: Token,
+    name: Ident,
+    fields: Vec<(Ident, ExpressionNode)>,
+    env: Rc<RefCell<Env<Object>>>,
+) -> EvalResult {
+    let r#type = eval_ident(name.clone(), env.clone())?;
+    let Object::StructType(type_name, declared) = r#type else {
+        return Err(eval_err(
+            format!(
+                "`{}` isn't a struct type, it's a value of type {}",
+                name.get_symbol(),
+                r#type.type_name()
+            ),
+            name.token().clone(),
+        ));
+    };
+    // the parser made sure that no field is given twice
+    let mut values = vec![None; declared.len()];
+    for (field, value) in fields {
+        let Some(i) = declared.iter().position(|&name| name == field.get_symbol()) else {
+            return Err(eval_err(
+                format!(
+                    "The struct `{type_name}` has no field `{}`",
+                    field.get_symbol()
+                ),
+                field.token().clone(),
+            ));
+        };
+        values[i] = Some(eval(Node::Expression(value), env.clone())?);
+    }
+    let missing: Vec<_> = declared
+        .iter()
+        .zip(&values)
+        .filter(|(_, value)| value.is_none())
+        .map(|(name, _)| format!("`{name}`"))
+        .collect();
+    if !missing.is_empty() {
+        return Err(eval_err(
+            format!(
+                "Missing fields for the struct `{type_name}`: {}",
+                missing.join(", ")
+            ),
+            token,
+        ));
+    }
+    let values = values.into_iter().map(Option::unwrap);
+    Ok(Object::Struct(
+        type_name,
+        declared.into_iter().zip(values).collect(),
+    ))
+}
+
+// the error for `object.field`, when the object doesn't have that field
+fn no_field_err(object: &Object, field: &Ident) -> EvalError {
+    eval_err(object.no_field(field.get_symbol()), field.token().clone())
+}
+
+fn get_field(object: Object, field: &Ident) -> EvalResult {
+    object
+        .field(field.get_symbol())
+        .map_err(|err| eval_err(err, field.token().clone()))
+}
+
+// `object` with the field at the end of `path` (`b.c` of `a.b.c`) replaced by what `update` gives
+fn update_field(
+    mut object: Object,
+    path: &[&Ident],
+    update: impl FnOnce(Object) -> EvalResult,
+) -> EvalResult {
+    let Some((field, rest)) = path.split_first() else {
+        return update(object);
+    };
+    let name = field.get_symbol();
+    let (Object::Struct(_, fields) | Object::Variant(_, _, fields)) = &mut object else {
+        return Err(no_field_err(&object, field));
+    };
+    let Some(i) = fields.iter().position(|&(other, _)| other == name) else {
+        return Err(no_field_err(&object, field));
+    };
+    let value = std::mem::replace(&mut fields[i].1, Object::Null);
+    fields[i].1 = update_field(value, rest, update)?;
+    Ok(object)
+}
+
+// `a.b.c = value`, which assigns to `a` a copy of it with the field replaced
+// the value of it is the value that was assigned, same as for a variable
+fn eval_assign_field(
+    operator: Token,
+    target: ExpressionNode,
+    value: Object,
+    env: Rc<RefCell<Env<Object>>>,
+) -> EvalResult {
+    // the parser only takes fields of variables as targets
+    let (variable, path) = target.field_path().unwrap();
+    let name = variable.get_symbol();
+    let undeclared = || {
+        eval_err(
+            format!("Can't assign to a field of `{name}`, it was never declared"),
+            variable.token().clone(),
+        )
+    };
+    let constant = |span| EvalError {
+        issue: format!("Can't assign to a field of `{name}`, it's a constant"),
+        token: variable.token().clone(),
+        labels: vec![Label::new(
+            span,
+            format!("`{name}` is declared as a constant here"),
+        )],
+    };
+    let object = env.borrow().get(name).ok_or_else(undeclared)?;
+    let mut assigned = Object::Null;
+    let object = update_field(object, &path, |current| {
+        assigned = match operator.r#type.compound_operator() {
+            Some(r#type) => eval_binary(Token { r#type, ..operator }, current, value)?,
+            None => value,
+        };
+        Ok(assigned.clone())
+    })?;
+    match env.borrow_mut().assign(name, object) {
+        Ok(()) => Ok(assigned),
+        Err(AssignError::Undeclared) => Err(undeclared()),
+        Err(AssignError::Const(span)) => Err(constant(span)),
+    }
+}
+
+fn eval_index(token: Token, collection: Object, index: Object) -> EvalResult {
+    collection.index(index).map_err(|err| eval_err(err, token))
 }
 
 fn eval_int(token: Token) -> EvalResult {
-    if let TokenType::Int(ref int) = token.r#type {
-        let int = int
-            .parse::<isize>()
-            .map_err(|err| eval_err(err.to_string(), token))?;
+    if let TokenType::Int(int) = &token.r#type {
+        let int = int_literal_value(int).map_err(|err| eval_err(err, token.clone()))?;
         Ok(Object::Int(int))
     } else {
         Err(eval_err("Expected Integer".into(), token))
     }
 }
 
+fn eval_float(token: Token) -> EvalResult {
+    if let TokenType::Float(float) = &token.r#type {
+        let float = float_literal_value(float).map_err(|err| eval_err(err, token.clone()))?;
+        Ok(Object::Float(float))
+    } else {
+        Err(eval_err("Expected Float".into(), token))
+    }
+}
+
+fn eval_str(token: Token) -> EvalResult {
+    if let TokenType::Str(str) = &token.r#type {
+        let str = string_literal_value(str).map_err(|err| eval_err(err, token.clone()))?;
+        Ok(Object::Str(str))
+    } else {
+        Err(eval_err("Expected String".into(), token))
+    }
+}
+
 fn eval_bool(token: Token) -> EvalResult {
     let o = match token.r#type {
         TokenType::True => Object::Bool(true),
@@ -134,26 +552,26 @@ fn eval_bool(token: Token) -> EvalResult {
 fn eval_unary(operator: Token, operand: Object) -> EvalResult {
     let o = match operator.r#type {
         TokenType::Bang => (!<Object as Into<bool>>::into(operand)).into(),
-        TokenType::Plus => {
-            if let Object::Int(int) = operand {
-                Object::Int(int)
-            } else {
+        TokenType::Plus => match operand {
+            Object::Int(int) => Object::Int(int),
+            Object::Float(float) => Object::Float(float),
+            _ => {
                 return Err(eval_err(
-                    "Operand for the Unary Operator + should be an integer".into(),
+                    "Operand for the Unary Operator + should be a number".into(),
                     operator,
-                ));
+                ))
             }
-        }
-        TokenType::Minus => {
-            if let Object::Int(int) = operand {
-                Object::Int(-int)
-            } else {
+        },
+        TokenType::Minus => match operand {
+            Object::Int(int) => Object::Int(-int),
+            Object::Float(float) => Object::Float(-float),
+            _ => {
                 return Err(eval_err(
-                    "Operand for the Unary Operator - should be an integer".into(),
+                    "Operand for the Unary Operator - should be a number".into(),
                     operator,
-                ));
+                ))
             }
-        }
+        },
         _ => return Err(eval_err("Invalid unary operator".into(), operator)),
     };
     Ok(o)
@@ -169,11 +587,230 @@ fn eval_binary(operator: Token, lhs: Object, rhs: Object) -> EvalResult {
         TokenType::NotEq => (lhs.not_eq(rhs)).map_err(|err| eval_err(err, operator))?,
         TokenType::GT => (lhs.gt(rhs)).map_err(|err| eval_err(err, operator))?,
         TokenType::LT => (lhs.lt(rhs)).map_err(|err| eval_err(err, operator))?,
+        TokenType::GTEq => (lhs.gt_eq(rhs)).map_err(|err| eval_err(err, operator))?,
+        TokenType::LTEq => (lhs.lt_eq(rhs)).map_err(|err| eval_err(err, operator))?,
+        TokenType::Percent => (lhs % rhs).map_err(|err| eval_err(err, operator))?,
+        TokenType::DoubleAsterisk => (lhs.pow(rhs)).map_err(|err| eval_err(err, operator))?,
+        TokenType::Ampersand => (lhs & rhs).map_err(|err| eval_err(err, operator))?,
+        TokenType::Pipe => (lhs | rhs).map_err(|err| eval_err(err, operator))?,
+        TokenType::Caret => (lhs ^ rhs).map_err(|err| eval_err(err, operator))?,
+        TokenType::LShift => (lhs << rhs).map_err(|err| eval_err(err, operator))?,
+        TokenType::RShift => (lhs >> rhs).map_err(|err| eval_err(err, operator))?,
         _ => return Err(eval_err("Invalid binary operator".into(), operator)),
     };
     Ok(o)
 }
 
+// && and || only evaluate the right side when the left one doesn't settle it already
+// either way the result is a bool
+fn eval_logical(
+    operator: Token,
+    lhs: ExpressionNode,
+    rhs: ExpressionNode,
+    env: Rc<RefCell<Env<Object>>>,
+) -> EvalResult {
+    let lhs = eval(Node::Expression(lhs), env.clone())?;
+    if lhs.is_signal() {
+        return Ok(lhs);
+    }
+    let lhs: bool = lhs.into();
+    match (operator.r#type, lhs) {
+        (TokenType::And, false) => Ok(Object::Bool(false)),
+        (TokenType::Or, true) => Ok(Object::Bool(true)),
+        _ => {
+            let rhs = eval(Node::Expression(rhs), env)?;
+            if rhs.is_signal() {
+                return Ok(rhs);
+            }
+            Ok(Object::Bool(rhs.into()))
+        }
+    }
+}
+
+// the body of the first arm whose pattern matches (and whose guard holds), or null if there's none
+// the names the pattern binds are in an env of the arm's own, which its guard and body run in
+fn eval_match(
+    value: ExpressionNode,
+    arms: Vec<MatchArm>,
+    env: Rc<RefCell<Env<Object>>>,
+) -> EvalResult {
+    let value = eval(Node::Expression(value), env.clone())?;
+    if value.is_signal() {
+        return Ok(value);
+    }
+    for arm in arms {
+        let mut bindings = vec![];
+        if !match_pattern(&arm.pattern, &value, &mut bindings, &env)? {
+            continue;
+        }
+        let scope = Env::extend(env.clone());
+        for (name, value) in bindings {
+            declare(&scope, name.token(), value, false)?;
+        }
+        if let Some(guard) = arm.guard {
+            let guard = eval(Node::Expression(guard), scope.clone())?;
+            if guard.is_signal() {
+                return Ok(guard);
+            }
+            if !<Object as Into<bool>>::into(guard) {
+                continue;
+            }
+        }
+        return eval(Node::Expression(arm.body), scope);
+    }
+    Ok(Object::Null)
+}
+
+// whether `value` fits `pattern`, the names it binds are added to `bindings`
+// the enum of a variant pattern is looked up in `env`, it's an error if it doesn't have the variant
+fn match_pattern<'a>(
+    pattern: &'a Pattern,
+    value: &Object,
+    bindings: &mut Vec<(&'a Ident, Object)>,
+    env: &Rc<RefCell<Env<Object>>>,
+) -> Result<bool, EvalError> {
+    match (pattern, value) {
+        (Pattern::Wildcard(_), _) => Ok(true),
+        (Pattern::Binding(ident), value) => {
+            bindings.push((ident, value.clone()));
+            Ok(true)
+        }
+        (Pattern::Literal(literal), value) => Ok(value
+            .hash_key()
+            .is_ok_and(|key| key == Hashable::from_literal(literal))),
+        (Pattern::Array(_, patterns), Object::Array(elements))
+        | (Pattern::Tuple(_, patterns), Object::Tuple(elements)) => {
+            if patterns.len() != elements.len() {
+                return Ok(false);
+            }
+            match_all(patterns, elements, bindings, env)
+        }
+        (Pattern::Array(..) | Pattern::Tuple(..), _) => Ok(false),
+        (Pattern::Variant(name, variant, patterns), value) => {
+            let r#type = eval_ident(name.clone(), env.clone())?;
+            let Object::EnumType(type_name, variants) = &r#type else {
+                return Err(eval_err(
+                    format!(
+                        "`{}` isn't an enum type, it's a value of type {}",
+                        name.get_symbol(),
+                        r#type.type_name()
+                    ),
+                    name.token().clone(),
+                ));
+            };
+            let variant_name = variant.get_symbol();
+            let Some((_, fields)) = variants.iter().find(|&&(other, _)| other == variant_name)
+            else {
+                return Err(no_field_err(&r#type, variant));
+            };
+            if fields.len() != patterns.len() {
+                let plural = if fields.len() == 1 { "" } else { "s" };
+                return Err(eval_err(
+                    format!(
+                        "The variant `{type_name}.{variant_name}` has {} field{plural}, \
+                         the pattern has {}",
+                        fields.len(),
+                        patterns.len()
+                    ),
+                    variant.token().clone(),
+                ));
+            }
+            match value {
+                Object::Variant(one, two, fields) if one == type_name && *two == variant_name => {
+                    let values: Vec<_> = fields.iter().map(|(_, value)| value.clone()).collect();
+                    match_all(patterns, &values, bindings, env)
+                }
+                _ => Ok(false),
+            }
+        }
+    }
+}
+
+// whether every value fits the pattern at the same index
+fn match_all<'a>(
+    patterns: &'a [Pattern],
+    values: &[Object],
+    bindings: &mut Vec<(&'a Ident, Object)>,
+    env: &Rc<RefCell<Env<Object>>>,
+) -> Result<bool, EvalError> {
+    for (pattern, value) in patterns.iter().zip(values) {
+        if !match_pattern(pattern, value, bindings, env)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+// the names in the pattern of a destructuring let, bound to the parts of `value`
+// unlike in a match, a value that doesn't fit the pattern is an error
+fn destructure<'a>(
+    pattern: &'a Pattern,
+    value: Object,
+    bindings: &mut Vec<(&'a Ident, Object)>,
+) -> Result<(), EvalError> {
+    let (token, patterns, kind) = match pattern {
+        Pattern::Wildcard(_) => return Ok(()),
+        Pattern::Binding(ident) => {
+            bindings.push((ident, value));
+            return Ok(());
+        }
+        Pattern::Array(token, patterns) => (token, patterns, "an array"),
+        Pattern::Tuple(token, patterns) => (token, patterns, "a tuple"),
+        Pattern::Literal(_) | Pattern::Variant(..) => {
+            unreachable!("the parser doesn't let a let compare values")
+        }
+    };
+    let elements = match value {
+        Object::Array(elements) if kind == "an array" => elements,
+        Object::Tuple(elements) if kind == "a tuple" => elements,
+        value => {
+            return Err(eval_err(
+                format!(
+                    "Expected {kind} to destructure, got a value of type {}",
+                    value.type_name()
+                ),
+                token.clone(),
+            ))
+        }
+    };
+    if elements.len() != patterns.len() {
+        return Err(eval_err(
+            format!(
+                "Can't destructure {kind} of length {} with a pattern of length {}",
+                elements.len(),
+                patterns.len()
+            ),
+            token.clone(),
+        ));
+    }
+    patterns
+        .iter()
+        .zip(elements)
+        .try_for_each(|(pattern, element)| destructure(pattern, element, bindings))
+}
+
+// binds `name` (the token it's declared with) in the env, as a constant if `is_const`
+// which fails for a name that's a constant in the env already
+fn declare(
+    env: &Rc<RefCell<Env<Object>>>,
+    name: &Token,
+    value: Object,
+    is_const: bool,
+) -> Result<(), EvalError> {
+    let symbol = name.get_ident_name().unwrap();
+    let decl = is_const.then_some(name.span);
+    env.borrow_mut()
+        .declare(symbol, value, decl)
+        .map_err(|span| EvalError {
+            issue: format!("Can't declare `{symbol}` again, it's a constant in this scope"),
+            token: name.clone(),
+            labels: vec![Label::new(
+                span,
+                format!("`{symbol}` is declared as a constant here"),
+            )],
+        })
+}
+
 // fn eval_program_inner(block: Vec<StatementNode>) -> Object {
 // block
 //     .into_iter()
@@ -188,11 +825,108 @@ fn eval_binary(operator: Token, lhs: Object, rhs: Object) -> EvalResult {
 //  || any = right
 // }any
 
-fn eval_block(block: Vec<StatementNode>, env: Rc<RefCell<Env<Object>>>) -> EvalResult {
+pub(super) fn arity_err(
+    name: Option<Symbol>,
+    arity: Arity,
+    got: usize,
+    token: Token,
+    fn_token: Token,
+) -> EvalError {
+    let name = name.map(|name| format!(" to `{name}`")).unwrap_or_default();
+    let mut err = eval_err(
+        format!("Incorrect number of arguments passed{name}, Got: {got}, Expected: {arity}"),
+        token,
+    );
+    let what = if fn_token.r#type == TokenType::Macro {
+        "macro"
+    } else {
+        "function"
+    };
+    err.labels
+        .push(Label::new(fn_token.span, format!("{what} defined here")));
+    err
+}
+
+// the missing arguments are filled in with the defaults, evaluated one after the other in the
+// function's env, so a default can refer to the params before it
+pub(super) fn bind_params(
+    params: Vec<Param>,
+    args: Vec<Object>,
+    env: &Rc<RefCell<Env<Object>>>,
+) -> Result<(), EvalError> {
+    let mut args = args.into_iter();
+    for param in params {
+        let value = if param.rest.is_some() {
+            Object::Array(args.by_ref().collect())
+        } else if let Some(arg) = args.next() {
+            arg
+        } else {
+            // the arity was checked, so there's a default
+            eval(Node::Expression(param.default.unwrap()), env.clone())?
+        };
+        env.borrow_mut().set(param.ident.get_symbol(), value);
+    }
+    Ok(())
+}
+
+// the functions declared in a block exist from its start
+// so they can be called before their declaration
+// they all close over the same env, which is how they get to call each other
+// the struct and enum types declared in it are there from the start too, for the functions to use
+fn hoist_functions(block: &[StatementNode], env: &Rc<RefCell<Env<Object>>>) {
+    for stmt in block {
+        match stmt.without_export() {
+            StatementNode::Function(token, name, params, body) => {
+                let function = Object::Function(
+                    Some(name.get_symbol()),
+                    token.clone(),
+                    params.clone(),
+                    body.clone(),
+                    env.clone(),
+                );
+                env.borrow_mut().set(name.get_symbol(), function);
+            }
+            StatementNode::Struct(_, name, fields) => {
+                let fields = fields.iter().map(Ident::get_symbol).collect();
+                let r#type = Object::StructType(name.get_symbol(), fields);
+                env.borrow_mut().set(name.get_symbol(), r#type);
+            }
+            StatementNode::Enum(_, name, variants) => {
+                let variants = variants
+                    .iter()
+                    .map(|(variant, fields)| {
+                        let fields = fields.iter().map(Ident::get_symbol).collect();
+                        (variant.get_symbol(), fields)
+                    })
+                    .collect();
+                let r#type = Object::EnumType(name.get_symbol(), variants);
+                env.borrow_mut().set(name.get_symbol(), r#type);
+            }
+            _ => {}
+        }
+    }
+}
+
+// what a loop (labeled `label`) does after an iteration that ended with `result`
+// None to keep going, or the value the loop evaluates to
+fn loop_exit(result: Object, label: Option<Symbol>) -> Option<EvalResult> {
+    // an unlabeled break or continue belongs to the innermost loop
+    let targets = |target: &Option<Symbol>| target.is_none() || *target == label;
+    match result {
+        Object::Break(target) if targets(&target) => Some(Ok(Object::Null)),
+        Object::Continue(target) if targets(&target) => None,
+        // on its way to an outer loop, or out of the function
+        signal if signal.is_signal() => Some(Ok(signal)),
+        _ => None,
+    }
+}
+
+pub(super) fn eval_block(block: Vec<StatementNode>, env: Rc<RefCell<Env<Object>>>) -> EvalResult {
+    hoist_functions(&block, &env);
     let mut result = Object::Null;
     for stmt in block {
         let r = eval(Node::Statement(stmt), env.clone())?;
-        if matches!(r, Object::Return(_)) {
+        if r.is_signal() {
             return Ok(r);
         }
         result = r;
@@ -0,0 +1,555 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::eval::{arity_err, bind_params, eval, eval_block, eval_err, EvalError};
+use crate::ast::{
+    expression::{Block, ExpressionNode, Ident, MatchArm, Param},
+    fold::{self, Folder},
+    pattern::Pattern,
+    statement::StatementNode,
+    Node, Program,
+};
+use crate::env::Env;
+use crate::object::{Arity, Object};
+use crate::token::{string_literal, symbol::Symbol, token_type::TokenType, Token};
+
+// Macros
+//
+// `let name = macro(params) { body };` at the top level of a program defines a macro
+// and every call to it in the program is replaced with the code it gives back, before anything runs
+// the body gets the code of the arguments (unevaluated, as quotes) and has to give back a quote:
+//
+// let unless = macro(cond, then, otherwise) {
+//     quote(if !(unquote(cond)) { unquote(then) } else { unquote(otherwise) })
+// };
+//
+// the names bound in the quotes of a macro (by let, fn, params, for and match)
+// are renamed to fresh ones every time it's expanded, outside of their unquotes
+// so they can't capture the names in the code that the macro was called with, or shadow them
+// and the other way around, the names bound in the program outside of its top level are renamed
+// before the expansion, so a name a quote doesn't bind is the one where the macros are defined
+// (a global or a builtin), even at a call in a function with a variable of the same name
+
+// a macro that keeps expanding to a call to itself is stopped here
+const MAX_DEPTH: usize = 100;
+
+// takes the macro definitions out of the program and expands the calls to them
+pub(super) fn expand_macros(program: Program) -> Result<Program, EvalError> {
+    let env = Env::new();
+    let mut statements = vec![];
+    let mut has_macros = false;
+    for stmt in program.statements {
+        match stmt {
+            StatementNode::Let(_, name, ExpressionNode::Macro(token, params, body)) => {
+                let r#macro = Object::Macro(token, params, body, env.clone());
+                env.borrow_mut().set(name.get_symbol(), r#macro);
+                has_macros = true;
+            }
+            StatementNode::Export(
+                _,
+                box StatementNode::Let(_, _, ExpressionNode::Macro(token, ..)),
+            ) => {
+                let issue = "Macros can't be exported, they're expanded in the module they are in";
+                return Err(eval_err(issue.into(), token));
+            }
+            stmt => statements.push(stmt),
+        }
+    }
+
+    if !has_macros {
+        return Ok(Program { statements });
+    }
+    let program = Rename::program().fold_program(Program { statements });
+
+    let mut expander = Expander {
+        env,
+        depth: 0,
+        error: None,
+    };
+    let program = expander.fold_program(program);
+    match expander.error {
+        Some(err) => Err(err),
+        None => Ok(program),
+    }
+}
+
+// `quote(expr)`, the code of expr
+// with the unquote(arg) calls in it replaced by the code of the value of arg
+pub(super) fn quote(
+    expr: ExpressionNode,
+    env: Rc<RefCell<Env<Object>>>,
+) -> Result<Object, EvalError> {
+    let mut unquoter = Unquoter { env, error: None };
+    let expr = unquoter.fold_expression(expr);
+    match unquoter.error {
+        Some(err) => Err(err),
+        None => Ok(Object::Quote(expr)),
+    }
+}
+
+// quote and unquote look like calls, but they get the code of their argument
+pub(crate) fn is_call_to(function: &ExpressionNode, name: &str) -> bool {
+    match function {
+        ExpressionNode::Ident(ident) => ident.get_symbol().as_str() == name,
+        _ => false,
+    }
+}
+
+struct Expander {
+    env: Rc<RefCell<Env<Object>>>,
+    // how many expansions deep the expression being folded is
+    depth: usize,
+    // the first error, nothing is expanded after it
+    error: Option<EvalError>,
+}
+
+impl Expander {
+    fn lookup(&self, function: &ExpressionNode) -> Option<(Symbol, Object)> {
+        let ExpressionNode::Ident(ident) = function else {
+            return None;
+        };
+        let name = ident.get_symbol();
+        let r#macro = self.env.borrow().get(name)?;
+        Some((name, r#macro))
+    }
+
+    fn expand(
+        &self,
+        name: Symbol,
+        r#macro: Object,
+        token: Token,
+        args: Vec<ExpressionNode>,
+    ) -> Result<ExpressionNode, EvalError> {
+        let Object::Macro(macro_token, params, body, env) = r#macro else {
+            unreachable!("only macros are defined in the env of the expander")
+        };
+        if self.depth == MAX_DEPTH {
+            return Err(eval_err(
+                format!(
+                    "`{name}` was expanded {MAX_DEPTH} times in a row, does it expand to itself?"
+                ),
+                token,
+            ));
+        }
+        let arity = Arity::of(&params);
+        if !arity.accepts(args.len()) {
+            return Err(arity_err(Some(name), arity, args.len(), token, macro_token));
+        }
+
+        let env = Env::extend(env);
+        bind_params(params, args.into_iter().map(Object::Quote).collect(), &env)?;
+        let body = Hygiene.fold_block(body);
+        let expansion = match eval_block(body.statements, env)? {
+            Object::Return(value) => *value,
+            value => value,
+        };
+        match expansion {
+            Object::Quote(expansion) => Ok(expansion),
+            value => Err(eval_err(
+                format!(
+                    "`{name}` gave back a value of type {}, but macros have to give back a quote",
+                    value.type_name()
+                ),
+                token,
+            )),
+        }
+    }
+}
+
+impl Folder for Expander {
+    fn fold_expression(&mut self, expr: ExpressionNode) -> ExpressionNode {
+        if self.error.is_some() {
+            return expr;
+        }
+        let ExpressionNode::FunctionCall(token, function, args) = expr else {
+            return fold::fold_expression(self, expr);
+        };
+        let Some((name, r#macro)) = self.lookup(&function) else {
+            return fold::fold_expression(
+                self,
+                ExpressionNode::FunctionCall(token, function, args),
+            );
+        };
+        // the errors point at the name of the macro
+        let ExpressionNode::Ident(name_ident) = *function else {
+            unreachable!()
+        };
+        match self.expand(name, r#macro, name_ident.token().clone(), args) {
+            // the expansion can have macro calls of its own
+            Ok(expansion) => {
+                self.depth += 1;
+                let expansion = self.fold_expression(expansion);
+                self.depth -= 1;
+                expansion
+            }
+            Err(err) => {
+                self.error = Some(err);
+                ExpressionNode::Block(Block::new(vec![]))
+            }
+        }
+    }
+}
+
+struct Unquoter {
+    env: Rc<RefCell<Env<Object>>>,
+    error: Option<EvalError>,
+}
+
+impl Folder for Unquoter {
+    fn fold_expression(&mut self, expr: ExpressionNode) -> ExpressionNode {
+        match expr {
+            ExpressionNode::FunctionCall(token, function, mut args)
+                if is_call_to(&function, "unquote") =>
+            {
+                let code = if args.len() == 1 {
+                    eval(Node::Expression(args.pop().unwrap()), self.env.clone())
+                        .and_then(|value| to_code(value, &token))
+                } else {
+                    Err(eval_err(
+                        format!("`unquote` takes 1 argument, got {}", args.len()),
+                        token,
+                    ))
+                };
+                code.unwrap_or_else(|err| {
+                    self.error.get_or_insert(err);
+                    ExpressionNode::Block(Block::new(vec![]))
+                })
+            }
+            expr => fold::fold_expression(self, expr),
+        }
+    }
+}
+
+// the code of a value, put at the place of `token`
+fn to_code(value: Object, token: &Token) -> Result<ExpressionNode, EvalError> {
+    let literal = |r#type| Token::new(r#type, token.span);
+    let negative = |code| ExpressionNode::UnaryOperator(literal(TokenType::Minus), Box::new(code));
+    let code = match value {
+        Object::Quote(code) => code,
+        Object::Int(int) => {
+            let digits = int.unsigned_abs().to_string().into();
+            let code = ExpressionNode::Int(literal(TokenType::Int(digits)));
+            if int < 0 {
+                negative(code)
+            } else {
+                code
+            }
+        }
+        Object::Float(float) => {
+            let digits = format!("{:?}", float.abs()).into();
+            let code = ExpressionNode::Float(literal(TokenType::Float(digits)));
+            if float.is_sign_negative() {
+                negative(code)
+            } else {
+                code
+            }
+        }
+        Object::Bool(true) => ExpressionNode::Bool(literal(TokenType::True)),
+        Object::Bool(false) => ExpressionNode::Bool(literal(TokenType::False)),
+        Object::Str(str) => {
+            ExpressionNode::Str(literal(TokenType::Str(string_literal(&str).into())))
+        }
+        Object::Array(elements) => ExpressionNode::Array(
+            literal(TokenType::Lbracket),
+            elements
+                .into_iter()
+                .map(|elem| to_code(elem, token))
+                .collect::<Result<_, _>>()?,
+        ),
+        Object::Tuple(elements) => ExpressionNode::Tuple(
+            literal(TokenType::Lparen),
+            elements
+                .into_iter()
+                .map(|elem| to_code(elem, token))
+                .collect::<Result<_, _>>()?,
+        ),
+        Object::Hash(entries) => ExpressionNode::Hash(
+            literal(TokenType::Lbrace),
+            entries
+                .into_iter()
+                .map(|(key, value)| Ok((to_code(key.into(), token)?, to_code(value, token)?)))
+                .collect::<Result<_, _>>()?,
+        ),
+        // refers to the struct type by its name, so it has to be in scope where the code ends up
+        Object::Struct(name, fields) => ExpressionNode::Struct(
+            literal(TokenType::Lbrace),
+            Ident::new(literal(TokenType::Ident(name))),
+            fields
+                .into_iter()
+                .map(|(field, value)| {
+                    let field = Ident::new(literal(TokenType::Ident(field)));
+                    Ok((field, to_code(value, token)?))
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        // `Name.Variant` or `Name.Variant(values)`, with the enum type in scope the same way
+        Object::Variant(name, variant, fields) => {
+            let variant = ExpressionNode::Field(
+                literal(TokenType::Dot),
+                Box::new(ExpressionNode::Ident(Ident::new(literal(
+                    TokenType::Ident(name),
+                )))),
+                Ident::new(literal(TokenType::Ident(variant))),
+            );
+            if fields.is_empty() {
+                variant
+            } else {
+                ExpressionNode::FunctionCall(
+                    literal(TokenType::Lparen),
+                    Box::new(variant),
+                    fields
+                        .into_iter()
+                        .map(|(_, value)| to_code(value, token))
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+        }
+        value => {
+            return Err(eval_err(
+                format!(
+                    "A value of type {} can't be turned into code",
+                    value.type_name()
+                ),
+                token.clone(),
+            ))
+        }
+    };
+    Ok(code)
+}
+
+// renames what the quotes in a macro body bind, every quote gets names of its own
+struct Hygiene;
+
+impl Folder for Hygiene {
+    fn fold_expression(&mut self, expr: ExpressionNode) -> ExpressionNode {
+        match expr {
+            ExpressionNode::FunctionCall(token, function, args)
+                if is_call_to(&function, "quote") =>
+            {
+                let mut rename = Rename::quote();
+                let args = args
+                    .into_iter()
+                    .map(|arg| rename.fold_expression(arg))
+                    .collect();
+                ExpressionNode::FunctionCall(token, function, args)
+            }
+            expr => fold::fold_expression(self, expr),
+        }
+    }
+}
+
+// gives the names bound in some code fresh symbols (see Symbol::fresh), in the scope of the binding:
+// a param in its function, what a pattern binds in its match arm
+// and a let (or a for) from where it is on, in the function (or the arm) it is in
+// the functions, structs and enums declared in a block are there from its start
+// the names bound outside of the code are left alone, and so are the fields of structs
+// and the variants of enums (and their fields), they aren't bindings
+struct Rename {
+    // the names bound in each scope around, innermost last, with their new names
+    scopes: Vec<HashMap<Symbol, Symbol>>,
+    // a quote in a macro body, the code in its unquotes runs in the macro
+    // and the names it binds at its top level are renamed too
+    // otherwise, a program, whose top level names are the globals and keep theirs
+    in_quote: bool,
+}
+
+impl Rename {
+    fn quote() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+            in_quote: true,
+        }
+    }
+
+    fn program() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+            in_quote: false,
+        }
+    }
+
+    // `ident` bound in the current scope, a name that is there already keeps the new name it has
+    // (the same as a let of a variable of the scope sets it)
+    fn bind(&mut self, ident: Ident) -> Ident {
+        let name = ident.get_symbol();
+        let keep = !self.in_quote && self.scopes.len() == 1;
+        let scope = self.scopes.last_mut().unwrap();
+        let renamed = *scope
+            .entry(name)
+            .or_insert_with(|| if keep { name } else { Symbol::fresh(name) });
+        with_name(ident, renamed)
+    }
+
+    fn in_scope<T>(&mut self, fold: impl FnOnce(&mut Self) -> T) -> T {
+        self.scopes.push(HashMap::new());
+        let folded = fold(self);
+        self.scopes.pop();
+        folded
+    }
+
+    fn fold_function(&mut self, params: Vec<Param>, body: Block) -> (Vec<Param>, Block) {
+        self.in_scope(|rename| {
+            let params = params
+                .into_iter()
+                .map(|param| rename.fold_param(param))
+                .collect();
+            (params, rename.fold_block(body))
+        })
+    }
+
+    fn fold_statements(&mut self, statements: Vec<StatementNode>) -> Vec<StatementNode> {
+        for stmt in &statements {
+            if let StatementNode::Function(_, name, ..)
+            | StatementNode::Struct(_, name, _)
+            | StatementNode::Enum(_, name, _) = stmt
+            {
+                self.bind(name.clone());
+            }
+        }
+        statements
+            .into_iter()
+            .map(|stmt| self.fold_statement(stmt))
+            .collect()
+    }
+}
+
+fn with_name(ident: Ident, name: Symbol) -> Ident {
+    Ident::new(Token {
+        r#type: TokenType::Ident(name),
+        ..ident.token().clone()
+    })
+}
+
+impl Folder for Rename {
+    fn fold_program(&mut self, program: Program) -> Program {
+        Program {
+            statements: self.fold_statements(program.statements),
+        }
+    }
+
+    fn fold_block(&mut self, block: Block) -> Block {
+        Block::new(self.fold_statements(block.statements))
+    }
+
+    fn fold_statement(&mut self, stmt: StatementNode) -> StatementNode {
+        match stmt {
+            // a function can call itself through the let it's bound by
+            StatementNode::Let(token, name, value @ ExpressionNode::Function(..)) => {
+                let name = self.bind(name);
+                StatementNode::Let(token, name, self.fold_expression(value))
+            }
+            StatementNode::Let(token, name, value) => {
+                let value = self.fold_expression(value);
+                StatementNode::Let(token, self.bind(name), value)
+            }
+            StatementNode::Destructure(token, pattern, value) => {
+                let value = self.fold_expression(value);
+                StatementNode::Destructure(token, Box::new(self.fold_pattern(*pattern)), value)
+            }
+            StatementNode::Function(token, name, params, body) => {
+                let name = self.fold_ident(name);
+                let (params, body) = self.fold_function(params, body);
+                StatementNode::Function(token, name, params, body)
+            }
+            StatementNode::Struct(token, name, fields) => {
+                StatementNode::Struct(token, self.fold_ident(name), fields)
+            }
+            StatementNode::Enum(token, name, variants) => {
+                StatementNode::Enum(token, self.fold_ident(name), variants)
+            }
+            stmt => fold::fold_statement(self, stmt),
+        }
+    }
+
+    // only called for the patterns that bind, in a destructuring let or a match arm
+    fn fold_pattern(&mut self, pattern: Pattern) -> Pattern {
+        match pattern {
+            Pattern::Binding(name) => Pattern::Binding(self.bind(name)),
+            Pattern::Variant(name, variant, patterns) => Pattern::Variant(
+                self.fold_ident(name),
+                variant,
+                patterns
+                    .into_iter()
+                    .map(|pattern| self.fold_pattern(pattern))
+                    .collect(),
+            ),
+            pattern => fold::fold_pattern(self, pattern),
+        }
+    }
+
+    // a default sees the params before it
+    fn fold_param(&mut self, param: Param) -> Param {
+        let default = param.default.map(|default| self.fold_expression(default));
+        Param {
+            rest: param.rest,
+            ident: self.bind(param.ident),
+            default,
+        }
+    }
+
+    fn fold_expression(&mut self, expr: ExpressionNode) -> ExpressionNode {
+        match expr {
+            ExpressionNode::Function(token, params, body) => {
+                let (params, body) = self.fold_function(params, body);
+                ExpressionNode::Function(token, params, body)
+            }
+            ExpressionNode::Macro(token, params, body) => {
+                let (params, body) = self.fold_function(params, body);
+                ExpressionNode::Macro(token, params, body)
+            }
+            ExpressionNode::Match(token, value, arms) => {
+                let value = self.fold_expression(*value);
+                let arms = arms
+                    .into_iter()
+                    .map(|arm| {
+                        self.in_scope(|rename| MatchArm {
+                            pattern: rename.fold_pattern(arm.pattern),
+                            guard: arm.guard.map(|guard| rename.fold_expression(guard)),
+                            body: rename.fold_expression(arm.body),
+                        })
+                    })
+                    .collect();
+                ExpressionNode::Match(token, Box::new(value), arms)
+            }
+            // the loop variable is bound in the enclosing scope, after the iterable
+            ExpressionNode::For(token, label, var, iterable, body) => {
+                let iterable = self.fold_expression(*iterable);
+                let var = self.bind(var);
+                let body = self.fold_block(body);
+                ExpressionNode::For(token, label, var, Box::new(iterable), body)
+            }
+            ExpressionNode::Struct(token, name, fields) => ExpressionNode::Struct(
+                token,
+                self.fold_ident(name),
+                fields
+                    .into_iter()
+                    .map(|(field, value)| (field, self.fold_expression(value)))
+                    .collect(),
+            ),
+            ExpressionNode::Field(token, value, field) => {
+                ExpressionNode::Field(token, Box::new(self.fold_expression(*value)), field)
+            }
+            // the code in an unquote runs in the macro, and can have quotes of its own
+            ExpressionNode::FunctionCall(token, function, args)
+                if self.in_quote && is_call_to(&function, "unquote") =>
+            {
+                let args = args
+                    .into_iter()
+                    .map(|arg| Hygiene.fold_expression(arg))
+                    .collect();
+                ExpressionNode::FunctionCall(token, function, args)
+            }
+            expr => fold::fold_expression(self, expr),
+        }
+    }
+
+    // a name used in the code, it refers to the innermost binding of it
+    fn fold_ident(&mut self, ident: Ident) -> Ident {
+        let name = ident.get_symbol();
+        match self.scopes.iter().rev().find_map(|scope| scope.get(&name)) {
+            Some(&renamed) => with_name(ident, renamed),
+            None => ident,
+        }
+    }
+}
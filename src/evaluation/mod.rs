@@ -1,14 +1,84 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
 use crate::ast::{Node, Program};
+use crate::diagnostics::Diagnostic;
 use crate::env::Env;
+use crate::module::{self, Module, ModuleError};
 use crate::object::Object;
+use crate::token::{symbol::Symbol, Span};
 
+mod consts;
 mod eval;
+mod macros;
+
+pub(crate) use macros::is_call_to;
 
 use eval::eval;
 // pub(crate) use env::Env;
 
 pub fn eval_program(program: Program) -> Object {
+    try_eval_program(program)
+        .unwrap_or_else(|err| Object::Error(format!("{} @ {:?}", err.message, err.primary.span)))
+}
+
+// same as eval_program, but errors come back as a Diagnostic
+// that can be rendered against the source of the program
+pub fn try_eval_program(program: Program) -> Result<Object, Diagnostic> {
+    module::check_standalone(&program)?;
+    let program = expand_macros(program)?;
+    first_error(check_consts(&program, &[]))?;
     let env = Env::new();
-    eval(Node::Program(program), env)
-        .unwrap_or_else(|err| Object::Error(format!("{} @ {:?}", err.issue, err.token.span)))
+    eval(Node::Program(program), env).map_err(Diagnostic::from)
+}
+
+// evaluates the modules the loader gave back in order, each in an env of its own
+// that starts out with what the modules it imports export
+// (their values once those modules ran, an importer can't reassign them for the exporter)
+// the value is the one of the last module, the entry
+pub fn eval_modules(modules: &[Module]) -> Result<Object, ModuleError> {
+    let mut envs: Vec<Rc<RefCell<Env<Object>>>> = vec![];
+    let mut value = Object::Null;
+    for module in modules {
+        let env = Env::new();
+        for &import in &module.imports {
+            for &name in &modules[import].exports {
+                let decl = modules[import].const_span(name);
+                if let Some(value) = envs[import].borrow().get(name) {
+                    // the first of two imports with the same name stays if it's a constant
+                    env.borrow_mut().declare(name, value, decl).ok();
+                }
+            }
+        }
+        let program = Program {
+            statements: module.program.statements.clone(),
+        };
+        value = expand_macros(program)
+            .and_then(|program| {
+                first_error(check_consts(&program, &module.imported_consts(modules)))?;
+                eval(Node::Program(program), env.clone()).map_err(Diagnostic::from)
+            })
+            .map_err(|err| module::locate(modules, err))?;
+        envs.push(env);
+    }
+    Ok(value)
+}
+
+// defining and expanding the macros is a pass of its own
+// that comes before the evaluator or the emitter
+// (the macros run in the evaluator either way)
+pub fn expand_macros(program: Program) -> Result<Program, Diagnostic> {
+    macros::expand_macros(program).map_err(Diagnostic::from)
+}
+
+// the assignments and declarations that a constant rules out (see consts.rs)
+// which are checked after the macros are expanded, as they can write to the constants too
+// `imported` are the constants the program starts out with
+pub(crate) fn check_consts(program: &Program, imported: &[(Symbol, Span)]) -> Vec<Diagnostic> {
+    consts::check_consts(program, imported)
+}
+
+// the evaluator stops at the first error of a pass
+fn first_error(errors: Vec<Diagnostic>) -> Result<(), Diagnostic> {
+    errors.into_iter().next().map_or(Ok(()), Err)
 }
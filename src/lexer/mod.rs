@@ -1,6 +1,9 @@
+use std::rc::Rc;
+
 use crate::token::{
+    symbol::Symbol,
     token_type::{tt_delimiters, tt_double_operators, tt_keywords, tt_single_operators, TokenType},
-    Token,
+    Token, Trivia, TriviaKind,
 };
 
 #[derive(Debug)]
@@ -20,6 +23,12 @@ pub struct Lexer {
     // 0 <= start_pos <= end_pos < len(input)
     start_pos: usize,
     end_pos: usize,
+
+    // lossless mode
+    // whitespace and comments are attached to the tokens that follow them
+    // and the Eof token (which carries the trailing trivia) is handed out as well
+    keep_trivia: bool,
+    eof_emitted: bool,
 }
 
 impl Lexer {
@@ -33,9 +42,28 @@ impl Lexer {
             input,
             start_pos: 0,
             end_pos: 0,
+            keep_trivia: false,
+            eof_emitted: false,
         }
     }
     // maybe another fn from_file?
+
+    // Switches the lexer to the lossless mode
+    // every token carries the whitespace and comments that precede it in `leading_trivia`
+    // and the last token produced is always Eof, carrying whatever trivia ends the input
+    // so concatenating the trivia and the text of every token gives back the input byte for byte
+    pub fn with_trivia(mut self) -> Self {
+        self.keep_trivia = true;
+        self
+    }
+
+    // Starts lexing at `pos` instead of the beginning of the input
+    // used to re-lex only a part of the input, `pos` should be the start of a token or trivia
+    pub(crate) fn starting_at(mut self, pos: usize) -> Self {
+        self.start_pos = pos;
+        self.end_pos = pos;
+        self
+    }
 }
 
 // methods for managing state
@@ -48,32 +76,118 @@ impl Lexer {
         self.end_pos += 1;
     }
 
-    fn step_until(&mut self, f: fn(u8) -> bool) -> Vec<u8> {
+    fn step_until(&mut self, f: fn(u8) -> bool) {
         // first condition is required to prevent infinite loops
         // for inputs that end with whitespace
         while self.end_pos < self.input.len() && f(self.input[self.end_pos]) {
             self.step_one();
         }
-        let lexeme = &self.input[self.start_pos..self.end_pos];
-        lexeme.to_owned()
     }
 
-    fn skip_ws(&mut self) {
-        // I don't plan to use whitespace information currently for my target language
-        // But if you ever need it you can grasp it from here, i guess
-        self.step_until(|c| c.is_ascii_whitespace());
+    // the lexeme we are currently looking at, borrowed straight from the input
+    fn lexeme(&self) -> &str {
+        // safe to unwrap as the lexemes asked for are all ascii
+        std::str::from_utf8(&self.input[self.start_pos..self.end_pos]).unwrap()
+    }
+
+    // skips whitespace and comments
+    // they are only recorded when the lexer is in the lossless mode
+    fn skip_trivia(&mut self) -> Vec<Trivia> {
+        let mut trivia = vec![];
+        loop {
+            self.start_pos = self.end_pos;
+            let kind = match (self.peek_at(0), self.peek_at(1)) {
+                (c, _) if c.is_ascii_whitespace() => {
+                    self.step_until(|c| c.is_ascii_whitespace());
+                    TriviaKind::Whitespace
+                }
+                (b'/', b'/') => {
+                    self.step_until(|c| c != b'\n' && c != 0);
+                    TriviaKind::Comment
+                }
+                _ => break,
+            };
+            if self.keep_trivia {
+                trivia.push(Trivia::new(kind, (self.start_pos, self.end_pos)));
+            }
+        }
+        trivia
+    }
+
+    fn ident(&mut self) -> &str {
+        self.step_until(|c| c.is_ascii_alphanumeric());
+        self.lexeme()
     }
 
-    fn ident(&mut self) -> String {
-        let v = self.step_until(|c| c.is_ascii_alphanumeric());
-        // safe to unwrap as they are all ascii alphanumeric chars
-        String::from_utf8(v).unwrap()
+    fn digits(&mut self) {
+        self.step_until(|c| c.is_ascii_digit() || c == b'_');
+    }
+
+    // a decimal literal, that turns into a float literal if it has a fraction and/or an exponent
+    // the `.` and the `e` are only consumed if they are followed by digits
+    // so that 1.foo and 1else don't end up swallowing the characters after the number
+    fn number(&mut self) -> TokenType {
+        self.digits();
+        let mut is_float = false;
+
+        if self.peek_at(0) == b'.' && self.peek_at(1).is_ascii_digit() {
+            self.end_pos += 1;
+            self.digits();
+            is_float = true;
+        }
+
+        let exponent_digits = match (self.peek_at(0), self.peek_at(1)) {
+            (b'e' | b'E', c) if c.is_ascii_digit() => Some(1),
+            (b'e' | b'E', b'+' | b'-') if self.peek_at(2).is_ascii_digit() => Some(2),
+            _ => None,
+        };
+        if let Some(skip) = exponent_digits {
+            self.end_pos += skip;
+            self.digits();
+            is_float = true;
+        }
+
+        let s = Rc::from(self.lexeme());
+        if is_float {
+            TokenType::Float(s)
+        } else {
+            TokenType::Int(s)
+        }
     }
 
-    fn digits(&mut self) -> String {
-        let v = self.step_until(|c| c.is_ascii_digit());
-        // safe to unwrap as they are all ascii alphanumeric chars
-        String::from_utf8(v).unwrap()
+    // byte at the given distance from end_pos
+    // the input is padded with 0s, so anything past the end reads as 0 as well
+    fn peek_at(&self, offset: usize) -> u8 {
+        self.input.get(self.end_pos + offset).copied().unwrap_or(0)
+    }
+
+    // a string literal, quotes included
+    // escapes are only stepped over here (so that \" doesn't end the literal)
+    // making sense of them is left to the parser, the same as with the number literals
+    // an unterminated literal runs till the end of the input
+    fn string(&mut self) -> Rc<str> {
+        self.end_pos += 1;
+        loop {
+            match (self.peek_at(0), self.peek_at(1)) {
+                (b'"', _) => {
+                    self.end_pos += 1;
+                    break;
+                }
+                (0, _) => break,
+                (b'\\', c) if c != 0 => self.end_pos += 2,
+                _ => self.end_pos += 1,
+            }
+        }
+        Rc::from(self.lexeme())
+    }
+
+    // 0x, 0b and 0o literals
+    // all the alphanumeric chars are grabbed here and the parser validates them against the radix
+    // so that something like 0b102 is reported as a bad literal instead of being split into 2 tokens
+    fn radix_digits(&mut self) -> Rc<str> {
+        self.end_pos += 2;
+        self.step_until(|c| c.is_ascii_alphanumeric() || c == b'_');
+        Rc::from(self.lexeme())
     }
 }
 
@@ -83,7 +197,13 @@ impl Lexer {
     }
 
     fn next_token(&mut self) -> Token {
-        self.skip_ws();
+        let trivia = self.skip_trivia();
+        let mut token = self.next_lexeme();
+        token.leading_trivia = trivia;
+        token
+    }
+
+    fn next_lexeme(&mut self) -> Token {
         self.start_pos = self.end_pos;
         // invariance: start_pos is pointing to the beginning of a new lexeme
         // when we reach the match statement
@@ -93,16 +213,35 @@ impl Lexer {
             ('\x00', _) => self.create_token(TokenType::Eof),
             ('a'..='z' | 'A'..='Z', _) => {
                 let s = self.ident();
-                if let Some(kw) = tt_keywords(s.as_str()) {
+                if let Some(kw) = tt_keywords(s) {
                     self.create_token(kw)
                 } else {
+                    let s = Symbol::intern(s);
                     self.create_token(TokenType::Ident(s))
                 }
             }
-            ('0'..='9', _) => {
-                let s = self.digits();
+            ('0', 'x' | 'b' | 'o') => {
+                let s = self.radix_digits();
                 self.create_token(TokenType::Int(s))
             }
+            ('0'..='9', _) => {
+                let tt = self.number();
+                self.create_token(tt)
+            }
+            ('"', _) => {
+                let s = self.string();
+                self.create_token(TokenType::Str(s))
+            }
+            // the only operator that's 3 chars long
+            ('.', '.') if self.input[self.start_pos + 2] == b'.' => {
+                self.end_pos += 3;
+                self.create_token(TokenType::Ellipsis)
+            }
+            ('\'', 'a'..='z' | 'A'..='Z') => {
+                self.end_pos += 1;
+                let s = Symbol::intern(self.ident());
+                self.create_token(TokenType::Label(s))
+            }
             (c0, c1) => {
                 if let Some(tt) = tt_double_operators(c0, c1) {
                     self.end_pos += 2;
@@ -110,9 +249,23 @@ impl Lexer {
                 } else if let Some(tt) = tt_single_operators(c0).or(tt_delimiters(c0)) {
                     self.end_pos += 1;
                     self.create_token(tt)
-                } else {
+                } else if c0.is_ascii() {
                     self.end_pos += 1;
                     self.create_token(TokenType::Illegal(c0))
+                } else {
+                    // the input came from a String, so this is the start of a valid utf-8 sequence
+                    // the whole char is consumed to keep the spans on char boundaries
+                    let len = match self.input[self.start_pos] {
+                        0xf0.. => 4,
+                        0xe0.. => 3,
+                        _ => 2,
+                    };
+                    self.end_pos += len;
+                    let c = std::str::from_utf8(&self.input[self.start_pos..self.end_pos])
+                        .ok()
+                        .and_then(|s| s.chars().next())
+                        .unwrap_or(char::REPLACEMENT_CHARACTER);
+                    self.create_token(TokenType::Illegal(c))
                 }
             }
         }
@@ -125,8 +278,15 @@ impl Lexer {
 impl Iterator for Lexer {
     type Item = Token;
     fn next(&mut self) -> Option<Self::Item> {
+        if self.eof_emitted {
+            return None;
+        }
         let token = self.next_token();
         match token.r#type {
+            TokenType::Eof if self.keep_trivia => {
+                self.eof_emitted = true;
+                Some(token)
+            }
             TokenType::Eof => None,
             _ => Some(token),
         }
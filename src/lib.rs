@@ -1,14 +1,18 @@
 #![feature(let_chains)]
 #![feature(box_patterns)]
 
-mod ast;
+pub mod ast;
 mod bytecode;
+pub mod diagnostics;
 pub mod emitter;
 mod env;
 pub mod evaluation;
 pub mod lexer;
+pub mod module;
 mod object;
 pub mod parser;
-mod token;
+pub mod printer;
+pub mod token;
+pub mod vm;
 // mod type_inference;
 // mod typed_ast;
@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::ast::{fold::Folder, statement::StatementNode, Program};
+use crate::diagnostics::{Diagnostic, Label, Severity};
+use crate::lexer::Lexer;
+use crate::parser::{incremental::ShiftSpans, Parser};
+use crate::token::{
+    shift_span, string_literal_value, symbol::Symbol, token_type::TokenType, Pos, Span, Token,
+};
+
+// Programs that are spread over files
+//
+// every file is a module, it brings in what other modules export with `import "path/to/mod";`
+// and exports the lets, consts, functions, struct and enum types it declares with `export let`,
+// `export const`, `export fn`, `export struct` and `export enum`
+// (a const stays one in the modules that import it)
+// the path of an import is looked up next to the file it's in first,
+// then in the directories of the search path, in order (the `.si` can be left out)
+//
+// every module is loaded once, no matter how many modules import it
+// and they come back in an order where every module comes after the ones it imports
+// so they can be evaluated or compiled one after the other, each with globals of its own
+//
+// a function can be called from another module than the one it's in
+// so the spans of every module are moved past the ones of the modules loaded before it
+// that way a span alone tells which module it points into (see `locate`)
+
+pub const EXTENSION: &str = "si";
+
+#[derive(Debug)]
+pub struct Module {
+    pub path: PathBuf,
+    pub source: String,
+    // where the spans of the program start, they are offsets into the source after this
+    pub offset: Pos,
+    pub program: Program,
+    // the modules it imports, as indices into the modules the loader gave back
+    pub imports: Vec<usize>,
+    // the names of the lets, consts, functions, struct and enum types it exports
+    pub exports: Vec<Symbol>,
+    // the exports that are constants, with the span of the name they are declared with
+    pub consts: Vec<(Symbol, Span)>,
+}
+
+impl Module {
+    // where `name` is declared, if it's one of the constants it exports
+    pub fn const_span(&self, name: Symbol) -> Option<Span> {
+        self.consts
+            .iter()
+            .find(|&&(constant, _)| constant == name)
+            .map(|&(_, span)| span)
+    }
+
+    // the constants exported by the modules it imports, which it starts out with
+    pub fn imported_consts(&self, modules: &[Module]) -> Vec<(Symbol, Span)> {
+        self.imports
+            .iter()
+            .flat_map(|&import| modules[import].consts.iter().copied())
+            .collect()
+    }
+
+    fn contains(&self, span: Span) -> bool {
+        (self.offset..=self.offset + self.source.len()).contains(&span.0)
+    }
+
+    // the diagnostic, with its spans moved back to be offsets into the source
+    fn file_diagnostic(&self, diagnostic: Diagnostic) -> FileDiagnostic {
+        let local = |label: Label| Label {
+            span: shift_span(label.span, -(self.offset as isize)),
+            ..label
+        };
+        FileDiagnostic {
+            path: self.path.clone(),
+            source: self.source.clone(),
+            diagnostic: Diagnostic {
+                primary: local(diagnostic.primary),
+                secondary: diagnostic.secondary.into_iter().map(local).collect(),
+                ..diagnostic
+            },
+        }
+    }
+}
+
+// the diagnostics found while loading the modules, or running them
+#[derive(Debug)]
+pub struct ModuleError {
+    pub diagnostics: Vec<FileDiagnostic>,
+}
+
+// a diagnostic, along with the source of the file it points into
+#[derive(Debug)]
+pub struct FileDiagnostic {
+    pub path: PathBuf,
+    pub source: String,
+    pub diagnostic: Diagnostic,
+}
+
+impl ModuleError {
+    fn new(path: &Path, source: &str, diagnostics: Vec<Diagnostic>) -> Self {
+        let diagnostics = diagnostics
+            .into_iter()
+            .map(|diagnostic| FileDiagnostic {
+                path: path.to_owned(),
+                source: source.to_owned(),
+                diagnostic,
+            })
+            .collect();
+        Self { diagnostics }
+    }
+
+    pub fn render(&self) -> String {
+        self.diagnostics
+            .iter()
+            .map(|file| {
+                let filename = file.path.display().to_string();
+                file.diagnostic.render(&file.source, &filename)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// the file of a diagnostic from evaluating or compiling the modules
+// a label that points into another file than the diagnostic becomes a note of its own there
+pub fn locate(modules: &[Module], mut diagnostic: Diagnostic) -> ModuleError {
+    let find = |span| modules.iter().find(|module| module.contains(span));
+    // the entry, for a span that isn't in any module (which shouldn't happen)
+    let module = find(diagnostic.primary.span)
+        .or(modules.last())
+        .expect("the loader gives back the entry at least");
+    let (here, elsewhere) = std::mem::take(&mut diagnostic.secondary)
+        .into_iter()
+        .partition(|label| module.contains(label.span));
+    diagnostic.secondary = here;
+
+    let mut diagnostics = vec![module.file_diagnostic(diagnostic)];
+    for label in elsewhere {
+        if let Some(module) = find(label.span) {
+            let note = Diagnostic::new(Severity::Note, label.message, label.span);
+            diagnostics.push(module.file_diagnostic(note));
+        }
+    }
+    ModuleError { diagnostics }
+}
+
+#[derive(Debug, Default)]
+pub struct Loader {
+    search_path: Vec<PathBuf>,
+    modules: Vec<Module>,
+    // where the modules that are loaded already are in `modules`
+    loaded: HashMap<PathBuf, usize>,
+    // the modules that are being loaded, each of them imports the next one
+    loading: Vec<PathBuf>,
+    // where the spans of the next module start
+    offset: Pos,
+}
+
+impl Loader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_search_path(mut self, dirs: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.search_path.extend(dirs);
+        self
+    }
+
+    // `entry` and all the modules it imports, directly or not, with `entry` last
+    pub fn load(mut self, entry: &Path) -> Result<Vec<Module>, ModuleError> {
+        let read = entry
+            .canonicalize()
+            .and_then(|path| Ok((std::fs::read_to_string(&path)?, path)));
+        let (source, path) = read.map_err(|err| {
+            let diagnostic = Diagnostic::error(format!("Couldn't read it: {err}"), (0, 0));
+            ModuleError::new(entry, "", vec![diagnostic])
+        })?;
+        self.load_module(path, source)?;
+        Ok(self.modules)
+    }
+
+    fn load_module(&mut self, path: PathBuf, source: String) -> Result<usize, ModuleError> {
+        let error = |diagnostics| ModuleError::new(&path, &source, diagnostics);
+        let (program, errors) = Parser::new(Lexer::from_string(source.clone())).parse_program();
+        if !errors.is_empty() {
+            return Err(error(errors.iter().map(|err| err.diagnostic()).collect()));
+        }
+
+        self.loading.push(path.clone());
+        let mut imports = vec![];
+        let mut exports = vec![];
+        let mut consts = vec![];
+        for stmt in &program.statements {
+            match stmt {
+                StatementNode::Import(_, token) => {
+                    let import = self.import(&path, token).map_err(|err| match err {
+                        ImportError::Here(diagnostic) => error(vec![diagnostic]),
+                        ImportError::There(err) => err,
+                    })?;
+                    imports.push(import);
+                }
+                StatementNode::Export(_, stmt) => {
+                    let names = match &**stmt {
+                        StatementNode::Let(_, name, _)
+                        | StatementNode::Function(_, name, ..)
+                        | StatementNode::Struct(_, name, _)
+                        | StatementNode::Enum(_, name, _) => vec![name],
+                        StatementNode::Destructure(_, pattern, _) => pattern.bindings(),
+                        _ => unreachable!(
+                            "the parser only lets lets, consts, fns, structs and enums be exported"
+                        ),
+                    };
+                    for name in names {
+                        exports.push(name.get_symbol());
+                        if stmt.is_const() {
+                            consts.push((name.get_symbol(), name.token().span));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.loading.pop();
+
+        let offset = self.offset;
+        self.offset += source.len() + 1;
+        self.modules.push(Module {
+            path: path.clone(),
+            source,
+            offset,
+            program: ShiftSpans(offset as isize).fold_program(program),
+            imports,
+            exports,
+            consts: consts
+                .into_iter()
+                .map(|(name, span)| (name, shift_span(span, offset as isize)))
+                .collect(),
+        });
+        self.loaded.insert(path, self.modules.len() - 1);
+        Ok(self.modules.len() - 1)
+    }
+
+    // the index of the module `token` (the path in an import in `importer`) refers to
+    fn import(&mut self, importer: &Path, token: &Token) -> Result<usize, ImportError> {
+        let path = self.resolve(importer, token)?;
+        if let Some(&index) = self.loaded.get(&path) {
+            return Ok(index);
+        }
+        if let Some(start) = self.loading.iter().position(|loading| *loading == path) {
+            let cycle: Vec<_> = self.loading[start..]
+                .iter()
+                .chain([&path])
+                .map(|path| format!("`{}`", path.display()))
+                .collect();
+            let diagnostic =
+                Diagnostic::error("Modules can't import each other in a cycle", token.span)
+                    .with_primary_message(cycle.join(" imports "));
+            return Err(ImportError::Here(diagnostic));
+        }
+        let source = std::fs::read_to_string(&path).map_err(|err| {
+            let issue = format!("Couldn't read `{}`: {err}", path.display());
+            ImportError::Here(Diagnostic::error(issue, token.span))
+        })?;
+        self.load_module(path, source).map_err(ImportError::There)
+    }
+
+    fn resolve(&self, importer: &Path, token: &Token) -> Result<PathBuf, ImportError> {
+        let TokenType::Str(lexeme) = &token.r#type else {
+            unreachable!("the parser only takes a string after `import`")
+        };
+        let name = string_literal_value(lexeme)
+            .map_err(|err| ImportError::Here(Diagnostic::error(err, token.span)))?;
+        let mut file = PathBuf::from(&name);
+        if file
+            .extension()
+            .is_none_or(|extension| extension != EXTENSION)
+        {
+            file = PathBuf::from(format!("{name}.{EXTENSION}"));
+        }
+
+        // the importer was canonicalized, so it has a parent
+        let dir = importer.parent().unwrap();
+        std::iter::once(dir)
+            .chain(self.search_path.iter().map(PathBuf::as_path))
+            .map(|dir| dir.join(&file))
+            .find(|candidate| candidate.is_file())
+            .and_then(|found| found.canonicalize().ok())
+            .ok_or_else(|| {
+                let places = match self.search_path.len() {
+                    0 => "next to this file",
+                    _ => "next to this file and in the search path",
+                };
+                let diagnostic =
+                    Diagnostic::error(format!("Can't find the module `{name}`"), token.span)
+                        .with_primary_message(format!("there's no `{}` {places}", file.display()));
+                ImportError::Here(diagnostic)
+            })
+    }
+}
+
+// an import that went wrong in the module it's in, or in the module it imports
+enum ImportError {
+    Here(Diagnostic),
+    There(ModuleError),
+}
+
+// a program that isn't loaded from a file doesn't have anything to import from
+pub(crate) fn check_standalone(program: &Program) -> Result<(), Diagnostic> {
+    match program
+        .statements
+        .iter()
+        .find(|stmt| matches!(stmt, StatementNode::Import(..)))
+    {
+        Some(StatementNode::Import(token, _)) => Err(Diagnostic::error(
+            "Only the modules loaded from files can import (see module::Loader)",
+            token.span,
+        )),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,145 @@
+use std::fmt;
+
+use super::Object;
+
+// Functions that are always in scope, unless a binding with the same name shadows them
+//
+// The evaluator looks them up by name, the emitter refers to them by their index in BUILTINS
+// so new ones go at the end
+#[derive(Clone, Copy)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub func: fn(Vec<Object>) -> Result<Object, String>,
+}
+
+impl fmt::Debug for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "builtin {}", self.name)
+    }
+}
+
+pub(crate) const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "len",
+        func: len,
+    },
+    Builtin {
+        name: "first",
+        func: first,
+    },
+    Builtin {
+        name: "last",
+        func: last,
+    },
+    Builtin {
+        name: "rest",
+        func: rest,
+    },
+    Builtin {
+        name: "push",
+        func: push,
+    },
+    Builtin {
+        name: "keys",
+        func: keys,
+    },
+    Builtin {
+        name: "values",
+        func: values,
+    },
+    Builtin {
+        name: "contains",
+        func: contains,
+    },
+];
+
+pub(crate) fn lookup(name: &str) -> Option<(usize, Builtin)> {
+    BUILTINS
+        .iter()
+        .enumerate()
+        .find(|(_, builtin)| builtin.name == name)
+        .map(|(i, builtin)| (i, *builtin))
+}
+
+// the arguments of `name`, if there are exactly N of them
+fn args<const N: usize>(name: &str, args: Vec<Object>) -> Result<[Object; N], String> {
+    let got = args.len();
+    args.try_into().map_err(|_| {
+        let plural = if N == 1 { "" } else { "s" };
+        format!("`{name}` takes {N} argument{plural}, got {got}")
+    })
+}
+
+fn unsupported(name: &str, arg: &Object) -> String {
+    format!("`{name}` is not supported for {}", arg.type_name())
+}
+
+fn len(arguments: Vec<Object>) -> Result<Object, String> {
+    match args("len", arguments)? {
+        [Object::Array(elements) | Object::Tuple(elements)] => {
+            Ok(Object::Int(elements.len() as isize))
+        }
+        [Object::Hash(entries)] => Ok(Object::Int(entries.len() as isize)),
+        // in chars, not bytes
+        [Object::Str(str)] => Ok(Object::Int(str.chars().count() as isize)),
+        [arg] => Err(unsupported("len", &arg)),
+    }
+}
+
+// first, last and rest give back null for an empty array
+fn first(arguments: Vec<Object>) -> Result<Object, String> {
+    match args("first", arguments)? {
+        [Object::Array(elements)] => Ok(elements.into_iter().next().unwrap_or(Object::Null)),
+        [arg] => Err(unsupported("first", &arg)),
+    }
+}
+
+fn last(arguments: Vec<Object>) -> Result<Object, String> {
+    match args("last", arguments)? {
+        [Object::Array(elements)] => Ok(elements.into_iter().last().unwrap_or(Object::Null)),
+        [arg] => Err(unsupported("last", &arg)),
+    }
+}
+
+fn rest(arguments: Vec<Object>) -> Result<Object, String> {
+    match args("rest", arguments)? {
+        [Object::Array(elements)] if elements.is_empty() => Ok(Object::Null),
+        [Object::Array(elements)] => Ok(Object::Array(elements.into_iter().skip(1).collect())),
+        [arg] => Err(unsupported("rest", &arg)),
+    }
+}
+
+// arrays are values, so push gives back a new array and leaves the old one alone
+fn push(arguments: Vec<Object>) -> Result<Object, String> {
+    match args("push", arguments)? {
+        [Object::Array(mut elements), elem] => {
+            elements.push(elem);
+            Ok(Object::Array(elements))
+        }
+        [arg, _] => Err(unsupported("push", &arg)),
+    }
+}
+
+// keys and values come out in the order of the keys
+fn keys(arguments: Vec<Object>) -> Result<Object, String> {
+    match args("keys", arguments)? {
+        [Object::Hash(entries)] => Ok(Object::Array(
+            entries.into_keys().map(Object::from).collect(),
+        )),
+        [arg] => Err(unsupported("keys", &arg)),
+    }
+}
+
+fn values(arguments: Vec<Object>) -> Result<Object, String> {
+    match args("values", arguments)? {
+        [Object::Hash(entries)] => Ok(Object::Array(entries.into_values().collect())),
+        [arg] => Err(unsupported("values", &arg)),
+    }
+}
+
+fn contains(arguments: Vec<Object>) -> Result<Object, String> {
+    match args("contains", arguments)? {
+        [Object::Hash(entries), key] => Ok(Object::Bool(entries.contains_key(&key.hash_key()?))),
+        [arg, _] => Err(unsupported("contains", &arg)),
+    }
+}
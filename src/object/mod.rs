@@ -1,76 +1,532 @@
 use crate::ast;
+use crate::ast::expression::ExpressionNode;
 use crate::env::Env;
-use crate::token::Token;
+use crate::token::{
+    int_literal_value, string_literal_value, symbol::Symbol, token_type::TokenType, Token,
+};
 use std::cell::RefCell;
-use std::ops::{Add, Div, Mul, Sub};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Rem, Shl, Shr, Sub};
 use std::rc::Rc;
 
+pub(crate) mod builtins;
+
+pub use builtins::Builtin;
+
 #[derive(Debug, Clone)]
 pub enum Object {
     Error(String),
     Return(Box<Object>),
+    // break and continue on their way out to the loop they belong to (None means the innermost one)
+    Break(Option<Symbol>),
+    Continue(Option<Symbol>),
     Int(isize),
+    Float(f64),
     Bool(bool),
+    Str(String),
+    // the name of a function that was declared with one comes first, for the error messages
     Function(
+        Option<Symbol>,
+        Token,
+        Vec<ast::expression::Param>,
+        ast::expression::Block,
+        Rc<RefCell<Env<Object>>>,
+    ),
+    // instructions, number of locals (the params included), number of params,
+    // the arguments it takes, name
+    CompiledFunction(Vec<u8>, u16, u8, Arity, Option<Symbol>),
+    // a CompiledFunction, with the variables it captured
+    // which it shares with the functions they belong to (see vm::Vm)
+    Closure(Rc<Object>, Vec<Rc<RefCell<Object>>>),
+    Builtin(Builtin),
+    // the code given to `quote`, that macros take apart and put together
+    Quote(ExpressionNode),
+    // only around while the macros are expanded
+    Macro(
         Token,
-        Vec<ast::expression::Ident>,
+        Vec<ast::expression::Param>,
         ast::expression::Block,
         Rc<RefCell<Env<Object>>>,
     ),
-    CompiledFunction(Vec<u8>),
+    Array(Vec<Object>),
+    // like an array, but with a length that is part of what it is
+    // nothing can be added to it, so it's what functions give back several values in
+    Tuple(Vec<Object>),
+    // ordered by key, so that iterating over a hash always gives the same order
+    Hash(BTreeMap<Hashable, Object>),
+    // what `struct Name { field, other }` declares, the name and the fields
+    StructType(Symbol, Vec<Symbol>),
+    // a value of a struct type, with its fields in the order they are declared
+    Struct(Symbol, Vec<(Symbol, Object)>),
+    // what `enum Name { Variant(field), Other }` declares, the name and the variants (with fields)
+    EnumType(Symbol, Vec<(Symbol, Vec<Symbol>)>),
+    // a value of an enum type: the type, the variant and its fields, in the order they are declared
+    Variant(Symbol, Symbol, Vec<(Symbol, Object)>),
+    // `Name.Variant` for a variant with fields, which makes a value of it out of the arguments
+    Constructor(Symbol, Symbol, Vec<Symbol>),
     Null,
 }
 
+// The objects that can be used as hash keys
+// floats are left out, NaN isn't even equal to itself
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Hashable {
+    Int(isize),
+    Bool(bool),
+    Str(String),
+}
+
+impl From<Hashable> for Object {
+    fn from(value: Hashable) -> Self {
+        match value {
+            Hashable::Int(int) => Object::Int(int),
+            Hashable::Bool(bool) => Object::Bool(bool),
+            Hashable::Str(str) => Object::Str(str),
+        }
+    }
+}
+
+impl Hashable {
+    // the value of a literal pattern (see ast::pattern::Pattern::Literal)
+    // the parser has already rejected the invalid literals
+    pub(crate) fn from_literal(literal: &ExpressionNode) -> Hashable {
+        match literal {
+            ExpressionNode::Int(Token {
+                r#type: TokenType::Int(int),
+                ..
+            }) => Hashable::Int(int_literal_value(int).unwrap()),
+            ExpressionNode::UnaryOperator(_, int) => match Self::from_literal(int) {
+                Hashable::Int(int) => Hashable::Int(-int),
+                _ => unreachable!("only ints can be negated in a pattern"),
+            },
+            ExpressionNode::Str(Token {
+                r#type: TokenType::Str(str),
+                ..
+            }) => Hashable::Str(string_literal_value(str).unwrap()),
+            ExpressionNode::Bool(token) => Hashable::Bool(token.r#type == TokenType::True),
+            _ => unreachable!("not a literal pattern"),
+        }
+    }
+}
+
+impl fmt::Display for Hashable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Hashable::Int(int) => write!(f, "{int}"),
+            Hashable::Bool(bool) => write!(f, "{bool}"),
+            Hashable::Str(str) => write!(f, "{str:?}"),
+        }
+    }
+}
+
+// How many arguments a function can be called with
+// `max` is None when it has a rest param, that takes any number of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Arity {
+    pub(crate) fn of(params: &[ast::expression::Param]) -> Self {
+        let rest = params.iter().any(|param| param.rest.is_some());
+        let named = params.len() - rest as usize;
+        Self {
+            min: params
+                .iter()
+                .filter(|param| param.default.is_none())
+                .count()
+                - rest as usize,
+            max: (!rest).then_some(named),
+        }
+    }
+
+    pub fn accepts(&self, args: usize) -> bool {
+        self.min <= args && self.max.is_none_or(|max| args <= max)
+    }
+}
+
+// `2`, `1 to 3` or `at least 1`
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{max}"),
+            Some(max) => write!(f, "{} to {max}", self.min),
+            None => write!(f, "at least {}", self.min),
+        }
+    }
+}
+
+// ints stay ints when combined with ints
+// as soon as a float is involved, the int operand is promoted and the result is a float
+fn numeric_op(
+    lhs: Object,
+    rhs: Object,
+    int_op: fn(isize, isize) -> Result<isize, String>,
+    float_op: fn(f64, f64) -> f64,
+    err: &str,
+) -> Result<Object, String> {
+    match (lhs, rhs) {
+        (Object::Int(lhs), Object::Int(rhs)) => Ok(Object::Int(int_op(lhs, rhs)?)),
+        (Object::Int(lhs), Object::Float(rhs)) => Ok(Object::Float(float_op(lhs as f64, rhs))),
+        (Object::Float(lhs), Object::Int(rhs)) => Ok(Object::Float(float_op(lhs, rhs as f64))),
+        (Object::Float(lhs), Object::Float(rhs)) => Ok(Object::Float(float_op(lhs, rhs))),
+        _ => Err(err.into()),
+    }
+}
+
 impl Add for Object {
     type Output = Result<Object, String>;
     fn add(self, rhs: Self) -> Self::Output {
-        match (self, rhs) {
-            (Object::Int(lhs), Object::Int(rhs)) => Ok(Object::Int(lhs + rhs)),
-            // (Object::Bool(lhs), Object::Bool(rhs)) => Object::Int(lhs as isize + rhs as isize),
-            _ => Err("Addition requires that both operands are integers".into()),
-        }
+        numeric_op(
+            self,
+            rhs,
+            |lhs, rhs| {
+                lhs.checked_add(rhs)
+                    .ok_or(format!("{lhs} + {rhs} is too big for an int"))
+            },
+            |lhs, rhs| lhs + rhs,
+            "Addition requires that both operands are numbers",
+        )
     }
 }
 impl Sub for Object {
     type Output = Result<Object, String>;
     fn sub(self, rhs: Self) -> Self::Output {
-        match (self, rhs) {
-            (Object::Int(lhs), Object::Int(rhs)) => Ok(Object::Int(lhs - rhs)),
-            _ => Err("Subtraction requires that both operands are integers".into()),
-        }
+        numeric_op(
+            self,
+            rhs,
+            |lhs, rhs| {
+                lhs.checked_sub(rhs)
+                    .ok_or(format!("{lhs} - {rhs} is too big for an int"))
+            },
+            |lhs, rhs| lhs - rhs,
+            "Subtraction requires that both operands are numbers",
+        )
     }
 }
 
 impl Mul for Object {
     type Output = Result<Object, String>;
     fn mul(self, rhs: Self) -> Self::Output {
-        match (self, rhs) {
-            (Object::Int(lhs), Object::Int(rhs)) => Ok(Object::Int(lhs * rhs)),
-            _ => Err("Multiplication requires that both operands are integers".into()),
-        }
+        numeric_op(
+            self,
+            rhs,
+            |lhs, rhs| {
+                lhs.checked_mul(rhs)
+                    .ok_or(format!("{lhs} * {rhs} is too big for an int"))
+            },
+            |lhs, rhs| lhs * rhs,
+            "Multiplication requires that both operands are numbers",
+        )
     }
 }
 
 impl Div for Object {
     type Output = Result<Object, String>;
     fn div(self, rhs: Self) -> Self::Output {
-        match (self, rhs) {
-            (Object::Int(lhs), Object::Int(rhs)) => Ok(Object::Int(lhs / rhs)),
-            _ => Err("Division requires that both operands are integers".into()),
-        }
+        // int division truncates, float division follows IEEE 754 (so x / 0.0 is inf)
+        numeric_op(
+            self,
+            rhs,
+            |lhs, rhs| lhs.checked_div(rhs).ok_or("Division by zero".into()),
+            |lhs, rhs| lhs / rhs,
+            "Division requires that both operands are numbers",
+        )
+    }
+}
+
+impl Rem for Object {
+    type Output = Result<Object, String>;
+    fn rem(self, rhs: Self) -> Self::Output {
+        // the result takes the sign of the left operand, -7 % 3 is -1
+        numeric_op(
+            self,
+            rhs,
+            |lhs, rhs| lhs.checked_rem(rhs).ok_or("Modulo by zero".into()),
+            |lhs, rhs| lhs % rhs,
+            "Modulo requires that both operands are numbers",
+        )
+    }
+}
+
+// &, | and ^ work bit by bit on ints, and as the (non short-circuiting) logical operators on bools
+fn bitwise_op(
+    lhs: Object,
+    rhs: Object,
+    int_op: fn(isize, isize) -> isize,
+    bool_op: fn(bool, bool) -> bool,
+    operator: &str,
+) -> Result<Object, String> {
+    match (lhs, rhs) {
+        (Object::Int(lhs), Object::Int(rhs)) => Ok(Object::Int(int_op(lhs, rhs))),
+        (Object::Bool(lhs), Object::Bool(rhs)) => Ok(Object::Bool(bool_op(lhs, rhs))),
+        (lhs, rhs) => Err(format!(
+            "{operator} requires two ints or two bools, got {} and {}",
+            lhs.type_name(),
+            rhs.type_name()
+        )),
+    }
+}
+
+impl BitAnd for Object {
+    type Output = Result<Object, String>;
+    fn bitand(self, rhs: Self) -> Self::Output {
+        bitwise_op(self, rhs, |lhs, rhs| lhs & rhs, |lhs, rhs| lhs & rhs, "&")
+    }
+}
+
+impl BitOr for Object {
+    type Output = Result<Object, String>;
+    fn bitor(self, rhs: Self) -> Self::Output {
+        bitwise_op(self, rhs, |lhs, rhs| lhs | rhs, |lhs, rhs| lhs | rhs, "|")
+    }
+}
+
+impl BitXor for Object {
+    type Output = Result<Object, String>;
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        bitwise_op(self, rhs, |lhs, rhs| lhs ^ rhs, |lhs, rhs| lhs ^ rhs, "^")
+    }
+}
+
+// shifting by a negative amount or by the width of an int (or more) is an error, not a wrap around
+// >> keeps the sign
+fn shift_op(
+    lhs: Object,
+    rhs: Object,
+    op: fn(isize, u32) -> Option<isize>,
+    operator: &str,
+) -> Result<Object, String> {
+    match (lhs, rhs) {
+        (Object::Int(lhs), Object::Int(rhs)) => u32::try_from(rhs)
+            .ok()
+            .and_then(|rhs| op(lhs, rhs))
+            .map(Object::Int)
+            .ok_or(format!(
+                "Can't shift by {rhs}, it has to be between 0 and {}",
+                isize::BITS - 1
+            )),
+        (lhs, rhs) => Err(format!(
+            "{operator} requires that both operands are ints, got {} and {}",
+            lhs.type_name(),
+            rhs.type_name()
+        )),
+    }
+}
+
+impl Shl for Object {
+    type Output = Result<Object, String>;
+    fn shl(self, rhs: Self) -> Self::Output {
+        shift_op(self, rhs, isize::checked_shl, "<<")
+    }
+}
+
+impl Shr for Object {
+    type Output = Result<Object, String>;
+    fn shr(self, rhs: Self) -> Self::Output {
+        shift_op(self, rhs, isize::checked_shr, ">>")
     }
 }
 
 impl Object {
+    // what the object is, as far as the user can tell
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            Object::Error(_) => "error",
+            Object::Return(o) => o.type_name(),
+            Object::Break(_) | Object::Continue(_) => "null",
+            Object::Int(_) => "int",
+            Object::Float(_) => "float",
+            Object::Bool(_) => "bool",
+            Object::Str(_) => "string",
+            Object::Function(..)
+            | Object::CompiledFunction(..)
+            | Object::Closure(..)
+            | Object::Builtin(_)
+            | Object::Constructor(..) => "function",
+            Object::Quote(_) => "quote",
+            Object::Macro(..) => "macro",
+            Object::Array(_) => "array",
+            Object::Tuple(_) => "tuple",
+            Object::Hash(_) => "hash",
+            Object::StructType(..) => "struct type",
+            Object::Struct(..) => "struct",
+            Object::EnumType(..) => "enum type",
+            Object::Variant(..) => "enum",
+            Object::Null => "null",
+        }
+    }
+
+    // return, break and continue, which cut the evaluation of a block short
+    pub(crate) fn is_signal(&self) -> bool {
+        matches!(
+            self,
+            Object::Return(_) | Object::Break(_) | Object::Continue(_)
+        )
+    }
+
+    // what a for loop goes over
+    // the elements of an array or a tuple, the keys of a hash and the chars of a string
+    pub(crate) fn into_iterable(self) -> Result<Vec<Object>, String> {
+        match self {
+            Object::Array(elements) | Object::Tuple(elements) => Ok(elements),
+            Object::Hash(entries) => Ok(entries.into_keys().map(Object::from).collect()),
+            Object::Str(str) => Ok(str.chars().map(|c| Object::Str(c.into())).collect()),
+            _ => Err(format!(
+                "Can't iterate over a value of type {}",
+                self.type_name()
+            )),
+        }
+    }
+
+    pub(crate) fn hash_key(&self) -> Result<Hashable, String> {
+        match self {
+            Object::Int(int) => Ok(Hashable::Int(*int)),
+            Object::Bool(bool) => Ok(Hashable::Bool(*bool)),
+            Object::Str(str) => Ok(Hashable::Str(str.clone())),
+            _ => Err(format!(
+                "Values of type {} can't be hash keys, only ints, bools and strings can",
+                self.type_name()
+            )),
+        }
+    }
+
+    // `collection[index]`, an element of an array or a tuple, or the value of a key in a hash
+    pub(crate) fn index(self, index: Object) -> Result<Object, String> {
+        let (a_kind, kinds) = match self {
+            Object::Tuple(_) => ("a tuple", "Tuples"),
+            _ => ("an array", "Arrays"),
+        };
+        match (self, index) {
+            (Object::Array(elements) | Object::Tuple(elements), Object::Int(i)) => {
+                let len = elements.len();
+                usize::try_from(i)
+                    .ok()
+                    .and_then(|i| elements.into_iter().nth(i))
+                    .ok_or_else(|| {
+                        format!("Index {i} is out of bounds for {a_kind} of length {len}")
+                    })
+            }
+            (Object::Hash(mut entries), key) => {
+                let key = key.hash_key()?;
+                entries
+                    .remove(&key)
+                    .ok_or_else(|| format!("No entry for the key {key} in the hash"))
+            }
+            (Object::Array(_) | Object::Tuple(_), index) => Err(format!(
+                "{kinds} are indexed by ints, got {}",
+                index.type_name()
+            )),
+            (collection, _) => Err(format!(
+                "Can't index into a value of type {}",
+                collection.type_name()
+            )),
+        }
+    }
+
+    // `object.name`, a field of a struct or a variant
+    // or of an enum type, the variant itself when it has no fields, or what makes one when it does
+    pub(crate) fn field(self, name: Symbol) -> Result<Object, String> {
+        match self {
+            Object::Struct(_, ref fields) | Object::Variant(_, _, ref fields) => fields
+                .iter()
+                .find(|&&(other, _)| other == name)
+                .map(|(_, value)| value.clone())
+                .ok_or_else(|| self.no_field(name)),
+            Object::EnumType(type_name, ref variants) => {
+                let Some((_, fields)) = variants.iter().find(|&&(other, _)| other == name) else {
+                    return Err(self.no_field(name));
+                };
+                Ok(match fields.is_empty() {
+                    true => Object::Variant(type_name, name, vec![]),
+                    false => Object::Constructor(type_name, name, fields.clone()),
+                })
+            }
+            object => Err(object.no_field(name)),
+        }
+    }
+
+    // the error for `object.name`, when the object doesn't have that field
+    pub(crate) fn no_field(&self, name: Symbol) -> String {
+        match self {
+            Object::Struct(type_name, _) => {
+                format!("The struct `{type_name}` has no field `{name}`")
+            }
+            Object::Variant(type_name, variant, _) => {
+                format!("The variant `{type_name}.{variant}` has no field `{name}`")
+            }
+            Object::EnumType(type_name, _) => {
+                format!("The enum `{type_name}` has no variant `{name}`")
+            }
+            object => format!(
+                "Values of type {} don't have fields (like `{name}`), only structs and enums do",
+                object.type_name()
+            ),
+        }
+    }
+
     pub(crate) fn eq(self, rhs: Self) -> Result<Object, String> {
         match (&self, &rhs) {
             (Object::Null, Object::Null) => Ok(Object::Bool(true)),
+            // arrays (and tuples) are equal if they have the same length
+            // and their elements are equal pairwise
+            (Object::Array(one), Object::Array(two)) | (Object::Tuple(one), Object::Tuple(two)) => {
+                if one.len() != two.len() {
+                    return Ok(Object::Bool(false));
+                }
+                for (one, two) in one.iter().zip(two.iter()) {
+                    if let Object::Bool(false) = one.clone().eq(two.clone())? {
+                        return Ok(Object::Bool(false));
+                    }
+                }
+                Ok(Object::Bool(true))
+            }
+            (Object::Hash(one), Object::Hash(two)) => {
+                if one.len() != two.len() {
+                    return Ok(Object::Bool(false));
+                }
+                for (key, one) in one {
+                    let Some(two) = two.get(key) else {
+                        return Ok(Object::Bool(false));
+                    };
+                    if let Object::Bool(false) = one.clone().eq(two.clone())? {
+                        return Ok(Object::Bool(false));
+                    }
+                }
+                Ok(Object::Bool(true))
+            }
+            // structs are equal if they are of the same type and their fields are equal
+            // (types in different scopes can share a name, so the fields are compared too)
+            // the same goes for variants, which have to be the same variant as well
+            (Object::Struct(one, one_fields), Object::Struct(two, two_fields))
+            | (Object::Variant(one, _, one_fields), Object::Variant(two, _, two_fields)) => {
+                let one_names = one_fields.iter().map(|(name, _)| name);
+                if one != two || one_names.ne(two_fields.iter().map(|(name, _)| name)) {
+                    return Ok(Object::Bool(false));
+                }
+                if let (Object::Variant(_, one, _), Object::Variant(_, two, _)) = (&self, &rhs) {
+                    if one != two {
+                        return Ok(Object::Bool(false));
+                    }
+                }
+                for ((_, one), (_, two)) in one_fields.iter().zip(two_fields.iter()) {
+                    if let Object::Bool(false) = one.clone().eq(two.clone())? {
+                        return Ok(Object::Bool(false));
+                    }
+                }
+                Ok(Object::Bool(true))
+            }
             (Object::Int(one), Object::Int(two)) => Ok(Object::Bool(one == two)),
+            (Object::Float(one), Object::Float(two)) => Ok(Object::Bool(one == two)),
+            (Object::Int(one), Object::Float(two)) => Ok(Object::Bool(*one as f64 == *two)),
+            (Object::Float(one), Object::Int(two)) => Ok(Object::Bool(*one == *two as f64)),
             (Object::Bool(one), Object::Bool(two)) => Ok(Object::Bool(one == two)),
-            _ => Err(format!(
-                "==/!= operator is not valid for types: {:?}, {:?}",
-                self, rhs
-            )),
+            (Object::Str(one), Object::Str(two)) => Ok(Object::Bool(one == two)),
+            // printed the way they are written, a quote is shown as its code
+            _ => Err(format!("==/!= operator is not valid for {self} and {rhs}")),
         }
     }
 
@@ -83,23 +539,153 @@ impl Object {
         }
     }
 
-    pub(crate) fn gt(self, rhs: Self) -> Result<Object, String> {
-        match (&self, &rhs) {
-            (Object::Int(one), Object::Int(two)) => Ok(Object::Bool(one > two)),
-            _ => Err(format!(
-                ">/< operator is not valid for types: {:?}, {:?}",
-                self, rhs
-            )),
+    // an int raised to a negative power isn't an int anymore, so that gives a float
+    pub(crate) fn pow(self, rhs: Self) -> Result<Object, String> {
+        match (self, rhs) {
+            (Object::Int(lhs), Object::Int(rhs)) if rhs < 0 => {
+                Ok(Object::Float((lhs as f64).powf(rhs as f64)))
+            }
+            (Object::Int(lhs), Object::Int(rhs)) => u32::try_from(rhs)
+                .ok()
+                .and_then(|rhs| lhs.checked_pow(rhs))
+                .map(Object::Int)
+                .ok_or(format!("{lhs} ** {rhs} is too big for an int")),
+            (lhs, rhs) => numeric_op(
+                lhs,
+                rhs,
+                // ints with ints are taken care of above
+                |_, _| unreachable!(),
+                f64::powf,
+                "Exponentiation requires that both operands are numbers",
+            ),
+        }
+    }
+
+    // NaN isn't ordered, every comparison with it is false
+    fn compare(&self, rhs: &Self, operator: &str) -> Result<Option<Ordering>, String> {
+        match (self, rhs) {
+            (Object::Int(one), Object::Int(two)) => Ok(Some(one.cmp(two))),
+            (Object::Float(one), Object::Float(two)) => Ok(one.partial_cmp(two)),
+            (Object::Int(one), Object::Float(two)) => Ok((*one as f64).partial_cmp(two)),
+            (Object::Float(one), Object::Int(two)) => Ok(one.partial_cmp(&(*two as f64))),
+            _ => Err(format!("{operator} operator is not valid for {self} and {rhs}")),
         }
     }
 
+    pub(crate) fn gt_eq(self, rhs: Self) -> Result<Object, String> {
+        let ordering = self.compare(&rhs, ">=")?;
+        Ok(Object::Bool(ordering.is_some_and(Ordering::is_ge)))
+    }
+
+    pub(crate) fn lt_eq(self, rhs: Self) -> Result<Object, String> {
+        let ordering = self.compare(&rhs, "<=")?;
+        Ok(Object::Bool(ordering.is_some_and(Ordering::is_le)))
+    }
+
+    pub(crate) fn gt(self, rhs: Self) -> Result<Object, String> {
+        let ordering = self.compare(&rhs, ">")?;
+        Ok(Object::Bool(ordering.is_some_and(Ordering::is_gt)))
+    }
+
     pub(crate) fn lt(self, rhs: Self) -> Result<Object, String> {
-        match (&self, &rhs) {
-            (Object::Int(one), Object::Int(two)) => Ok(Object::Bool(one < two)),
-            _ => Err(format!(
-                ">/< operator is not valid for types: {:?}, {:?}",
-                self, rhs
-            )),
+        let ordering = self.compare(&rhs, "<")?;
+        Ok(Object::Bool(ordering.is_some_and(Ordering::is_lt)))
+    }
+}
+
+// how a value looks when it's printed, close to the code that makes it
+// `[1, "two"]`, `(1,)`, `{"a": 1}` and `Point { x: 1, y: 2 }`
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `items` separated by commas
+        fn list<T>(
+            f: &mut fmt::Formatter<'_>,
+            items: impl IntoIterator<Item = T>,
+            item: impl Fn(&mut fmt::Formatter<'_>, T) -> fmt::Result,
+        ) -> fmt::Result {
+            for (i, elem) in items.into_iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                item(f, elem)?;
+            }
+            Ok(())
+        }
+
+        match self {
+            Object::Error(err) => write!(f, "error: {err}"),
+            Object::Return(value) => write!(f, "{value}"),
+            Object::Break(_) | Object::Continue(_) | Object::Null => write!(f, "null"),
+            Object::Int(int) => write!(f, "{int}"),
+            // with a `.0` when it's whole, so it doesn't look like an int
+            Object::Float(float) => write!(f, "{float:?}"),
+            Object::Bool(bool) => write!(f, "{bool}"),
+            Object::Str(str) => write!(f, "{str:?}"),
+            Object::Function(Some(name), ..) | Object::CompiledFunction(.., Some(name)) => {
+                write!(f, "fn {name}")
+            }
+            Object::Function(None, ..) | Object::CompiledFunction(.., None) => write!(f, "fn"),
+            Object::Closure(function, _) => write!(f, "{function}"),
+            Object::Builtin(builtin) => write!(f, "{builtin:?}"),
+            Object::Quote(code) => write!(f, "quote({code})"),
+            Object::Macro(..) => write!(f, "macro"),
+            Object::Array(elements) => {
+                write!(f, "[")?;
+                list(f, elements, |f, elem| write!(f, "{elem}"))?;
+                write!(f, "]")
+            }
+            Object::Tuple(elements) => {
+                write!(f, "(")?;
+                list(f, elements, |f, elem| write!(f, "{elem}"))?;
+                if elements.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            Object::Hash(entries) => {
+                write!(f, "{{")?;
+                list(f, entries, |f, (key, value)| write!(f, "{key}: {value}"))?;
+                write!(f, "}}")
+            }
+            Object::StructType(name, fields) if fields.is_empty() => {
+                write!(f, "struct {name} {{}}")
+            }
+            Object::StructType(name, fields) => {
+                write!(f, "struct {name} {{ ")?;
+                list(f, fields, |f, field| write!(f, "{field}"))?;
+                write!(f, " }}")
+            }
+            Object::Struct(name, fields) if fields.is_empty() => write!(f, "{name} {{}}"),
+            Object::Struct(name, fields) => {
+                write!(f, "{name} {{ ")?;
+                list(f, fields, |f, (field, value)| write!(f, "{field}: {value}"))?;
+                write!(f, " }}")
+            }
+            Object::EnumType(name, variants) if variants.is_empty() => {
+                write!(f, "enum {name} {{}}")
+            }
+            Object::EnumType(name, variants) => {
+                write!(f, "enum {name} {{ ")?;
+                list(f, variants, |f, (variant, fields)| {
+                    write!(f, "{variant}")?;
+                    if !fields.is_empty() {
+                        write!(f, "(")?;
+                        list(f, fields, |f, field| write!(f, "{field}"))?;
+                        write!(f, ")")?;
+                    }
+                    Ok(())
+                })?;
+                write!(f, " }}")
+            }
+            Object::Variant(name, variant, fields) if fields.is_empty() => {
+                write!(f, "{name}.{variant}")
+            }
+            Object::Variant(name, variant, fields) => {
+                write!(f, "{name}.{variant}(")?;
+                list(f, fields, |f, (_, value)| write!(f, "{value}"))?;
+                write!(f, ")")
+            }
+            Object::Constructor(name, variant, _) => write!(f, "fn {name}.{variant}"),
         }
     }
 }
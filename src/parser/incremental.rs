@@ -0,0 +1,190 @@
+use super::{ParseError, Parser};
+use crate::ast::{fold::Folder, statement::StatementNode, Program};
+use crate::lexer::Lexer;
+use crate::token::{shift_span, Pos, Span, Token};
+
+// A replacement of the text in `range` (byte offsets into the current source) with `text`
+// i.e, what an editor reports on every keystroke
+#[derive(Debug, Clone)]
+pub struct Edit {
+    pub range: Span,
+    pub text: String,
+}
+
+impl Edit {
+    pub fn new(range: Span, text: impl Into<String>) -> Self {
+        Self {
+            range,
+            text: text.into(),
+        }
+    }
+}
+
+// Where a top level statement sits in the source
+#[derive(Debug)]
+struct Item {
+    span: Span,
+    // end of the token that followed the statement
+    // the parser looked at it to decide that the statement was over
+    // so an edit touching it can change how the statement parses
+    lookahead_end: Pos,
+    // the errors found while parsing the statement
+    errors: Vec<ParseError>,
+}
+
+impl Item {
+    fn shift_spans(&mut self, delta: isize) {
+        self.span = shift_span(self.span, delta);
+        self.lookahead_end = self.lookahead_end.wrapping_add_signed(delta);
+        self.errors
+            .iter_mut()
+            .for_each(|err| err.shift_spans(delta));
+    }
+}
+
+// moves every span in a reused statement by the change in length
+// (and the spans of a module to where it sits among the others, see module::Loader)
+pub(crate) struct ShiftSpans(pub(crate) isize);
+
+impl Folder for ShiftSpans {
+    fn fold_token(&mut self, mut token: Token) -> Token {
+        token.shift(self.0);
+        token
+    }
+
+    fn fold_error(&mut self, span: Span) -> Span {
+        shift_span(span, self.0)
+    }
+}
+
+// A source file that is kept parsed while it is being edited
+//
+// An edit only re-lexes and re-parses the top level statements it can affect
+// parsing resumes at the statement boundary right before the edit
+// and stops as soon as it reaches a statement that started after the edited range in the old source,
+// from there on, the text is the same as before, so the old statements are reused
+// (after moving their spans by the change in length)
+//
+// The result is always the same as parsing the new source from scratch
+pub struct Document {
+    source: String,
+    program: Program,
+    items: Vec<Item>,
+    // how many statements the last edit parsed again, all the others were reused
+    reparsed: usize,
+}
+
+impl Document {
+    pub fn new(source: String) -> Self {
+        let mut doc = Self {
+            source,
+            program: Program::new(),
+            items: vec![],
+            reparsed: 0,
+        };
+        doc.reparse_from(0, vec![], vec![], vec![], 0);
+        doc
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    // the program, with error nodes in place of the statements that failed to parse
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn reparsed(&self) -> usize {
+        self.reparsed
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ParseError> {
+        self.items.iter().flat_map(|item| item.errors.iter())
+    }
+
+    pub fn apply(&mut self, edit: Edit) -> &Program {
+        let (start, end) = edit.range;
+        assert!(
+            start <= end && end <= self.source.len(),
+            "edit {:?} is out of bounds",
+            edit.range
+        );
+        self.source.replace_range(start..end, &edit.text);
+        let delta = edit.text.len() as isize - (end - start) as isize;
+
+        let mut statements = std::mem::take(&mut self.program.statements);
+        let mut items = std::mem::take(&mut self.items);
+
+        // the statements that were decided before reaching the edit are kept as they are
+        let damaged = items
+            .iter()
+            .position(|item| item.lookahead_end >= start)
+            .unwrap_or(items.len());
+        let resume = damaged.checked_sub(1).map_or(0, |i| items[i].span.1);
+
+        // statements after the edit are candidates for reuse
+        let reusable: Vec<_> = items
+            .split_off(damaged)
+            .into_iter()
+            .zip(statements.split_off(damaged))
+            .filter(|(item, _)| item.span.0 >= end)
+            .collect();
+
+        self.reparse_from(resume, statements, items, reusable, delta);
+        &self.program
+    }
+
+    // parses statements starting at `resume` till it lines up with one of the `reusable` ones
+    fn reparse_from(
+        &mut self,
+        resume: Pos,
+        mut statements: Vec<StatementNode>,
+        mut items: Vec<Item>,
+        reusable: Vec<(Item, StatementNode)>,
+        delta: isize,
+    ) {
+        let lexer = Lexer::from_string(self.source.clone()).starting_at(resume);
+        let mut parser = Parser::new(lexer);
+        let mut reusable = reusable.into_iter().peekable();
+        self.reparsed = 0;
+
+        while let Some(current) = parser.current.as_ref() {
+            let pos = current.span.0;
+
+            // the old statements that were passed without lining up can't be reused anymore
+            while reusable
+                .peek()
+                .is_some_and(|(item, _)| shift_span(item.span, delta).0 < pos)
+            {
+                reusable.next();
+            }
+            if reusable
+                .peek()
+                .is_some_and(|(item, _)| shift_span(item.span, delta).0 == pos)
+            {
+                for (mut item, stmt) in reusable {
+                    item.shift_spans(delta);
+                    statements.push(ShiftSpans(delta).fold_statement(stmt));
+                    items.push(item);
+                }
+                break;
+            }
+
+            let stmt = parser.parse_statement_or_recover();
+            statements.push(stmt);
+            self.reparsed += 1;
+            items.push(Item {
+                span: (pos, parser.prev_end),
+                lookahead_end: parser
+                    .current
+                    .as_ref()
+                    .map_or(self.source.len(), |token| token.span.1),
+                errors: std::mem::take(&mut parser.errors),
+            });
+        }
+
+        self.program.statements = statements;
+        self.items = items;
+    }
+}
@@ -1,29 +1,94 @@
+use std::fmt;
 use std::mem::discriminant;
 
 use crate::ast::{
     expression::{
-        BinaryOperator, Block, Bool, ExpressionNode, Function, FunctionCall, Ident, If, Int,
-        UnaryOperator,
+        Array, Assign, AssignField, BinaryOperator, Block, Bool, Else, ExpressionNode, Field,
+        Float, For, Function, FunctionCall, Hash, Ident, If, Index, Int, Macro, Match, MatchArm,
+        Param, Str, Struct, Tuple, UnaryOperator, While,
+    },
+    pattern::Pattern,
+    statement::{
+        BreakStatement, DestructureStatement, EnumStatement, ExportStatement, ExpressionStatement,
+        FunctionStatement, ImportStatement, LetStatement, ReturnStatement, StatementNode,
+        StructStatement,
     },
-    statement::{ExpressionStatement, LetStatement, ReturnStatement, StatementNode},
     Program,
 };
+use crate::diagnostics::Diagnostic;
 use crate::lexer::Lexer;
-use crate::token::{token_type::TokenType, Token};
+use crate::token::{
+    float_literal_value, int_literal_value, string_literal_value, symbol::Symbol,
+    token_type::TokenType, Pos, Span, Token,
+};
 
-#[allow(dead_code)]
 #[derive(Debug)]
 pub struct ParseError {
     expected: String,
-    found: Option<Token>,
+    // an Eof token if the input ran out
+    found: Token,
+    // why the found token doesn't do, if there is more to say than "expected x"
+    reason: Option<String>,
+}
+
+impl ParseError {
+    pub(crate) fn shift_spans(&mut self, delta: isize) {
+        self.found.shift(delta);
+    }
+
+    pub fn span(&self) -> Span {
+        self.found.span
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string(), self.span());
+        match &self.reason {
+            Some(reason) => diagnostic.with_primary_message(reason),
+            None => diagnostic,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Expected {}, found {}", self.expected, self.found.r#type)
+    }
 }
 
 type ParseResult<T> = Result<T, ParseError>;
 
+pub mod incremental;
+
 pub struct Parser {
     lexer: Lexer,
     current: Option<Token>,
     peek: Option<Token>,
+
+    // end of the current token, and that of the token before it
+    // i.e, where the AST node that was parsed last ends
+    current_end: Pos,
+    prev_end: Pos,
+
+    // errors the parser recovered from
+    errors: Vec<ParseError>,
+
+    // the labels of the loops around the current statement, innermost last
+    // a function body starts with none, break and continue can't reach out of it
+    loops: Vec<Option<Symbol>>,
+
+    // how many blocks the current statement is in
+    // imports and exports are only allowed outside of them
+    blocks: usize,
+
+    // whether `Name {` starts a struct literal
+    // it doesn't right before the block of an if, a while, a for or a match
+    // where `if a == b { ... }` compares a and b, only a struct literal in parentheses does there
+    struct_literals: bool,
+
+    // where every statement starts and ends (its `;` included)
+    // nested ones too, in the order they start
+    // the AST drops the brackets and the `;`s, the printer needs them to know where the comments go
+    statement_spans: Vec<Span>,
 }
 
 impl Parser {
@@ -32,6 +97,13 @@ impl Parser {
             lexer,
             current: None,
             peek: None,
+            current_end: 0,
+            prev_end: 0,
+            errors: vec![],
+            loops: vec![],
+            blocks: 0,
+            struct_literals: true,
+            statement_spans: vec![],
         };
         p.advance_tokens();
         p.advance_tokens();
@@ -45,15 +117,37 @@ impl Parser {
         let next = self.lexer.next();
         self.current = self.peek.take();
         self.peek = next;
+
+        self.prev_end = self.current_end;
+        if let Some(current) = &self.current {
+            self.current_end = current.span.1;
+        }
     }
 
     fn check_token_type(&self, expected: TokenType) -> ParseResult<()> {
         match self.current.as_ref() {
             Some(Token { r#type: tt, .. }) if discriminant(&expected) == discriminant(tt) => Ok(()),
-            _ => Err(ParseError {
-                expected: format!("{:?}", expected),
-                found: self.current.clone(),
-            }),
+            _ => Err(self.error(expected.to_string())),
+        }
+    }
+
+    fn error(&self, expected: impl Into<String>) -> ParseError {
+        // the end of input is reported right after the last token
+        let found = self
+            .current
+            .clone()
+            .unwrap_or_else(|| Token::new(TokenType::Eof, (self.current_end, self.current_end)));
+        ParseError {
+            expected: expected.into(),
+            found,
+            reason: None,
+        }
+    }
+
+    fn error_with_reason(&self, expected: &str, reason: impl Into<String>) -> ParseError {
+        ParseError {
+            reason: Some(reason.into()),
+            ..self.error(expected)
         }
     }
 
@@ -63,43 +157,157 @@ impl Parser {
         self.advance_tokens();
         Ok(token)
     }
+
+    // runs `parse` with struct literals allowed or not, and then as they were before
+    fn with_struct_literals<T>(
+        &mut self,
+        allowed: bool,
+        parse: impl FnOnce(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<T> {
+        let outer = std::mem::replace(&mut self.struct_literals, allowed);
+        let result = parse(self);
+        self.struct_literals = outer;
+        result
+    }
 }
 
 // invariance: this is followed by all the parse functions(aka parselets) in this project
 // a little bit like the target arch calling conventions
 // when a parselet is called, the `current` token points to the token that belongs to the AST node returned by the parselet
 impl Parser {
-    pub fn parse_program(&mut self) -> ParseResult<Program> {
+    // Parses the whole input, even if it has errors in it
+    // statements that fail to parse are replaced by StatementNode::Error in the returned program
+    // and all the errors found along the way are returned with it (an empty list means success)
+    pub fn parse_program(&mut self) -> (Program, Vec<ParseError>) {
         let mut program = Program::new();
         while self.current.is_some() {
-            let stmt = self.parse_statement()?;
+            let stmt = self.parse_statement_or_recover();
             program.add_statement(stmt);
         }
-        Ok(program)
+        (program, std::mem::take(&mut self.errors))
+    }
+
+    // error recovery happens at the statement level, both at the top level and inside blocks
+    // the error is recorded, and the tokens till the next statement boundary are skipped
+    fn parse_statement_or_recover(&mut self) -> StatementNode {
+        // safe to unwrap as all the callers check for the end of input
+        let start = self.current.as_ref().unwrap().span;
+        let index = self.start_statement(start.0);
+        let stmt = match self.parse_statement() {
+            Ok(stmt) => stmt,
+            Err(err) => {
+                self.errors.push(err);
+                self.synchronize(start);
+                StatementNode::Error((start.0, self.prev_end))
+            }
+        };
+        self.statement_spans[index].1 = self.prev_end;
+        stmt
+    }
+
+    // records where a statement starts, its end is filled in once it's parsed
+    fn start_statement(&mut self, start: Pos) -> usize {
+        self.statement_spans.push((start, start));
+        self.statement_spans.len() - 1
+    }
+
+    pub(crate) fn statement_spans(&self) -> &[Span] {
+        &self.statement_spans
+    }
+
+    // skips tokens till the end of the broken statement
+    // i.e, a `;` (which is skipped too), or a `}`/let/return that doesn't belong to a nested block
+    fn synchronize(&mut self, start: Span) {
+        let mut depth = 0;
+        while let Some(token) = self.current.as_ref() {
+            match token.r#type {
+                TokenType::Semicolon if depth == 0 => {
+                    self.advance_tokens();
+                    return;
+                }
+                TokenType::Rbrace
+                | TokenType::Let
+                | TokenType::Const
+                | TokenType::Struct
+                | TokenType::Enum
+                | TokenType::Return
+                    if depth == 0 =>
+                {
+                    break
+                }
+                TokenType::Lbrace => depth += 1,
+                TokenType::Rbrace => depth -= 1,
+                _ => {}
+            }
+            self.advance_tokens();
+        }
+        // the error node has to cover at least one token, or we would be stuck here forever
+        // can only happen with a stray } at the top level
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|token| token.span == start)
+        {
+            self.advance_tokens();
+        }
     }
 
     fn parse_statement(&mut self) -> ParseResult<StatementNode> {
-        // safe to unwrap because of the check in parse_program function
-        let stmt: StatementNode = match self.current.as_ref().unwrap().r#type {
-            TokenType::Let => self.parse_let_statement()?.into(),
+        // the input can run out inside a block that was never closed
+        let Some(current) = self.current.as_ref() else {
+            return Err(self.error("a statement or `}`"));
+        };
+        let stmt: StatementNode = match current.r#type {
+            TokenType::Let | TokenType::Const => self.parse_let_statement()?,
             TokenType::Return => self.parse_return_statement()?.into(),
+            TokenType::Break | TokenType::Continue => self.parse_break_statement()?.into(),
+            TokenType::Struct => self.parse_struct_statement()?.into(),
+            TokenType::Enum => self.parse_enum_statement()?.into(),
+            TokenType::Import => self.parse_import_statement()?.into(),
+            TokenType::Export => self.parse_export_statement()?.into(),
+            // `fn(` is a function expression, `fn name(` a declaration
+            TokenType::Function
+                if matches!(
+                    self.peek.as_ref().map(|token| &token.r#type),
+                    Some(TokenType::Ident(_))
+                ) =>
+            {
+                self.parse_function_statement()?.into()
+            }
             _ => self.parse_expression_statement()?.into(),
         };
         self.expect(TokenType::Semicolon).ok();
         Ok(stmt)
     }
 
-    fn parse_let_statement(&mut self) -> ParseResult<LetStatement> {
-        let let_token = self.expect(TokenType::Let)?;
+    // `let name = value`, or `let pattern = value` that destructures the value
+    // and the same with `const`, whose names can't be assigned to or declared again
+    fn parse_let_statement(&mut self) -> ParseResult<StatementNode> {
+        let let_token = self.current.take().unwrap();
+        self.advance_tokens();
 
-        let ident = self.parse_ident()?;
+        if let Some(TokenType::Ident(_)) = self.current.as_ref().map(|token| &token.r#type) {
+            let ident = self.parse_ident()?;
+            self.expect(TokenType::Assign)?;
+            let expr = self.parse_expression(0)?;
+            return Ok(LetStatement::new(let_token, ident, expr).into());
+        }
 
+        let pattern = self.parse_pattern()?;
+        if let Some(refutable) = find_refutable(&pattern) {
+            let reason = match refutable {
+                Pattern::Variant(..) => "a let can't tell variants apart, use a match for that",
+                _ => "a let can't compare values, use a match for that",
+            };
+            Err(ParseError {
+                expected: "a name, `_`, a tuple or an array".into(),
+                found: refutable.token().clone(),
+                reason: Some(reason.into()),
+            })?;
+        }
         self.expect(TokenType::Assign)?;
-
         let expr = self.parse_expression(0)?;
-
-        let stmt = LetStatement::new(let_token, ident, expr);
-        Ok(stmt)
+        Ok(DestructureStatement::new(let_token, pattern, expr).into())
     }
 
     fn parse_return_statement(&mut self) -> ParseResult<ReturnStatement> {
@@ -111,6 +319,147 @@ impl Parser {
         Ok(stmt)
     }
 
+    // `import "path/to/mod"`
+    fn parse_import_statement(&mut self) -> ParseResult<ImportStatement> {
+        self.check_top_level()?;
+        let token = self.expect(TokenType::Import)?;
+        let path = self.expect(TokenType::Str("".into()))?;
+        Ok(ImportStatement::new(token, path))
+    }
+
+    // `export let ...`, `export const ...`, `export fn name...`, `export struct ...`
+    // or `export enum ...`
+    fn parse_export_statement(&mut self) -> ParseResult<ExportStatement> {
+        self.check_top_level()?;
+        let token = self.expect(TokenType::Export)?;
+        let stmt = match self.current.as_ref().map(|token| &token.r#type) {
+            Some(TokenType::Let | TokenType::Const) => self.parse_let_statement()?,
+            // an anonymous function has no name to export, so the name is expected here
+            Some(TokenType::Function) => self.parse_function_statement()?.into(),
+            Some(TokenType::Struct) => self.parse_struct_statement()?.into(),
+            Some(TokenType::Enum) => self.parse_enum_statement()?.into(),
+            _ => Err(self.error_with_reason(
+                "`let`, `const`, `fn`, `struct` or `enum`",
+                "only lets, consts, function, struct and enum declarations can be exported",
+            ))?,
+        };
+        Ok(ExportStatement::new(token, stmt))
+    }
+
+    // a module imports and exports things as a whole, not in a block
+    fn check_top_level(&self) -> ParseResult<()> {
+        if self.blocks > 0 {
+            let keyword = &self.current.as_ref().unwrap().r#type;
+            Err(self.error_with_reason(
+                "a statement",
+                format!("{keyword} is only allowed at the top level of a module"),
+            ))?;
+        }
+        Ok(())
+    }
+
+    // `break` or `continue`, with an optional label
+    // which have to be inside a loop (with that label), so this is checked right away
+    fn parse_break_statement(&mut self) -> ParseResult<BreakStatement> {
+        let token = self.current.take().unwrap();
+        self.advance_tokens();
+        let label = self.expect(TokenType::Label(Symbol::EMPTY)).ok();
+
+        let (found, reason) = match &label {
+            None if self.loops.is_empty() => (
+                &token,
+                format!("{} is only allowed inside a loop", token.r#type),
+            ),
+            Some(label) if !self.loops.contains(&label.get_label_name()) => (
+                label,
+                format!("there's no loop with {} around this", label.r#type),
+            ),
+            _ => return Ok(BreakStatement::new(token, label)),
+        };
+        Err(ParseError {
+            expected: "a statement".into(),
+            found: found.clone(),
+            reason: Some(reason),
+        })
+    }
+
+    fn parse_function_statement(&mut self) -> ParseResult<FunctionStatement> {
+        let fn_token = self.expect(TokenType::Function)?;
+        let name = self.parse_ident()?;
+        let params = self.parse_param_list()?;
+        let body = self.parse_function_body()?;
+        Ok(FunctionStatement::new(fn_token, name, params, body))
+    }
+
+    // `struct Name { field, other }`
+    fn parse_struct_statement(&mut self) -> ParseResult<StructStatement> {
+        let token = self.expect(TokenType::Struct)?;
+        let name = self.parse_ident()?;
+        self.expect(TokenType::Lbrace)?;
+        let mut fields: Vec<Ident> = vec![];
+        let mut tc = Ok(Token::default());
+        while self.check_token_type(TokenType::Rbrace).is_err() {
+            tc?;
+            let field = self.parse_ident()?;
+            self.check_new_name(fields.iter(), &field, "field")?;
+            fields.push(field);
+            tc = self.expect(TokenType::Comma);
+        }
+        tc.ok();
+        self.expect(TokenType::Rbrace)?;
+        Ok(StructStatement::new(token, name, fields))
+    }
+
+    // `enum Name { Variant(field, other), Other }`
+    fn parse_enum_statement(&mut self) -> ParseResult<EnumStatement> {
+        let token = self.expect(TokenType::Enum)?;
+        let name = self.parse_ident()?;
+        self.expect(TokenType::Lbrace)?;
+        let mut variants: Vec<(Ident, Vec<Ident>)> = vec![];
+        let mut tc = Ok(Token::default());
+        while self.check_token_type(TokenType::Rbrace).is_err() {
+            tc?;
+            let variant = self.parse_ident()?;
+            self.check_new_name(variants.iter().map(|(name, _)| name), &variant, "variant")?;
+            let mut fields = vec![];
+            if self.expect(TokenType::Lparen).is_ok() {
+                let mut tc = Ok(Token::default());
+                while self.check_token_type(TokenType::Rparen).is_err() {
+                    tc?;
+                    let field = self.parse_ident()?;
+                    self.check_new_name(fields.iter(), &field, "field")?;
+                    fields.push(field);
+                    tc = self.expect(TokenType::Comma);
+                }
+                self.expect(TokenType::Rparen)?;
+            }
+            variants.push((variant, fields));
+            tc = self.expect(TokenType::Comma);
+        }
+        self.expect(TokenType::Rbrace)?;
+        Ok(EnumStatement::new(token, name, variants))
+    }
+
+    // a field (or a variant) can only be declared, or given a value, once
+    fn check_new_name<'a>(
+        &self,
+        mut names: impl Iterator<Item = &'a Ident>,
+        name: &Ident,
+        what: &str,
+    ) -> ParseResult<()> {
+        if names.any(|other| other.get_symbol() == name.get_symbol()) {
+            Err(ParseError {
+                expected: format!("another {what}"),
+                found: name.token().clone(),
+                reason: Some(format!(
+                    "the {what} `{}` is there already",
+                    name.get_symbol()
+                )),
+            })?;
+        }
+        Ok(())
+    }
+
     fn parse_expression_statement(&mut self) -> ParseResult<ExpressionStatement> {
         let expr = self.parse_expression(0)?;
 
@@ -121,7 +470,7 @@ impl Parser {
 }
 impl Parser {
     fn parse_ident(&mut self) -> ParseResult<Ident> {
-        self.check_token_type(TokenType::Ident("".into()))?;
+        self.check_token_type(TokenType::Ident(Symbol::EMPTY))?;
         let ident = Ident::new(self.current.take().unwrap());
         self.advance_tokens();
         Ok(ident)
@@ -129,11 +478,54 @@ impl Parser {
 
     fn parse_int(&mut self) -> ParseResult<Int> {
         self.check_token_type(TokenType::Int("".into()))?;
+        // out of range or malformed literals are rejected here
+        // so that the later stages can assume a valid literal
+        if let Some(Token {
+            r#type: TokenType::Int(lexeme),
+            ..
+        }) = &self.current
+        {
+            if let Err(err) = int_literal_value(lexeme) {
+                return Err(self.error_with_reason("a valid integer literal", err));
+            }
+        }
         let int = Int::new(self.current.take().unwrap());
         self.advance_tokens();
         Ok(int)
     }
 
+    fn parse_float(&mut self) -> ParseResult<Float> {
+        self.check_token_type(TokenType::Float("".into()))?;
+        if let Some(Token {
+            r#type: TokenType::Float(lexeme),
+            ..
+        }) = &self.current
+        {
+            if let Err(err) = float_literal_value(lexeme) {
+                return Err(self.error_with_reason("a valid float literal", err));
+            }
+        }
+        let float = Float::new(self.current.take().unwrap());
+        self.advance_tokens();
+        Ok(float)
+    }
+
+    fn parse_str(&mut self) -> ParseResult<Str> {
+        self.check_token_type(TokenType::Str("".into()))?;
+        if let Some(Token {
+            r#type: TokenType::Str(lexeme),
+            ..
+        }) = &self.current
+        {
+            if let Err(err) = string_literal_value(lexeme) {
+                return Err(self.error_with_reason("a valid string literal", err));
+            }
+        }
+        let str = Str::new(self.current.take().unwrap());
+        self.advance_tokens();
+        Ok(str)
+    }
+
     fn parse_bool(&mut self) -> ParseResult<Bool> {
         // no need for the check here i suppose
         let b = Bool::new(self.current.take().unwrap());
@@ -143,11 +535,23 @@ impl Parser {
 
     fn parse_block(&mut self) -> ParseResult<Block> {
         self.expect(TokenType::Lbrace)?;
+        self.parse_block_rest(vec![])
+    }
 
-        let mut stmts = vec![];
+    // the statements of a block after the ones in `stmts`, till the closing `}`
+    fn parse_block_rest(&mut self, mut stmts: Vec<StatementNode>) -> ParseResult<Block> {
+        self.blocks += 1;
+        let outer = std::mem::replace(&mut self.struct_literals, true);
         while self.check_token_type(TokenType::Rbrace).is_err() {
-            stmts.push(self.parse_statement()?);
+            if self.current.is_none() {
+                self.blocks -= 1;
+                self.struct_literals = outer;
+                return Err(self.error("a statement or `}`"));
+            }
+            stmts.push(self.parse_statement_or_recover());
         }
+        self.blocks -= 1;
+        self.struct_literals = outer;
 
         self.expect(TokenType::Rbrace)?;
 
@@ -157,90 +561,483 @@ impl Parser {
     fn parse_if_else(&mut self) -> ParseResult<If> {
         let if_token = self.expect(TokenType::If)?;
 
-        let condition = self.parse_expression(0)?;
+        let condition = self.parse_head()?;
 
         let action = self.parse_block()?;
 
         let mut alternate = None;
         if self.expect(TokenType::Else).is_ok() {
-            alternate = Some(self.parse_block()?);
+            alternate = if self.check_token_type(TokenType::If).is_ok() {
+                Some(Else::If(Box::new(self.parse_if_else()?.into())))
+            } else {
+                Some(Else::Block(self.parse_block()?))
+            };
         }
 
         Ok(If::new(if_token, condition, action, alternate))
     }
 
-    fn parse_arg_list(&mut self) -> ParseResult<Vec<Ident>> {
+    // `'label: while ...` or `'label: for ...`
+    fn parse_labeled_loop(&mut self) -> ParseResult<ExpressionNode> {
+        let label = self.expect(TokenType::Label(Symbol::EMPTY))?;
+        self.expect(TokenType::Colon)?;
+        match self.current.as_ref().map(|token| &token.r#type) {
+            Some(TokenType::While) => Ok(self.parse_while(Some(label))?.into()),
+            Some(TokenType::For) => Ok(self.parse_for(Some(label))?.into()),
+            _ => Err(self.error_with_reason("a loop", "only loops can be labeled")),
+        }
+    }
+
+    fn parse_while(&mut self, label: Option<Token>) -> ParseResult<While> {
+        let while_token = self.expect(TokenType::While)?;
+        let condition = self.parse_head()?;
+        let body = self.parse_loop_body(label.as_ref())?;
+        Ok(While::new(while_token, label, condition, body))
+    }
+
+    fn parse_for(&mut self, label: Option<Token>) -> ParseResult<For> {
+        let for_token = self.expect(TokenType::For)?;
+        let var = self.parse_ident()?;
+        self.expect(TokenType::In)?;
+        let iterable = self.parse_head()?;
+        let body = self.parse_loop_body(label.as_ref())?;
+        Ok(For::new(for_token, label, var, iterable, body))
+    }
+
+    // the expression between `if`, `while`, `for ... in` or `match` and the `{` after it
+    fn parse_head(&mut self) -> ParseResult<ExpressionNode> {
+        self.with_struct_literals(false, |parser| parser.parse_expression(0))
+    }
+
+    fn parse_loop_body(&mut self, label: Option<&Token>) -> ParseResult<Block> {
+        self.loops.push(label.and_then(Token::get_label_name));
+        let body = self.parse_block();
+        self.loops.pop();
+        body
+    }
+
+    // match value { pattern => body, pattern if guard => body, ... }
+    // the comma after an arm can be left out when its body is a block
+    fn parse_match(&mut self) -> ParseResult<Match> {
+        let match_token = self.expect(TokenType::Match)?;
+        let value = self.parse_head()?;
+        self.expect(TokenType::Lbrace)?;
+        let arms = self.with_struct_literals(true, Self::parse_match_arms)?;
+        Ok(Match::new(match_token, value, arms))
+    }
+
+    // the arms of a match, till its closing `}`
+    fn parse_match_arms(&mut self) -> ParseResult<Vec<MatchArm>> {
+        let mut arms = vec![];
+        while self.expect(TokenType::Rbrace).is_err() {
+            let pattern = self.parse_pattern()?;
+            let guard = match self.expect(TokenType::If) {
+                Ok(_) => Some(self.parse_expression(0)?),
+                Err(_) => None,
+            };
+            self.expect(TokenType::FatArrow)?;
+            let body = self.parse_expression(0)?;
+            let is_block = matches!(body, ExpressionNode::Block(_));
+            arms.push(MatchArm {
+                pattern,
+                guard,
+                body,
+            });
+            if self.expect(TokenType::Comma).is_err()
+                && !is_block
+                && self.check_token_type(TokenType::Rbrace).is_err()
+            {
+                return Err(self.error("`,` or `}`"));
+            }
+        }
+        Ok(arms)
+    }
+
+    fn parse_pattern(&mut self) -> ParseResult<Pattern> {
+        let Some(current) = self.current.as_ref() else {
+            return Err(self.error("a pattern"));
+        };
+        let pattern = match current.r#type {
+            TokenType::Underscore => Pattern::Wildcard(self.expect(TokenType::Underscore)?),
+            // `Shape.Circle(r)`
+            TokenType::Ident(_)
+                if matches!(
+                    self.peek.as_ref().map(|token| &token.r#type),
+                    Some(TokenType::Dot)
+                ) =>
+            {
+                let name = self.parse_ident()?;
+                self.expect(TokenType::Dot)?;
+                let variant = self.parse_ident()?;
+                let patterns = match self.check_token_type(TokenType::Lparen) {
+                    Ok(()) => {
+                        self.parse_pattern_list(TokenType::Lparen, TokenType::Rparen)?
+                            .0
+                    }
+                    Err(_) => vec![],
+                };
+                Pattern::Variant(name, variant, patterns)
+            }
+            TokenType::Ident(_) => Pattern::Binding(self.parse_ident()?),
+            TokenType::Int(_) => Pattern::Literal(self.parse_int()?.into()),
+            TokenType::Minus => {
+                let minus = self.current.take().unwrap();
+                self.advance_tokens();
+                let int = self.parse_int()?;
+                Pattern::Literal(UnaryOperator::new(minus, int.into()).into())
+            }
+            TokenType::Str(_) => Pattern::Literal(self.parse_str()?.into()),
+            TokenType::True | TokenType::False => Pattern::Literal(self.parse_bool()?.into()),
+            TokenType::Float(_) => Err(self.error_with_reason(
+                "a pattern",
+                "floats can't be patterns, bind the value and compare it in a guard instead",
+            ))?,
+            TokenType::Lbracket => {
+                let token = self.current.clone().unwrap();
+                let (patterns, _) =
+                    self.parse_pattern_list(TokenType::Lbracket, TokenType::Rbracket)?;
+                Pattern::Array(token, patterns)
+            }
+            // like in expressions, it takes a comma to make a tuple out of a single pattern
+            TokenType::Lparen => {
+                let token = self.current.clone().unwrap();
+                match self.parse_pattern_list(TokenType::Lparen, TokenType::Rparen)? {
+                    (mut patterns, false) if patterns.len() == 1 => patterns.pop().unwrap(),
+                    (patterns, _) => Pattern::Tuple(token, patterns),
+                }
+            }
+            _ => Err(self.error_with_reason("a pattern", "a pattern can't start with this"))?,
+        };
+        Ok(pattern)
+    }
+
+    // the patterns between `open` and `close`, separated by commas
+    // and whether there's a comma after the last one
+    // a name can only be bound once in them
+    fn parse_pattern_list(
+        &mut self,
+        open: TokenType,
+        close: TokenType,
+    ) -> ParseResult<(Vec<Pattern>, bool)> {
+        self.expect(open)?;
+        let mut patterns: Vec<Pattern> = vec![];
+        let mut tc = Ok(Token::default());
+        while self.check_token_type(close.clone()).is_err() {
+            tc?;
+            let pattern = self.parse_pattern()?;
+            for name in pattern.bindings() {
+                let names = patterns.iter().flat_map(Pattern::bindings);
+                self.check_new_name(names, name, "binding")?;
+            }
+            patterns.push(pattern);
+            tc = self.expect(TokenType::Comma);
+        }
+        let trailing_comma = tc.is_ok() && !patterns.is_empty();
+        self.expect(close)?;
+        Ok((patterns, trailing_comma))
+    }
+
+    // a `{` in an expression starts either a block or a hash literal
+    // `{}` is an empty hash, a statement keyword (`let`, `break`, ...) starts a block
+    // and a block can't start with a `key:`
+    // so the first statement (or key) is parsed, and the token after it decides
+    fn parse_block_or_hash(&mut self) -> ParseResult<ExpressionNode> {
+        let lbrace = self.expect(TokenType::Lbrace)?;
+        if self.expect(TokenType::Rbrace).is_ok() {
+            return Ok(Hash::new(lbrace, vec![]).into());
+        }
+        let Some(current) = self.current.as_ref() else {
+            return Err(self.error("a statement or `}`"));
+        };
+        let declares_function = current.r#type == TokenType::Function
+            && matches!(
+                self.peek.as_ref().map(|token| &token.r#type),
+                Some(TokenType::Ident(_))
+            );
+        if declares_function
+            || matches!(
+                current.r#type,
+                TokenType::Let
+                    | TokenType::Const
+                    | TokenType::Struct
+                    | TokenType::Enum
+                    | TokenType::Return
+                    | TokenType::Break
+                    | TokenType::Continue
+                    | TokenType::Import
+                    | TokenType::Export
+            )
+        {
+            return Ok(self.parse_block_rest(vec![])?.into());
+        }
+
+        let start = current.span;
+        let index = self.start_statement(start.0);
+        let first = match self.parse_expression(0) {
+            Ok(key) if self.check_token_type(TokenType::Colon).is_ok() => {
+                // it wasn't a statement after all
+                self.statement_spans.remove(index);
+                return Ok(self.parse_hash_rest(lbrace, key)?.into());
+            }
+            Ok(expr) => {
+                self.expect(TokenType::Semicolon).ok();
+                StatementNode::Expression(expr)
+            }
+            // same as parse_statement_or_recover, the rest of the block is still worth parsing
+            Err(err) => {
+                self.errors.push(err);
+                self.synchronize(start);
+                StatementNode::Error((start.0, self.prev_end))
+            }
+        };
+        self.statement_spans[index].1 = self.prev_end;
+        Ok(self.parse_block_rest(vec![first])?.into())
+    }
+
+    // the rest of a hash literal, after its first key
+    fn parse_hash_rest(&mut self, lbrace: Token, first_key: ExpressionNode) -> ParseResult<Hash> {
+        let mut entries = vec![];
+        let mut key = first_key;
+        loop {
+            self.expect(TokenType::Colon)?;
+            let value = self.parse_expression(0)?;
+            entries.push((key, value));
+            // a trailing comma is allowed
+            if self.expect(TokenType::Comma).is_err()
+                || self.check_token_type(TokenType::Rbrace).is_ok()
+            {
+                break;
+            }
+            key = self.parse_expression(0)?;
+        }
+        self.expect(TokenType::Rbrace)?;
+        Ok(Hash::new(lbrace, entries))
+    }
+
+    // the params without a default come first, then the ones with a default, then the rest param
+    fn parse_param_list(&mut self) -> ParseResult<Vec<Param>> {
         self.expect(TokenType::Lparen)?;
-        let mut args = vec![];
+        let mut params: Vec<Param> = vec![];
         let mut tc = Ok(Token::default());
         while self.check_token_type(TokenType::Rparen).is_err() {
             tc?;
-            args.push(self.parse_ident()?);
+            if params.last().is_some_and(|param| param.rest.is_some()) {
+                Err(self.error_with_reason("`)`", "the rest param has to be the last one"))?;
+            }
+            let param = self.parse_param()?;
+            let names = params.iter().map(|param| &param.ident);
+            self.check_new_name(names, &param.ident, "param")?;
+            let after_default = params.last().is_some_and(|param| param.default.is_some());
+            if after_default && param.default.is_none() && param.rest.is_none() {
+                Err(self.error_with_reason(
+                    "`=`",
+                    "the params after one with a default need a default too",
+                ))?;
+            }
+            params.push(param);
             tc = self.expect(TokenType::Comma);
         }
         tc.ok();
         self.expect(TokenType::Rparen)?;
-        Ok(args)
+        Ok(params)
+    }
+
+    // `name`, `name = default` or `...name`
+    fn parse_param(&mut self) -> ParseResult<Param> {
+        let rest = self.expect(TokenType::Ellipsis).ok();
+        let ident = self.parse_ident()?;
+        let mut default = None;
+        if self.check_token_type(TokenType::Assign).is_ok() {
+            if rest.is_some() {
+                Err(self.error_with_reason("`,` or `)`", "the rest param can't have a default"))?;
+            }
+            self.advance_tokens();
+            // the params are in parens, so a default can be a struct literal even in a condition
+            default = Some(self.with_struct_literals(true, |parser| parser.parse_expression(0))?);
+        }
+        Ok(Param {
+            ident,
+            default,
+            rest,
+        })
     }
 
     fn parse_call_arg_list(&mut self) -> ParseResult<Vec<ExpressionNode>> {
-        self.expect(TokenType::Lparen)?;
-        let mut args = vec![];
+        self.with_struct_literals(true, |parser| {
+            parser.parse_expression_list(TokenType::Lparen, TokenType::Rparen)
+        })
+    }
+
+    // comma separated expressions between `open` and `close`, a trailing comma is allowed
+    fn parse_expression_list(
+        &mut self,
+        open: TokenType,
+        close: TokenType,
+    ) -> ParseResult<Vec<ExpressionNode>> {
+        self.expect(open)?;
+        let mut exprs = vec![];
+        let mut tc = Ok(Token::default());
+        while self.check_token_type(close.clone()).is_err() {
+            tc?;
+            exprs.push(self.parse_expression(0)?);
+            tc = self.expect(TokenType::Comma);
+        }
+        tc.ok();
+        self.expect(close)?;
+        Ok(exprs)
+    }
+
+    // `(expr)` is the expression itself, it takes a comma to make a tuple: `(a, b)`, `(a,)`
+    // and `()` is the empty tuple
+    fn parse_group_or_tuple(&mut self) -> ParseResult<ExpressionNode> {
+        let token = self.expect(TokenType::Lparen)?;
+        if self.expect(TokenType::Rparen).is_ok() {
+            return Ok(Tuple::new(token, vec![]).into());
+        }
+        let first = self.parse_expression(0)?;
+        if self.expect(TokenType::Rparen).is_ok() {
+            return Ok(first);
+        }
+        self.expect(TokenType::Comma)
+            .map_err(|_| self.error("`,` or `)`"))?;
+        let mut elements = vec![first];
         let mut tc = Ok(Token::default());
         while self.check_token_type(TokenType::Rparen).is_err() {
             tc?;
-            args.push(self.parse_expression(0)?);
+            elements.push(self.parse_expression(0)?);
             tc = self.expect(TokenType::Comma);
         }
         tc.ok();
         self.expect(TokenType::Rparen)?;
-        Ok(args)
+        Ok(Tuple::new(token, elements).into())
+    }
+
+    fn parse_array(&mut self) -> ParseResult<Array> {
+        let token = self.current.clone().unwrap();
+        let elements = self.parse_expression_list(TokenType::Lbracket, TokenType::Rbracket)?;
+        Ok(Array::new(token, elements))
+    }
+
+    // `target = value`, `target += value` and so on
+    // where the target is a variable or a field of one, `point.x = value`
+    fn parse_assign(&mut self, target: ExpressionNode, prec: i8) -> ParseResult<ExpressionNode> {
+        if target.field_path().is_none() {
+            return Err(self.error_with_reason(
+                "a variable on the left of the assignment",
+                "only variables and their fields can be assigned to",
+            ));
+        }
+        let token = self.current.take().unwrap();
+        self.advance_tokens();
+        // right associative, a = b = c assigns c to both
+        let value = self.parse_expression(prec - prec % 10)?;
+        Ok(match target {
+            ExpressionNode::Ident(target) => Assign::new(token, target, value).into(),
+            target => AssignField::new(token, target, value).into(),
+        })
+    }
+
+    // `Name { field: value, other }`, after the name
+    fn parse_struct(&mut self, name: Ident) -> ParseResult<Struct> {
+        let token = self.expect(TokenType::Lbrace)?;
+        let mut fields: Vec<(Ident, ExpressionNode)> = vec![];
+        let mut tc = Ok(Token::default());
+        while self.check_token_type(TokenType::Rbrace).is_err() {
+            tc?;
+            let field = self.parse_ident()?;
+            self.check_new_name(fields.iter().map(|(name, _)| name), &field, "field")?;
+            let value = match self.expect(TokenType::Colon) {
+                Ok(_) => self.parse_expression(0)?,
+                Err(_) => field.clone().into(),
+            };
+            fields.push((field, value));
+            tc = self.expect(TokenType::Comma);
+        }
+        tc.ok();
+        self.expect(TokenType::Rbrace)?;
+        Ok(Struct::new(token, name, fields))
+    }
+
+    // `value.field`
+    fn parse_field(&mut self, value: ExpressionNode) -> ParseResult<Field> {
+        let token = self.expect(TokenType::Dot)?;
+        let field = self.parse_ident()?;
+        Ok(Field::new(token, value, field))
+    }
+
+    fn parse_index(&mut self, collection: ExpressionNode) -> ParseResult<Index> {
+        let token = self.expect(TokenType::Lbracket)?;
+        let index = self.with_struct_literals(true, |parser| parser.parse_expression(0))?;
+        self.expect(TokenType::Rbracket)?;
+        Ok(Index::new(token, collection, index))
     }
 
     fn parse_function(&mut self) -> ParseResult<Function> {
         let fn_token = self.expect(TokenType::Function)?;
 
-        let args = self.parse_arg_list()?;
+        let args = self.parse_param_list()?;
 
-        let body = self.parse_block()?;
+        let body = self.parse_function_body()?;
 
         Ok(Function::new(fn_token, args, body))
     }
 
+    fn parse_macro(&mut self) -> ParseResult<Macro> {
+        let token = self.expect(TokenType::Macro)?;
+        let params = self.parse_param_list()?;
+        let body = self.parse_function_body()?;
+        Ok(Macro::new(token, params, body))
+    }
+
+    // the loops around a function don't reach into it
+    fn parse_function_body(&mut self) -> ParseResult<Block> {
+        let loops = std::mem::take(&mut self.loops);
+        let body = self.parse_block();
+        self.loops = loops;
+        body
+    }
+
     fn parse_expression(&mut self, prec: i8) -> ParseResult<ExpressionNode> {
         if self.current.is_none() {
-            Err(ParseError {
-                expected: "Any Expression".into(),
-                found: self.current.take(),
-            })?;
+            Err(self.error("an expression"))?;
         }
         let mut left: ExpressionNode = match self.current.as_ref().unwrap().r#type {
-            TokenType::Ident(_) => self.parse_ident()?.into(),
+            TokenType::Ident(_) => {
+                let ident = self.parse_ident()?;
+                if self.struct_literals && self.check_token_type(TokenType::Lbrace).is_ok() {
+                    self.parse_struct(ident)?.into()
+                } else {
+                    ident.into()
+                }
+            }
             TokenType::Int(_) => self.parse_int()?.into(),
+            TokenType::Float(_) => self.parse_float()?.into(),
             TokenType::True | TokenType::False => self.parse_bool()?.into(),
-            TokenType::Lparen => {
-                self.advance_tokens();
-                let expr = self.parse_expression(0)?;
-                self.expect(TokenType::Rparen)?;
-                expr
-            }
+            TokenType::Lparen => self.with_struct_literals(true, Self::parse_group_or_tuple)?,
             TokenType::Plus | TokenType::Minus | TokenType::Bang => {
                 let operator = self.current.take().unwrap();
                 self.advance_tokens();
                 let operand = self.parse_expression(100)?;
                 UnaryOperator::new(operator, operand).into()
             }
-            TokenType::Lbrace => self.parse_block()?.into(),
+            TokenType::Str(_) => self.parse_str()?.into(),
+            TokenType::Lbrace => self.with_struct_literals(true, Self::parse_block_or_hash)?,
             TokenType::If => self.parse_if_else()?.into(),
             TokenType::Function => self.parse_function()?.into(),
+            TokenType::Macro => self.parse_macro()?.into(),
+            TokenType::Lbracket => self.with_struct_literals(true, Self::parse_array)?.into(),
+            TokenType::While => self.parse_while(None)?.into(),
+            TokenType::For => self.parse_for(None)?.into(),
+            TokenType::Label(_) => self.parse_labeled_loop()?,
+            TokenType::Match => self.parse_match()?.into(),
             // TokenType::Semicolon => return Ok(ExpressionNode::None),
-            _ => Err(ParseError {
-                expected: "Ident|Int|UnaryOperator|(|{|if|fn".into(),
-                found: self.current.take(),
-            })?,
+            _ => {
+                Err(self.error_with_reason("an expression", "an expression can't start with this"))?
+            }
         };
         let mut nop = get_prec_assoc(self.current.as_ref());
-        while prec <= nop {
+        // an operator is taken up only if it binds tighter than the one that called us
+        while prec < nop {
             if let Some(Token {
                 r#type: TokenType::Lparen,
                 ..
@@ -249,10 +1046,30 @@ impl Parser {
                 let token = self.current.clone().unwrap();
                 let args = self.parse_call_arg_list()?;
                 left = FunctionCall::new(token, left, args).into();
+            } else if let Some(Token {
+                r#type: TokenType::Lbracket,
+                ..
+            }) = self.current
+            {
+                left = self.parse_index(left)?.into();
+            } else if let Some(Token {
+                r#type: TokenType::Dot,
+                ..
+            }) = self.current
+            {
+                left = self.parse_field(left)?.into();
+            } else if self
+                .current
+                .as_ref()
+                .is_some_and(|t| t.r#type.is_assignment())
+            {
+                left = self.parse_assign(left, nop)?;
             } else {
                 let bop = self.current.take().unwrap();
                 self.advance_tokens();
-                let right = self.parse_expression(nop)?;
+                // stripping the associativity bump lets the right operand take up operators of the same precedence
+                // so left associative operators stop there and right associative ones keep going
+                let right = self.parse_expression(nop - nop % 10)?;
                 left = BinaryOperator::new(bop, left, right).into();
             }
 
@@ -263,51 +1080,46 @@ impl Parser {
     }
 }
 
-fn get_prec_assoc(op: Option<&Token>) -> i8 {
+pub(crate) fn get_prec_assoc(op: Option<&Token>) -> i8 {
     // Precedence + Associativity (left=0;right=5)
-    match op {
-        Some(Token {
-            r#type: TokenType::Plus,
-            ..
-        }) => 30 + 0,
-        Some(Token {
-            r#type: TokenType::Minus,
-            ..
-        }) => 30 + 0,
-        Some(Token {
-            r#type: TokenType::Asterisk,
-            ..
-        }) => 40 + 0,
-        Some(Token {
-            r#type: TokenType::Slash,
-            ..
-        }) => 40 + 0,
-
-        Some(Token {
-            r#type: TokenType::Eq,
-            ..
-        }) => 20 + 0,
-        Some(Token {
-            r#type: TokenType::NotEq,
-            ..
-        }) => 20 + 0,
-        Some(Token {
-            r#type: TokenType::GT,
-            ..
-        }) => 20 + 0,
-        Some(Token {
-            r#type: TokenType::LT,
-            ..
-        }) => 20 + 0,
-        Some(Token {
-            r#type: TokenType::Lparen,
-            ..
-        }) => 110 + 0,
-
-        Some(Token {
-            r#type: TokenType::Rparen,
-            ..
-        }) => -1 + 0,
+    let Some(op) = op else {
+        return -100 + 0;
+    };
+    match op.r#type {
+        TokenType::Assign
+        | TokenType::PlusAssign
+        | TokenType::MinusAssign
+        | TokenType::AsteriskAssign
+        | TokenType::SlashAssign => 0 + 5,
+        TokenType::Or => 10 + 0,
+        TokenType::And => 20 + 0,
+        TokenType::Eq
+        | TokenType::NotEq
+        | TokenType::GT
+        | TokenType::LT
+        | TokenType::GTEq
+        | TokenType::LTEq => 30 + 0,
+        TokenType::Pipe => 40 + 0,
+        TokenType::Caret => 50 + 0,
+        TokenType::Ampersand => 60 + 0,
+        TokenType::LShift | TokenType::RShift => 70 + 0,
+        TokenType::Plus | TokenType::Minus => 80 + 0,
+        TokenType::Asterisk | TokenType::Slash | TokenType::Percent => 90 + 0,
+        // above the operand of a unary operator, so -2 ** 2 is -(2 ** 2)
+        TokenType::DoubleAsterisk => 100 + 5,
+        TokenType::Lparen | TokenType::Lbracket | TokenType::Dot => 110 + 0,
+        TokenType::Rparen => -1 + 0,
         _ => -100 + 0,
     }
 }
+
+// the first literal (or variant) in a pattern, which a let can't have
+fn find_refutable(pattern: &Pattern) -> Option<&Pattern> {
+    match pattern {
+        Pattern::Literal(_) | Pattern::Variant(..) => Some(pattern),
+        Pattern::Array(_, patterns) | Pattern::Tuple(_, patterns) => {
+            patterns.iter().find_map(find_refutable)
+        }
+        Pattern::Wildcard(_) | Pattern::Binding(_) => None,
+    }
+}
@@ -0,0 +1,748 @@
+use std::fmt;
+
+use crate::ast::{
+    expression::{Block, Else, ExpressionNode, Param},
+    pattern::Pattern,
+    statement::StatementNode,
+    visit::Visitor,
+    Program,
+};
+use crate::lexer::Lexer;
+use crate::parser::{get_prec_assoc, ParseError, Parser};
+use crate::token::{token_type::TokenType, Pos, Span, Token, TriviaKind};
+
+// Canonical pretty printer
+//
+// * statements go on their own lines, ending with `;`
+//   except for the last expression of a block, which is the value of the block
+// * blocks are always broken over multiple lines, indented by 4 spaces
+// * parentheses are only added where the precedence table of the parser needs them
+// * literals are printed the way they were written (0xff stays 0xff)
+//
+// parsing the printed program gives back the same AST (modulo spans)
+//
+// `format_source` also carries over the comments and the blank lines between statements
+// a comment goes right before the statement (or the `}`) that came after it
+// or stays at the end of its line
+// a statement with a comment anywhere else in it (say, between two arguments) is kept as written
+
+const INDENT: &str = "    ";
+
+// formats a whole source file, or returns the errors if it doesn't parse
+pub fn format_source(source: &str) -> Result<String, Vec<ParseError>> {
+    let mut parser = Parser::new(Lexer::from_string(source.into()));
+    let (program, errors) = parser.parse_program();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut comments: Vec<Comment> = vec![];
+    let mut tokens = vec![];
+    // the comments from here on don't know the token after them yet
+    let mut waiting = 0;
+    for token in Lexer::from_string(source.into()).with_trivia() {
+        comments.extend(
+            token
+                .leading_trivia
+                .iter()
+                .filter(|trivia| trivia.kind == TriviaKind::Comment)
+                .map(|trivia| {
+                    let line_start = source[..trivia.span.0].rfind('\n').map_or(0, |i| i + 1);
+                    Comment {
+                        span: trivia.span,
+                        text: trivia.text(source).trim_end().to_string(),
+                        own_line: source[line_start..trivia.span.0].trim().is_empty(),
+                        before: 0,
+                    }
+                }),
+        );
+        // the `;`s are left out, the printer puts them back where they belong
+        if token.r#type == TokenType::Semicolon {
+            continue;
+        }
+        for comment in &mut comments[waiting..] {
+            comment.before = token.span.0;
+        }
+        waiting = comments.len();
+        tokens.push(token.span.0);
+    }
+
+    let mut printer = Printer::new(Some(source), comments);
+    printer.tokens = tokens;
+    printer.statement_spans = parser.statement_spans().to_vec();
+    printer.program(&program);
+    Ok(printer.out)
+}
+
+#[derive(Clone)]
+struct Comment {
+    span: Span,
+    text: String,
+    // nothing but whitespace before it on its line
+    own_line: bool,
+    // where the token after it starts (skipping the `;`s), the comment goes with that token
+    before: Pos,
+}
+
+struct Printer<'a> {
+    out: String,
+    indent: usize,
+    // only known when formatting a source file
+    source: Option<&'a str>,
+    // in the order they appear in the source, the ones before `next_comment` are already printed
+    comments: Vec<Comment>,
+    next_comment: usize,
+    // where the tokens start, but for the `;`s
+    tokens: Vec<Pos>,
+    // the spans of the statements from the parser, in the order they start
+    // those before `next_statement` belong to the statements already printed
+    statement_spans: Vec<Span>,
+    next_statement: usize,
+}
+
+impl<'a> Printer<'a> {
+    fn new(source: Option<&'a str>, comments: Vec<Comment>) -> Self {
+        Self {
+            out: String::new(),
+            indent: 0,
+            source,
+            comments,
+            next_comment: 0,
+            tokens: vec![],
+            statement_spans: vec![],
+            next_statement: 0,
+        }
+    }
+
+    fn newline(&mut self) {
+        self.out.push('\n');
+        self.out.push_str(&INDENT.repeat(self.indent));
+    }
+
+    fn program(&mut self, program: &Program) {
+        self.statements(&program.statements, false);
+        // the comments at the end of the file
+        self.own_line_comments(self.source.map_or(0, str::len));
+        // a file ends with a newline
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        // the statements are each preceded by a newline, so the output starts with one
+        self.out = self.out.trim_start_matches('\n').to_string();
+    }
+
+    // every statement goes on a new line
+    // returns where the last one ends in the source
+    fn statements(&mut self, stmts: &[StatementNode], is_block: bool) -> Option<Pos> {
+        let mut last_end = None;
+        for (i, stmt) in stmts.iter().enumerate() {
+            let span = self.span_of(stmt);
+            if let Some((start, _)) = span {
+                self.own_line_comments(start);
+                if i > 0 {
+                    self.blank_line(start);
+                }
+            }
+            self.newline();
+            let (printed, next_comment) = (self.out.len(), self.next_comment);
+            self.statement(stmt);
+
+            if let Some((start, end)) = span {
+                // there's a comment in it that would end up somewhere else
+                if self.next_comment_if(|comment| (start..end).contains(&comment.span.0)) {
+                    self.out.truncate(printed);
+                    self.next_comment = next_comment;
+                    self.verbatim(start, end);
+                }
+            }
+
+            // a function (struct, enum) declaration ends with its `}`, like it does in the source
+            let is_tail = is_block && i == stmts.len() - 1;
+            let is_declaration = matches!(
+                stmt.without_export(),
+                StatementNode::Function(..) | StatementNode::Struct(..) | StatementNode::Enum(..)
+            );
+            if !is_declaration && !(is_tail && matches!(stmt, StatementNode::Expression(_))) {
+                self.out.push(';');
+            }
+
+            if let Some((_, end)) = span {
+                self.trailing_comment(end);
+                last_end = Some(end);
+            }
+        }
+        last_end
+    }
+
+    // where a statement starts and ends in the source
+    // with the brackets around its parts and its `;`
+    // going by the spans the parser recorded (when formatting a source file), or by its tokens
+    fn span_of(&mut self, stmt: &StatementNode) -> Option<Span> {
+        let (start, end) = statement_span(stmt)?;
+        // the statements are printed in the order they were parsed
+        // so the first one after the ones already printed that has all the tokens is this one
+        let found = self.statement_spans[self.next_statement..]
+            .iter()
+            .position(|span| span.0 <= start && end <= span.1);
+        let Some(i) = found else {
+            return Some((start, end));
+        };
+        self.next_statement += i + 1;
+        Some(self.statement_spans[self.next_statement - 1])
+    }
+
+    // copies the statement from the source, along with the comments in it
+    fn verbatim(&mut self, start: Pos, end: Pos) {
+        let source = self.source.unwrap_or_default();
+        let text = &source[start..end];
+        // the `;` is added back after it, like for any other statement
+        let text = text.strip_suffix(';').unwrap_or(text).trim_end();
+        self.out.push_str(text);
+        while self.next_comment_if(|comment| comment.span.0 < end) {
+            self.next_comment += 1;
+        }
+        while self
+            .statement_spans
+            .get(self.next_statement)
+            .is_some_and(|span| span.0 < end)
+        {
+            self.next_statement += 1;
+        }
+    }
+
+    // whether the next comment to print is one that `wanted` says goes here
+    fn next_comment_if(&self, wanted: impl Fn(&Comment) -> bool) -> bool {
+        self.comments.get(self.next_comment).is_some_and(wanted)
+    }
+
+    fn take_comment(&mut self) -> Comment {
+        self.next_comment += 1;
+        self.comments[self.next_comment - 1].clone()
+    }
+
+    // keeps a blank line that was right before `pos` in the source (a single one, even if there were more)
+    fn blank_line(&mut self, pos: usize) {
+        let Some(source) = self.source else {
+            return;
+        };
+        let before = &source[..pos];
+        let whitespace = &before[before.trim_end().len()..];
+        let already_there = self.out.trim_end_matches(INDENT).ends_with("\n\n");
+        if whitespace.matches('\n').count() > 1 && !already_there && !self.out.is_empty() {
+            self.out.push('\n');
+        }
+    }
+
+    // the comments that go with the token at `pos`, each on its own line
+    fn own_line_comments(&mut self, pos: Pos) {
+        while self.next_comment_if(|comment| comment.before == pos) {
+            let comment = self.take_comment();
+            self.blank_line(comment.span.0);
+            self.newline();
+            self.out.push_str(&comment.text);
+        }
+    }
+
+    // a comment on the same line as the end of a statement stays there
+    fn trailing_comment(&mut self, end: Pos) {
+        let source = self.source.unwrap_or_default();
+        let right_after = |comment: &Comment| {
+            !comment.own_line
+                && comment.span.0 >= end
+                && source[end..comment.span.0]
+                    .trim_start_matches([' ', '\t', ';'])
+                    .is_empty()
+        };
+        if self.next_comment_if(right_after) {
+            let comment = self.take_comment();
+            self.out.push_str("  ");
+            self.out.push_str(&comment.text);
+        }
+    }
+
+    fn statement(&mut self, stmt: &StatementNode) {
+        match stmt {
+            StatementNode::Let(_, ident, expr) => {
+                self.out
+                    .push_str(if stmt.is_const() { "const " } else { "let " });
+                self.out.push_str(ident.get_symbol().as_str());
+                self.out.push_str(" = ");
+                self.expression(expr);
+            }
+            StatementNode::Destructure(_, pattern, expr) => {
+                self.out
+                    .push_str(if stmt.is_const() { "const " } else { "let " });
+                self.pattern(pattern);
+                self.out.push_str(" = ");
+                self.expression(expr);
+            }
+            StatementNode::Return(_, expr) => {
+                self.out.push_str("return ");
+                self.expression(expr);
+            }
+            StatementNode::Expression(expr) => self.expression(expr),
+            StatementNode::Function(_, name, params, body) => {
+                self.out.push_str("fn ");
+                self.out.push_str(name.get_symbol().as_str());
+                self.function(params, body);
+            }
+            StatementNode::Struct(_, name, fields) => {
+                self.out.push_str("struct ");
+                self.out.push_str(name.get_symbol().as_str());
+                if fields.is_empty() {
+                    self.out.push_str(" {}");
+                    return;
+                }
+                self.out.push_str(" { ");
+                for (i, field) in fields.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.out.push_str(field.get_symbol().as_str());
+                }
+                self.out.push_str(" }");
+            }
+            StatementNode::Enum(_, name, variants) => {
+                self.out.push_str("enum ");
+                self.out.push_str(name.get_symbol().as_str());
+                if variants.is_empty() {
+                    self.out.push_str(" {}");
+                    return;
+                }
+                self.out.push_str(" { ");
+                for (i, (variant, fields)) in variants.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.out.push_str(variant.get_symbol().as_str());
+                    if fields.is_empty() {
+                        continue;
+                    }
+                    self.out.push('(');
+                    for (i, field) in fields.iter().enumerate() {
+                        if i > 0 {
+                            self.out.push_str(", ");
+                        }
+                        self.out.push_str(field.get_symbol().as_str());
+                    }
+                    self.out.push(')');
+                }
+                self.out.push_str(" }");
+            }
+            StatementNode::Import(token, path) => {
+                self.token(token);
+                self.out.push(' ');
+                self.token(path);
+            }
+            StatementNode::Export(token, stmt) => {
+                self.token(token);
+                self.out.push(' ');
+                self.statement(stmt);
+            }
+            StatementNode::Break(token, label) | StatementNode::Continue(token, label) => {
+                self.token(token);
+                if let Some(label) = label {
+                    self.out.push(' ');
+                    self.token(label);
+                }
+            }
+            // the source of a statement that failed to parse isn't part of the AST
+            StatementNode::Error(_) => self.out.push_str("<error>"),
+        }
+    }
+
+    fn block(&mut self, stmts: &[StatementNode]) {
+        if stmts.is_empty() {
+            self.out.push_str("{}");
+            return;
+        }
+        self.out.push('{');
+        self.indent += 1;
+        // comments at the end of the block belong inside it, they go with its `}`
+        // which is the token right after the last statement
+        if let Some(end) = self.statements(stmts, true) {
+            let close = self.tokens.partition_point(|&pos| pos < end);
+            if let Some(&close) = self.tokens.get(close) {
+                self.own_line_comments(close);
+            }
+        }
+        self.indent -= 1;
+        self.newline();
+        self.out.push('}');
+    }
+
+    fn expression(&mut self, expr: &ExpressionNode) {
+        match expr {
+            ExpressionNode::Ident(ident) => self.token(ident.token()),
+            ExpressionNode::Int(token)
+            | ExpressionNode::Float(token)
+            | ExpressionNode::Bool(token)
+            | ExpressionNode::Str(token) => self.token(token),
+            ExpressionNode::UnaryOperator(operator, operand) => {
+                self.token(operator);
+                // the operand of a unary operator only takes up calls and **
+                // -(-x) keeps its parentheses, so that it doesn't look like --x
+                let same_operator = matches!(
+                    operand.as_ref(),
+                    ExpressionNode::UnaryOperator(inner, _) if inner.r#type == operator.r#type && operator.r#type != TokenType::Bang
+                );
+                self.operand(operand, binding_power(operand) < UNARY || same_operator);
+            }
+            ExpressionNode::BinaryOperator(operator, lhs, rhs) => {
+                let prec = get_prec_assoc(Some(operator));
+                let (base, right_assoc) = (prec - prec % 10, prec % 10 != 0);
+                let (lhs_bp, rhs_bp) = (binding_power(lhs), binding_power(rhs));
+                self.operand(lhs, lhs_bp < base || (lhs_bp == base && right_assoc));
+                self.out.push(' ');
+                self.token(operator);
+                self.out.push(' ');
+                self.operand(rhs, rhs_bp < base || (rhs_bp == base && !right_assoc));
+            }
+            ExpressionNode::Block(block) => self.block(&block.statements),
+            ExpressionNode::If(_, condition, action, alternate) => {
+                self.out.push_str("if ");
+                self.head(condition);
+                self.out.push(' ');
+                self.block(&action.statements);
+                match alternate {
+                    Some(Else::Block(Block { statements })) => {
+                        self.out.push_str(" else ");
+                        self.block(statements);
+                    }
+                    Some(Else::If(alternate)) => {
+                        self.out.push_str(" else ");
+                        self.expression(alternate);
+                    }
+                    None => {}
+                }
+            }
+            ExpressionNode::Function(_, args, body) => {
+                self.out.push_str("fn");
+                self.function(args, body);
+            }
+            ExpressionNode::Macro(_, params, body) => {
+                self.out.push_str("macro");
+                self.function(params, body);
+            }
+            ExpressionNode::FunctionCall(_, function, args) => {
+                self.operand(function, binding_power(function) < CALL);
+                self.out.push('(');
+                self.list(args);
+                self.out.push(')');
+            }
+            ExpressionNode::Array(_, elements) => {
+                self.out.push('[');
+                self.list(elements);
+                self.out.push(']');
+            }
+            // `(a,)`, without the comma it would just be `a`
+            ExpressionNode::Tuple(_, elements) => {
+                self.out.push('(');
+                self.list(elements);
+                if elements.len() == 1 {
+                    self.out.push(',');
+                }
+                self.out.push(')');
+            }
+            ExpressionNode::Index(_, collection, index) => {
+                self.operand(collection, binding_power(collection) < CALL);
+                self.out.push('[');
+                self.expression(index);
+                self.out.push(']');
+            }
+            ExpressionNode::Assign(operator, target, value) => {
+                self.out.push_str(target.get_symbol().as_str());
+                self.out.push(' ');
+                self.token(operator);
+                self.out.push(' ');
+                self.expression(value);
+            }
+            ExpressionNode::AssignField(operator, target, value) => {
+                self.expression(target);
+                self.out.push(' ');
+                self.token(operator);
+                self.out.push(' ');
+                self.expression(value);
+            }
+            // `Point { x: 1, y }`, where `y` is short for `y: y`
+            ExpressionNode::Struct(_, name, fields) => {
+                self.out.push_str(name.get_symbol().as_str());
+                if fields.is_empty() {
+                    self.out.push_str(" {}");
+                    return;
+                }
+                self.out.push_str(" { ");
+                for (i, (field, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.out.push_str(field.get_symbol().as_str());
+                    let shorthand = matches!(
+                        value,
+                        ExpressionNode::Ident(ident) if ident.get_symbol() == field.get_symbol()
+                    );
+                    if !shorthand {
+                        self.out.push_str(": ");
+                        self.expression(value);
+                    }
+                }
+                self.out.push_str(" }");
+            }
+            ExpressionNode::Field(_, value, field) => {
+                self.operand(value, binding_power(value) < CALL);
+                self.out.push('.');
+                self.out.push_str(field.get_symbol().as_str());
+            }
+            ExpressionNode::While(_, label, condition, body) => {
+                self.label(label.as_ref());
+                self.out.push_str("while ");
+                self.head(condition);
+                self.out.push(' ');
+                self.block(&body.statements);
+            }
+            ExpressionNode::For(_, label, var, iterable, body) => {
+                self.label(label.as_ref());
+                self.out.push_str("for ");
+                self.out.push_str(var.get_symbol().as_str());
+                self.out.push_str(" in ");
+                self.head(iterable);
+                self.out.push(' ');
+                self.block(&body.statements);
+            }
+            ExpressionNode::Hash(_, entries) => {
+                self.out.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.expression(key);
+                    self.out.push_str(": ");
+                    self.expression(value);
+                }
+                self.out.push('}');
+            }
+            // every arm on its own line, each followed by a comma
+            ExpressionNode::Match(_, value, arms) => {
+                self.out.push_str("match ");
+                self.head(value);
+                if arms.is_empty() {
+                    self.out.push_str(" {}");
+                    return;
+                }
+                self.out.push_str(" {");
+                self.indent += 1;
+                for arm in arms {
+                    self.newline();
+                    self.pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.out.push_str(" if ");
+                        self.expression(guard);
+                    }
+                    self.out.push_str(" => ");
+                    self.expression(&arm.body);
+                    self.out.push(',');
+                }
+                self.indent -= 1;
+                self.newline();
+                self.out.push('}');
+            }
+        }
+    }
+
+    // the params and the body of a function
+    fn function(&mut self, params: &[Param], body: &Block) {
+        self.out.push('(');
+        for (i, param) in params.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            if param.rest.is_some() {
+                self.out.push_str("...");
+            }
+            self.out.push_str(param.ident.get_symbol().as_str());
+            if let Some(default) = &param.default {
+                self.out.push_str(" = ");
+                self.expression(default);
+            }
+        }
+        self.out.push_str(") ");
+        self.block(&body.statements);
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Wildcard(token) => self.token(token),
+            Pattern::Binding(ident) => self.out.push_str(ident.get_symbol().as_str()),
+            Pattern::Literal(literal) => self.expression(literal),
+            Pattern::Array(_, patterns) => {
+                self.out.push('[');
+                for (i, pattern) in patterns.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.pattern(pattern);
+                }
+                self.out.push(']');
+            }
+            Pattern::Tuple(_, patterns) => {
+                self.out.push('(');
+                for (i, pattern) in patterns.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.pattern(pattern);
+                }
+                if patterns.len() == 1 {
+                    self.out.push(',');
+                }
+                self.out.push(')');
+            }
+            Pattern::Variant(name, variant, patterns) => {
+                self.out.push_str(name.get_symbol().as_str());
+                self.out.push('.');
+                self.out.push_str(variant.get_symbol().as_str());
+                if patterns.is_empty() {
+                    return;
+                }
+                self.out.push('(');
+                for (i, pattern) in patterns.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.pattern(pattern);
+                }
+                self.out.push(')');
+            }
+        }
+    }
+
+    fn label(&mut self, label: Option<&Token>) {
+        if let Some(label) = label {
+            self.token(label);
+            self.out.push_str(": ");
+        }
+    }
+
+    fn list(&mut self, exprs: &[ExpressionNode]) {
+        for (i, expr) in exprs.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.expression(expr);
+        }
+    }
+
+    // the expression before the block of an if, a while, a for or a match
+    fn head(&mut self, expr: &ExpressionNode) {
+        self.operand(expr, has_bare_struct(expr));
+    }
+
+    fn operand(&mut self, expr: &ExpressionNode, parenthesize: bool) {
+        if parenthesize {
+            self.out.push('(');
+            self.expression(expr);
+            self.out.push(')');
+        } else {
+            self.expression(expr);
+        }
+    }
+
+    fn token(&mut self, token: &Token) {
+        let text = match &token.r#type {
+            TokenType::Ident(s) | TokenType::Label(s) => s.as_str(),
+            TokenType::Int(s) | TokenType::Float(s) | TokenType::Str(s) => s,
+            tt => tt.lexeme().unwrap_or_default(),
+        };
+        self.out.push_str(text);
+    }
+}
+
+// the precedence the parser gives to the operand of a unary operator, and to a call (or an index)
+// see Parser::parse_expression
+const UNARY: i8 = 100;
+const CALL: i8 = 110;
+
+// how tightly an expression holds together
+// i.e, the precedence of the operator at its root, anything that isn't an operator can't be broken up
+fn binding_power(expr: &ExpressionNode) -> i8 {
+    match expr {
+        ExpressionNode::BinaryOperator(operator, ..)
+        | ExpressionNode::Assign(operator, ..)
+        | ExpressionNode::AssignField(operator, ..) => {
+            let prec = get_prec_assoc(Some(operator));
+            prec - prec % 10
+        }
+        ExpressionNode::UnaryOperator(..) => UNARY,
+        _ => i8::MAX,
+    }
+}
+
+// whether a struct literal in `expr` is outside of any brackets
+// which before the block of an if (and the like) only parses in parentheses
+fn has_bare_struct(expr: &ExpressionNode) -> bool {
+    match expr {
+        ExpressionNode::Struct(..) => true,
+        ExpressionNode::UnaryOperator(_, operand) => has_bare_struct(operand),
+        ExpressionNode::BinaryOperator(_, lhs, rhs) | ExpressionNode::AssignField(_, lhs, rhs) => {
+            has_bare_struct(lhs) || has_bare_struct(rhs)
+        }
+        ExpressionNode::Assign(_, _, value) => has_bare_struct(value),
+        ExpressionNode::Field(_, value, _)
+        | ExpressionNode::FunctionCall(_, value, _)
+        | ExpressionNode::Index(_, value, _) => has_bare_struct(value),
+        _ => false,
+    }
+}
+
+// where a statement starts and ends in the source, going by the tokens in it
+// a statement without tokens (like an empty block) doesn't have one
+fn statement_span(stmt: &StatementNode) -> Option<Span> {
+    let mut extent = Extent(None);
+    extent.visit_statement(stmt);
+    extent.0
+}
+
+struct Extent(Option<Span>);
+
+impl Extent {
+    fn cover(&mut self, span: Span) {
+        self.0 = Some(match self.0 {
+            Some((start, end)) => (start.min(span.0), end.max(span.1)),
+            None => span,
+        });
+    }
+}
+
+impl Visitor for Extent {
+    fn visit_token(&mut self, token: &Token) {
+        self.cover(token.span);
+    }
+
+    fn visit_error(&mut self, span: Span) {
+        self.cover(span);
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut printer = Printer::new(None, vec![]);
+        printer.program(self);
+        f.write_str(&printer.out)
+    }
+}
+
+impl fmt::Display for StatementNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut printer = Printer::new(None, vec![]);
+        printer.statement(self);
+        f.write_str(&printer.out)
+    }
+}
+
+impl fmt::Display for ExpressionNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut printer = Printer::new(None, vec![]);
+        printer.expression(self);
+        f.write_str(&printer.out)
+    }
+}
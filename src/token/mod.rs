@@ -1,25 +1,49 @@
-pub(crate) mod token_type;
+pub mod symbol;
+pub mod token_type;
 
+use symbol::Symbol;
 use token_type::TokenType;
 
 // currently Pos is just the offset in the file
 // where the token in question is found
 // you can make it more sophisticated by making it a tuple of line number + offset in the line
-type Pos = usize;
+pub type Pos = usize;
 // position of first character of the token + that of the last one
-type Span = (Pos, Pos);
+pub type Span = (Pos, Pos);
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Token {
     pub r#type: TokenType,
     pub span: Span,
-    // you can add the filename later on
-    // but for now, all the programs in the target language will into a single file
+    // there's no filename, the spans of a program loaded from many files
+    // tell which one they're in by where they are (see module::Loader)
+
+    // whitespace and comments found between the previous token and this one
+    // only filled in when the lexer is asked to keep them (see `Lexer::with_trivia`)
+    pub leading_trivia: Vec<Trivia>,
 }
 
 impl Token {
     pub fn new(r#type: TokenType, span: Span) -> Self {
-        Self { r#type, span }
+        Self {
+            r#type,
+            span,
+            leading_trivia: vec![],
+        }
+    }
+
+    // moves the token (and its trivia) by `delta` bytes
+    // used when an edit earlier in the source shifts everything after it
+    pub(crate) fn shift(&mut self, delta: isize) {
+        self.span = shift_span(self.span, delta);
+        for trivia in &mut self.leading_trivia {
+            trivia.span = shift_span(trivia.span, delta);
+        }
+    }
+
+    // the exact text of the token in the source it was lexed from
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.span.0..self.span.1]
     }
 }
 
@@ -28,16 +52,151 @@ impl Default for Token {
         Token {
             r#type: TokenType::Dummy,
             span: (0, 0),
+            leading_trivia: vec![],
+        }
+    }
+}
+
+// trivia is mostly empty, and printing it for every token in an AST dump is just noise
+impl std::fmt::Debug for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut d = f.debug_struct("Token");
+        d.field("type", &self.r#type).field("span", &self.span);
+        if !self.leading_trivia.is_empty() {
+            d.field("leading_trivia", &self.leading_trivia);
         }
+        d.finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    Whitespace,
+    // `//` till the end of the line, the newline itself is whitespace
+    Comment,
+}
+
+#[derive(Debug, Clone)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub span: Span,
+}
+
+impl Trivia {
+    pub fn new(kind: TriviaKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.span.0..self.span.1]
     }
 }
 
 impl Token {
-    pub fn get_ident_name(self) -> Option<String> {
+    pub fn get_ident_name(&self) -> Option<Symbol> {
         if let TokenType::Ident(ident) = self.r#type {
             Some(ident)
         } else {
             None
         }
     }
+
+    pub fn get_label_name(&self) -> Option<Symbol> {
+        if let TokenType::Label(label) = self.r#type {
+            Some(label)
+        } else {
+            None
+        }
+    }
+}
+
+pub(crate) fn shift_span(span: Span, delta: isize) -> Span {
+    (
+        span.0.wrapping_add_signed(delta),
+        span.1.wrapping_add_signed(delta),
+    )
+}
+
+// converts the lexeme of an integer literal to its value
+// supports the 0x, 0b and 0o prefixes and `_` as a digit separator
+pub(crate) fn int_literal_value(lexeme: &str) -> Result<isize, String> {
+    let (radix, digits) = match lexeme.get(..2) {
+        Some("0x") => (16, &lexeme[2..]),
+        Some("0b") => (2, &lexeme[2..]),
+        Some("0o") => (8, &lexeme[2..]),
+        _ => (10, lexeme),
+    };
+    let digits: String = digits.chars().filter(|&c| c != '_').collect();
+    if digits.is_empty() {
+        return Err(format!("integer literal `{lexeme}` has no digits"));
+    }
+    if let Some(c) = digits.chars().find(|c| !c.is_digit(radix)) {
+        return Err(format!(
+            "invalid digit `{c}` in base {radix} integer literal `{lexeme}`"
+        ));
+    }
+    // the only error left at this point is the literal not fitting in an isize
+    isize::from_str_radix(&digits, radix).map_err(|_| {
+        format!(
+            "integer literal `{lexeme}` is out of range, the maximum is {}",
+            isize::MAX
+        )
+    })
+}
+
+// converts the lexeme of a string literal to its value
+// i.e, strips the quotes and replaces the escapes: \n \t \r \0 \\ \"
+pub(crate) fn string_literal_value(lexeme: &str) -> Result<String, String> {
+    let mut chars = lexeme.chars().skip(1);
+    let mut value = String::new();
+    loop {
+        let c = match chars.next() {
+            // the lexer makes sure that the closing quote is the last char
+            Some('"') => return Ok(value),
+            Some('\\') => match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some('0') => '\0',
+                Some(c @ ('\\' | '"')) => c,
+                Some(c) => return Err(format!("unknown escape `\\{c}` in string literal")),
+                None => break,
+            },
+            Some(c) => c,
+            None => break,
+        };
+        value.push(c);
+    }
+    Err("unterminated string literal, the closing `\"` is missing".into())
+}
+
+// the other way around, the lexeme of a string literal with the value `value`
+pub(crate) fn string_literal(value: &str) -> String {
+    let mut lexeme = String::from('"');
+    for c in value.chars() {
+        match c {
+            '\n' => lexeme.push_str("\\n"),
+            '\t' => lexeme.push_str("\\t"),
+            '\r' => lexeme.push_str("\\r"),
+            '\0' => lexeme.push_str("\\0"),
+            '\\' | '"' => {
+                lexeme.push('\\');
+                lexeme.push(c);
+            }
+            c => lexeme.push(c),
+        }
+    }
+    lexeme.push('"');
+    lexeme
+}
+
+// converts the lexeme of a float literal to its value
+// literals too big to be represented (like 1e400) are rejected instead of becoming infinity
+pub(crate) fn float_literal_value(lexeme: &str) -> Result<f64, String> {
+    let digits: String = lexeme.chars().filter(|&c| c != '_').collect();
+    match digits.parse::<f64>() {
+        Ok(float) if float.is_finite() => Ok(float),
+        Ok(_) => Err(format!("float literal `{lexeme}` is out of range")),
+        Err(err) => Err(format!("invalid float literal `{lexeme}`: {err}")),
+    }
 }
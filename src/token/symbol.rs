@@ -0,0 +1,112 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+// An interned string
+// identifiers (and labels) are stored as symbols in the tokens
+// so lexing doesn't allocate a String per name and cloning a token is cheap
+// every distinct name is allocated only once, the first time it's seen
+// the literals keep their own text (see TokenType), they would only make the table grow
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+// the interned strings are leaked and live till the end of the program
+// the names in the programs a process sees are few, so this is fine for an interpreter
+struct Interner {
+    map: HashMap<&'static str, Symbol>,
+    strings: Vec<&'static str>,
+}
+
+// one per thread, so getting the string of a symbol doesn't have to take a lock
+// a symbol is only meaningful on the thread that interned it, the same as an Rc
+thread_local! {
+    static INTERNER: RefCell<Interner> = RefCell::new({
+        let mut interner = Interner {
+            map: HashMap::new(),
+            strings: vec![],
+        };
+        // so that Symbol::EMPTY is always there
+        interner.intern("");
+        interner
+    });
+}
+
+impl Interner {
+    fn intern(&mut self, s: &str) -> Symbol {
+        if let Some(&sym) = self.map.get(s) {
+            return sym;
+        }
+        let s: &'static str = Box::leak(s.into());
+        let sym = Symbol(self.strings.len() as u32);
+        self.strings.push(s);
+        self.map.insert(s, sym);
+        sym
+    }
+}
+
+impl Symbol {
+    // the default symbol, useful as a placeholder when only the kind of the token matters
+    pub const EMPTY: Symbol = Symbol(0);
+
+    pub fn intern(s: &str) -> Self {
+        INTERNER.with_borrow_mut(|interner| interner.intern(s))
+    }
+
+    // a symbol of its own with the text of `name`, equal to no other symbol
+    // the macros rename what they bind to these (see evaluation::macros)
+    // so the names can't clash with the ones around them, but are still shown as written
+    pub fn fresh(name: Symbol) -> Self {
+        INTERNER.with_borrow_mut(|interner| {
+            let sym = Symbol(interner.strings.len() as u32);
+            interner.strings.push(interner.strings[name.0 as usize]);
+            sym
+        })
+    }
+
+    pub fn as_str(self) -> &'static str {
+        INTERNER.with_borrow(|interner| interner.strings[self.0 as usize])
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(value: &str) -> Self {
+        Symbol::intern(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interning_the_same_name_gives_the_same_symbol() {
+        let one = Symbol::intern("counter");
+        let two = Symbol::intern(&String::from("counter"));
+        assert_eq!(one, two);
+        assert_ne!(one, Symbol::intern("count"));
+        assert_eq!(one.as_str(), "counter");
+        assert_eq!(Symbol::EMPTY.as_str(), "");
+    }
+
+    #[test]
+    fn a_fresh_symbol_has_the_same_text_but_is_another_symbol() {
+        let name = Symbol::intern("tmp");
+        let fresh = Symbol::fresh(name);
+        assert_ne!(fresh, name);
+        assert_ne!(fresh, Symbol::fresh(name));
+        assert_eq!(fresh.as_str(), "tmp");
+        assert_eq!(Symbol::intern("tmp"), name);
+    }
+}
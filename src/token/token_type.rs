@@ -1,11 +1,23 @@
+use std::rc::Rc;
+
+use super::symbol::Symbol;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TokenType {
     Dummy,
     Illegal(char),
     Eof,
 
-    Ident(String),
-    Int(String), // remains a string as I don't want to "parse" the data till the parse step
+    // interned, names come back over and over so that saves an allocation per lexeme
+    Ident(Symbol),
+    // literals keep their own text, most of them are only there once
+    // and interning them would keep every one that was ever lexed around (say, while typing)
+    Int(Rc<str>), // remains a string as I don't want to "parse" the data till the parse step
+    Float(Rc<str>),
+    // the whole literal, quotes and escapes included
+    Str(Rc<str>),
+    // a loop label, `'outer`, the quote included
+    Label(Symbol),
 
     // SingleOperator
     Assign,
@@ -13,30 +25,183 @@ pub enum TokenType {
     Minus,
     Asterisk,
     Slash,
+    Percent,
     LT,
     GT,
     Bang,
+    Ampersand,
+    Pipe,
+    Caret,
 
     // DoubleOperator
     Eq,
     NotEq,
+    LTEq,
+    GTEq,
+    DoubleAsterisk,
+    LShift,
+    RShift,
+    PlusAssign,
+    MinusAssign,
+    AsteriskAssign,
+    SlashAssign,
+    And,
+    Or,
+    FatArrow,
 
     // Delimiter
     Comma,
+    Colon,
+    // in front of the name of a field
+    Dot,
     Semicolon,
     Lparen,
     Rparen,
     Lbrace,
     Rbrace,
+    Lbracket,
+    Rbracket,
+    // the wildcard pattern
+    Underscore,
+    // in front of a rest parameter
+    Ellipsis,
 
     // Keyword
     Function,
     Let,
+    Const,
+    Struct,
+    Enum,
     True,
     False,
     If,
     Else,
     Return,
+    While,
+    For,
+    In,
+    Break,
+    Continue,
+    Match,
+    Macro,
+    Import,
+    Export,
+}
+
+impl TokenType {
+    // the source text of the tokens that are always spelled the same way
+    pub fn lexeme(&self) -> Option<&'static str> {
+        let text = match self {
+            TokenType::Assign => "=",
+            TokenType::Plus => "+",
+            TokenType::Minus => "-",
+            TokenType::Asterisk => "*",
+            TokenType::Slash => "/",
+            TokenType::Percent => "%",
+            TokenType::LT => "<",
+            TokenType::GT => ">",
+            TokenType::Bang => "!",
+            TokenType::Ampersand => "&",
+            TokenType::Pipe => "|",
+            TokenType::Caret => "^",
+            TokenType::Eq => "==",
+            TokenType::NotEq => "!=",
+            TokenType::LTEq => "<=",
+            TokenType::GTEq => ">=",
+            TokenType::DoubleAsterisk => "**",
+            TokenType::LShift => "<<",
+            TokenType::RShift => ">>",
+            TokenType::PlusAssign => "+=",
+            TokenType::MinusAssign => "-=",
+            TokenType::AsteriskAssign => "*=",
+            TokenType::SlashAssign => "/=",
+            TokenType::And => "&&",
+            TokenType::Or => "||",
+            TokenType::FatArrow => "=>",
+            TokenType::Comma => ",",
+            TokenType::Colon => ":",
+            TokenType::Dot => ".",
+            TokenType::Semicolon => ";",
+            TokenType::Lparen => "(",
+            TokenType::Rparen => ")",
+            TokenType::Lbrace => "{",
+            TokenType::Rbrace => "}",
+            TokenType::Lbracket => "[",
+            TokenType::Rbracket => "]",
+            TokenType::Underscore => "_",
+            TokenType::Ellipsis => "...",
+            TokenType::Function => "fn",
+            TokenType::Let => "let",
+            TokenType::Const => "const",
+            TokenType::Struct => "struct",
+            TokenType::Enum => "enum",
+            TokenType::True => "true",
+            TokenType::False => "false",
+            TokenType::If => "if",
+            TokenType::Else => "else",
+            TokenType::Return => "return",
+            TokenType::While => "while",
+            TokenType::For => "for",
+            TokenType::In => "in",
+            TokenType::Break => "break",
+            TokenType::Continue => "continue",
+            TokenType::Match => "match",
+            TokenType::Macro => "macro",
+            TokenType::Import => "import",
+            TokenType::Export => "export",
+            TokenType::Dummy
+            | TokenType::Illegal(_)
+            | TokenType::Eof
+            | TokenType::Ident(_)
+            | TokenType::Int(_)
+            | TokenType::Float(_)
+            | TokenType::Str(_)
+            | TokenType::Label(_) => return None,
+        };
+        Some(text)
+    }
+}
+
+impl TokenType {
+    pub fn is_assignment(&self) -> bool {
+        *self == TokenType::Assign || self.compound_operator().is_some()
+    }
+
+    // the operator a compound assignment applies, `+` for `+=`
+    pub fn compound_operator(&self) -> Option<TokenType> {
+        let tt = match self {
+            TokenType::PlusAssign => TokenType::Plus,
+            TokenType::MinusAssign => TokenType::Minus,
+            TokenType::AsteriskAssign => TokenType::Asterisk,
+            TokenType::SlashAssign => TokenType::Slash,
+            _ => return None,
+        };
+        Some(tt)
+    }
+}
+
+impl std::fmt::Display for TokenType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(text) = self.lexeme() {
+            return write!(f, "`{text}`");
+        }
+        match self {
+            TokenType::Eof => f.write_str("end of input"),
+            TokenType::Illegal(c) => write!(f, "illegal character `{c}`"),
+            // the placeholders used to look for a kind of token are printed without a value
+            TokenType::Ident(s) if *s == Symbol::EMPTY => f.write_str("identifier"),
+            TokenType::Int(s) if s.is_empty() => f.write_str("integer"),
+            TokenType::Float(s) if s.is_empty() => f.write_str("float"),
+            TokenType::Str(s) if s.is_empty() => f.write_str("string"),
+            TokenType::Label(s) if *s == Symbol::EMPTY => f.write_str("label"),
+            TokenType::Ident(s) => write!(f, "identifier `{s}`"),
+            TokenType::Int(s) => write!(f, "integer `{s}`"),
+            TokenType::Float(s) => write!(f, "float `{s}`"),
+            TokenType::Str(s) => write!(f, "string `{s}`"),
+            TokenType::Label(s) => write!(f, "label `{s}`"),
+            _ => f.write_str("<dummy>"),
+        }
+    }
 }
 
 pub(crate) fn tt_single_operators(c: char) -> Option<TokenType> {
@@ -46,9 +211,13 @@ pub(crate) fn tt_single_operators(c: char) -> Option<TokenType> {
         '-' => TokenType::Minus,
         '*' => TokenType::Asterisk,
         '/' => TokenType::Slash,
+        '%' => TokenType::Percent,
         '<' => TokenType::LT,
         '>' => TokenType::GT,
         '!' => TokenType::Bang,
+        '&' => TokenType::Ampersand,
+        '|' => TokenType::Pipe,
+        '^' => TokenType::Caret,
         _ => return None,
     };
     Some(tt)
@@ -58,6 +227,18 @@ pub(crate) fn tt_double_operators(c0: char, c1: char) -> Option<TokenType> {
     let tt = match (c0, c1) {
         ('=', '=') => TokenType::Eq,
         ('!', '=') => TokenType::NotEq,
+        ('<', '=') => TokenType::LTEq,
+        ('>', '=') => TokenType::GTEq,
+        ('*', '*') => TokenType::DoubleAsterisk,
+        ('<', '<') => TokenType::LShift,
+        ('>', '>') => TokenType::RShift,
+        ('+', '=') => TokenType::PlusAssign,
+        ('-', '=') => TokenType::MinusAssign,
+        ('*', '=') => TokenType::AsteriskAssign,
+        ('/', '=') => TokenType::SlashAssign,
+        ('&', '&') => TokenType::And,
+        ('|', '|') => TokenType::Or,
+        ('=', '>') => TokenType::FatArrow,
         _ => return None,
     };
     Some(tt)
@@ -66,11 +247,16 @@ pub(crate) fn tt_double_operators(c0: char, c1: char) -> Option<TokenType> {
 pub(crate) fn tt_delimiters(c: char) -> Option<TokenType> {
     let tt = match c {
         ',' => TokenType::Comma,
+        ':' => TokenType::Colon,
+        '.' => TokenType::Dot,
         ';' => TokenType::Semicolon,
         '(' => TokenType::Lparen,
         ')' => TokenType::Rparen,
         '{' => TokenType::Lbrace,
         '}' => TokenType::Rbrace,
+        '[' => TokenType::Lbracket,
+        ']' => TokenType::Rbracket,
+        '_' => TokenType::Underscore,
         _ => return None,
     };
     Some(tt)
@@ -80,11 +266,23 @@ pub(crate) fn tt_keywords<'a>(s: impl Into<&'a str>) -> Option<TokenType> {
     let tt = match s.into() {
         "fn" => TokenType::Function,
         "let" => TokenType::Let,
+        "const" => TokenType::Const,
+        "struct" => TokenType::Struct,
+        "enum" => TokenType::Enum,
         "true" => TokenType::True,
         "false" => TokenType::False,
         "if" => TokenType::If,
         "else" => TokenType::Else,
         "return" => TokenType::Return,
+        "while" => TokenType::While,
+        "for" => TokenType::For,
+        "in" => TokenType::In,
+        "break" => TokenType::Break,
+        "continue" => TokenType::Continue,
+        "match" => TokenType::Match,
+        "macro" => TokenType::Macro,
+        "import" => TokenType::Import,
+        "export" => TokenType::Export,
         _ => return None,
     };
     Some(tt)
@@ -0,0 +1,474 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use crate::bytecode::{Bytecode, Instruction};
+use crate::object::{builtins::BUILTINS, Arity, Object};
+use crate::token::symbol::Symbol;
+
+// Runs the bytecode the emitter gave back
+//
+// every call gets a frame, with each of its locals in a cell of its own
+// a closure captures the cells rather than the values in them (see Instruction::CaptureLocal)
+// so an assignment in the closure is seen by the function the variable belongs to, and the other
+// way around, same as in the evaluator where the closure holds on to the env of the function
+//
+// the errors are the ones the evaluator gives, without the place they happened at
+
+type Variable = Rc<RefCell<Object>>;
+
+#[derive(Debug)]
+struct Frame {
+    // the CompiledFunction that's running
+    function: Rc<Object>,
+    free: Vec<Variable>,
+    locals: Vec<Variable>,
+    // where the next instruction starts
+    ip: usize,
+    // the height of the stack when it was called, without the function and the arguments
+    base: usize,
+    // how many arguments it was called with (see Instruction::JumpIfPassed)
+    args: usize,
+}
+
+#[derive(Debug)]
+pub struct Vm {
+    constants: Vec<Rc<Object>>,
+    globals: Vec<Object>,
+    stack: Vec<Object>,
+    frames: Vec<Frame>,
+    // the variables captured for the next Closure
+    captured: Vec<Variable>,
+}
+
+impl Vm {
+    pub fn new(bytecode: Bytecode) -> Self {
+        let arity = Arity {
+            min: 0,
+            max: Some(0),
+        };
+        let main = Object::CompiledFunction(bytecode.instructions, 0, 0, arity, None);
+        Self {
+            constants: bytecode.constants.into_iter().map(Rc::new).collect(),
+            globals: vec![],
+            stack: vec![],
+            frames: vec![Frame {
+                function: Rc::new(main),
+                free: vec![],
+                locals: vec![],
+                ip: 0,
+                base: 0,
+                args: 0,
+            }],
+            captured: vec![],
+        }
+    }
+
+    // the value of the program, or the message of the error it stopped with
+    pub fn run(mut self) -> Result<Object, String> {
+        // the program leaves its value on the stack once it's done
+        while let Some(instruction) = self.next_instruction() {
+            match instruction {
+                Instruction::Constant(index) => {
+                    let constant = (*self.constants[index as usize]).clone();
+                    self.stack.push(constant);
+                }
+                Instruction::Pop => {
+                    self.pop();
+                }
+                Instruction::Not => {
+                    let operand: bool = self.pop().into();
+                    self.stack.push(Object::Bool(!operand));
+                }
+                Instruction::UnaryPlus => match self.pop() {
+                    operand @ (Object::Int(_) | Object::Float(_)) => self.stack.push(operand),
+                    _ => return Err("Operand for the Unary Operator + should be a number".into()),
+                },
+                Instruction::UnaryMinus => match self.pop() {
+                    Object::Int(int) => self.stack.push(Object::Int(-int)),
+                    Object::Float(float) => self.stack.push(Object::Float(-float)),
+                    _ => return Err("Operand for the Unary Operator - should be a number".into()),
+                },
+                Instruction::Add => self.binary(|lhs, rhs| lhs + rhs)?,
+                Instruction::Sub => self.binary(|lhs, rhs| lhs - rhs)?,
+                Instruction::Mul => self.binary(|lhs, rhs| lhs * rhs)?,
+                Instruction::Div => self.binary(|lhs, rhs| lhs / rhs)?,
+                Instruction::Mod => self.binary(|lhs, rhs| lhs % rhs)?,
+                Instruction::Pow => self.binary(Object::pow)?,
+                Instruction::Eq => self.binary(Object::eq)?,
+                Instruction::NotEq => self.binary(Object::not_eq)?,
+                Instruction::GT => self.binary(Object::gt)?,
+                Instruction::LT => self.binary(Object::lt)?,
+                Instruction::GTEq => self.binary(Object::gt_eq)?,
+                Instruction::LTEq => self.binary(Object::lt_eq)?,
+                Instruction::BitAnd => self.binary(|lhs, rhs| lhs & rhs)?,
+                Instruction::BitOr => self.binary(|lhs, rhs| lhs | rhs)?,
+                Instruction::BitXor => self.binary(|lhs, rhs| lhs ^ rhs)?,
+                Instruction::Shl => self.binary(|lhs, rhs| lhs << rhs)?,
+                Instruction::Shr => self.binary(|lhs, rhs| lhs >> rhs)?,
+                Instruction::Jump(target) => self.frame().ip = target,
+                Instruction::JumpNotTruthy(target) => {
+                    let condition: bool = self.pop().into();
+                    if !condition {
+                        self.frame().ip = target;
+                    }
+                }
+                Instruction::JumpIfPassed(param, target) => {
+                    if self.frame().args > param as usize {
+                        self.frame().ip = target;
+                    }
+                }
+                Instruction::JumpTable(table) => {
+                    let value = self.pop();
+                    let Object::Hash(table) = &*self.constants[table as usize] else {
+                        unreachable!("a jump table is a hash constant")
+                    };
+                    let target = value.hash_key().ok().and_then(|key| table.get(&key));
+                    if let Some(&Object::Int(target)) = target {
+                        self.frame().ip = target as usize;
+                    }
+                }
+                Instruction::Return => self.return_value(Object::Null),
+                Instruction::ReturnValue => {
+                    let value = self.pop();
+                    // a return at the top level ends the program
+                    if self.frames.len() == 1 {
+                        return Ok(value);
+                    }
+                    self.return_value(value);
+                }
+                Instruction::Call(args) => self.call(args as usize)?,
+                Instruction::SetGlobal(index) => {
+                    let value = self.pop();
+                    let index = index as usize;
+                    if index >= self.globals.len() {
+                        self.globals.resize(index + 1, Object::Null);
+                    }
+                    self.globals[index] = value;
+                }
+                Instruction::GetGlobal(index) => {
+                    let value = self.globals.get(index as usize).cloned();
+                    self.stack.push(value.unwrap_or(Object::Null));
+                }
+                Instruction::SetLocal(index) => {
+                    let value = self.pop();
+                    *self.frame().locals[index as usize].borrow_mut() = value;
+                }
+                Instruction::GetLocal(index) => {
+                    let value = self.frame().locals[index as usize].borrow().clone();
+                    self.stack.push(value);
+                }
+                Instruction::SetFree(index) => {
+                    let value = self.pop();
+                    *self.frame().free[index as usize].borrow_mut() = value;
+                }
+                Instruction::GetFree(index) => {
+                    let value = self.frame().free[index as usize].borrow().clone();
+                    self.stack.push(value);
+                }
+                Instruction::NewLocal(index) => {
+                    let variable = Rc::new(RefCell::new(Object::Null));
+                    self.frame().locals[index as usize] = variable;
+                }
+                Instruction::CaptureLocal(index) => {
+                    let variable = self.frame().locals[index as usize].clone();
+                    self.captured.push(variable);
+                }
+                Instruction::CaptureFree(index) => {
+                    let variable = self.frame().free[index as usize].clone();
+                    self.captured.push(variable);
+                }
+                Instruction::Closure(index, free) => {
+                    let free = self.captured.len() - free as usize;
+                    let free = self.captured.split_off(free);
+                    let function = self.constants[index as usize].clone();
+                    self.stack.push(Object::Closure(function, free));
+                }
+                Instruction::GetBuiltin(index) => {
+                    self.stack.push(Object::Builtin(BUILTINS[index as usize]));
+                }
+                Instruction::Null => self.stack.push(Object::Null),
+                Instruction::Array(len) => {
+                    let elements = self.pop_many(len as usize);
+                    self.stack.push(Object::Array(elements));
+                }
+                Instruction::Tuple(len) => {
+                    let elements = self.pop_many(len as usize);
+                    self.stack.push(Object::Tuple(elements));
+                }
+                Instruction::Hash(len) => {
+                    let mut entries = self.pop_many(2 * len as usize).into_iter();
+                    let mut hash = BTreeMap::new();
+                    while let (Some(key), Some(value)) = (entries.next(), entries.next()) {
+                        // the last one wins if a key is repeated
+                        hash.insert(key.hash_key()?, value);
+                    }
+                    self.stack.push(Object::Hash(hash));
+                }
+                Instruction::Index => {
+                    let index = self.pop();
+                    let collection = self.pop();
+                    self.stack.push(collection.index(index)?);
+                }
+                Instruction::Iterate => {
+                    let items = self.pop().into_iterable()?;
+                    self.stack.push(Object::Array(items));
+                }
+                Instruction::MatchLiteral(literal) => {
+                    let literal = self.constants[literal as usize].hash_key().ok();
+                    let value = self.pop().hash_key().ok();
+                    self.stack.push(Object::Bool(value == literal));
+                }
+                Instruction::MatchArray(len) => {
+                    let value = self.pop();
+                    let matches =
+                        matches!(value, Object::Array(elements) if elements.len() == len as usize);
+                    self.stack.push(Object::Bool(matches));
+                }
+                Instruction::MatchTuple(len) => {
+                    let value = self.pop();
+                    let matches =
+                        matches!(value, Object::Tuple(elements) if elements.len() == len as usize);
+                    self.stack.push(Object::Bool(matches));
+                }
+                Instruction::ExpectArray(len) => self.expect("an array", len as usize)?,
+                Instruction::ExpectTuple(len) => self.expect("a tuple", len as usize)?,
+                Instruction::Struct(name, len) => {
+                    let fields = self.pop_many(2 * len as usize);
+                    let r#type = self.pop();
+                    let value = make_struct(self.name(name), r#type, fields)?;
+                    self.stack.push(value);
+                }
+                Instruction::GetField(field) => {
+                    let value = self.pop().field(self.name(field))?;
+                    self.stack.push(value);
+                }
+                Instruction::SetField(field) => {
+                    let value = self.pop();
+                    let object = self.pop();
+                    self.stack.push(set_field(object, self.name(field), value)?);
+                }
+                Instruction::MatchVariant(name, variant, len) => {
+                    let r#type = self.pop();
+                    let value = self.pop();
+                    let (name, variant) = (self.name(name), self.name(variant));
+                    let matches = match_variant(name, &r#type, variant, len as usize, &value)?;
+                    self.stack.push(Object::Bool(matches));
+                }
+                Instruction::Payload(index) => {
+                    let Object::Variant(_, _, mut fields) = self.pop() else {
+                        unreachable!("MatchVariant made sure that it's a variant")
+                    };
+                    self.stack.push(fields.swap_remove(index as usize).1);
+                }
+            }
+        }
+        Ok(self.pop())
+    }
+
+    // the instruction at the ip of the current frame, which moves past it
+    // or None once the program is over
+    fn next_instruction(&mut self) -> Option<Instruction> {
+        let frame = self.frames.last_mut().unwrap();
+        let Object::CompiledFunction(instructions, ..) = &*frame.function else {
+            unreachable!("only compiled functions run")
+        };
+        // functions end with a return, only the program runs to the end
+        let bytes = instructions
+            .get(frame.ip..)
+            .filter(|bytes| !bytes.is_empty())?;
+        let (instruction, len) = Instruction::read(bytes);
+        frame.ip += len;
+        Some(instruction)
+    }
+
+    fn frame(&mut self) -> &mut Frame {
+        self.frames.last_mut().unwrap()
+    }
+
+    // the emitter keeps the stack balanced, there's always something to take off it
+    fn pop(&mut self) -> Object {
+        self.stack.pop().unwrap()
+    }
+
+    // the last `len` objects on the stack, in the order they were pushed
+    fn pop_many(&mut self, len: usize) -> Vec<Object> {
+        self.stack.split_off(self.stack.len() - len)
+    }
+
+    // the symbol for the name in a string constant
+    fn name(&self, index: u16) -> Symbol {
+        let Object::Str(name) = &*self.constants[index as usize] else {
+            unreachable!("names are string constants")
+        };
+        Symbol::intern(name)
+    }
+
+    fn binary(&mut self, op: fn(Object, Object) -> Result<Object, String>) -> Result<(), String> {
+        let rhs = self.pop();
+        let lhs = self.pop();
+        self.stack.push(op(lhs, rhs)?);
+        Ok(())
+    }
+
+    // the function is on the stack below its arguments
+    fn call(&mut self, args: usize) -> Result<(), String> {
+        let mut values = self.pop_many(args);
+        match self.pop() {
+            Object::Builtin(builtin) => self.stack.push((builtin.func)(values)?),
+            Object::Constructor(type_name, variant, fields) => {
+                if args != fields.len() {
+                    return Err(format!(
+                        "Incorrect number of arguments passed to `{type_name}.{variant}`, \
+                         Got: {args}, Expected: {}",
+                        fields.len()
+                    ));
+                }
+                let fields = fields.into_iter().zip(values).collect();
+                self.stack.push(Object::Variant(type_name, variant, fields));
+            }
+            Object::Closure(function, free) => {
+                let Object::CompiledFunction(_, locals, params, arity, name) = &*function else {
+                    unreachable!("a closure is made of a compiled function")
+                };
+                if !arity.accepts(args) {
+                    let name = name.map(|name| format!(" to `{name}`")).unwrap_or_default();
+                    return Err(format!(
+                        "Incorrect number of arguments passed{name}, Got: {args}, \
+                         Expected: {arity}"
+                    ));
+                }
+                // the rest param gets the arguments after the other params, as an array
+                let rest = arity.max.is_none().then(|| {
+                    let named = *params as usize - 1;
+                    Object::Array(values.split_off(named.min(values.len())))
+                });
+                // the params that weren't passed are set by their defaults
+                let params = *params as usize - rest.is_some() as usize;
+                values.resize(params, Object::Null);
+                values.extend(rest);
+                values.resize(*locals as usize, Object::Null);
+                let locals = values
+                    .into_iter()
+                    .map(|value| Rc::new(RefCell::new(value)))
+                    .collect();
+                self.frames.push(Frame {
+                    function: function.clone(),
+                    free,
+                    locals,
+                    ip: 0,
+                    base: self.stack.len(),
+                    args,
+                });
+            }
+            _ => return Err("Can't call a non function".into()),
+        }
+        Ok(())
+    }
+
+    // back to the caller, with `value` instead of the function on the stack
+    fn return_value(&mut self, value: Object) {
+        let frame = self.frames.pop().unwrap();
+        self.stack.truncate(frame.base);
+        self.stack.push(value);
+    }
+
+    // the check of a destructuring let, that the value on the stack has `len` elements
+    fn expect(&self, kind: &str, len: usize) -> Result<(), String> {
+        let elements = match (kind, self.stack.last().unwrap()) {
+            ("an array", Object::Array(elements)) | ("a tuple", Object::Tuple(elements)) => {
+                elements
+            }
+            (_, value) => {
+                return Err(format!(
+                    "Expected {kind} to destructure, got a value of type {}",
+                    value.type_name()
+                ))
+            }
+        };
+        if elements.len() != len {
+            return Err(format!(
+                "Can't destructure {kind} of length {} with a pattern of length {len}",
+                elements.len()
+            ));
+        }
+        Ok(())
+    }
+}
+
+// a value of the struct type `r#type` (written as `name`)
+// out of `fields`, the name of each field followed by its value
+fn make_struct(name: Symbol, r#type: Object, fields: Vec<Object>) -> Result<Object, String> {
+    let Object::StructType(type_name, declared) = r#type else {
+        return Err(format!(
+            "`{name}` isn't a struct type, it's a value of type {}",
+            r#type.type_name()
+        ));
+    };
+    // the parser made sure that no field is given twice
+    let mut values = vec![None; declared.len()];
+    let mut fields = fields.into_iter();
+    while let (Some(Object::Str(field)), Some(value)) = (fields.next(), fields.next()) {
+        let Some(i) = declared.iter().position(|name| name.as_str() == field) else {
+            return Err(format!("The struct `{type_name}` has no field `{field}`"));
+        };
+        values[i] = Some(value);
+    }
+    let missing: Vec<_> = declared
+        .iter()
+        .zip(&values)
+        .filter(|(_, value)| value.is_none())
+        .map(|(name, _)| format!("`{name}`"))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "Missing fields for the struct `{type_name}`: {}",
+            missing.join(", ")
+        ));
+    }
+    let values = values.into_iter().map(Option::unwrap);
+    Ok(Object::Struct(
+        type_name,
+        declared.into_iter().zip(values).collect(),
+    ))
+}
+
+// a copy of the struct (or the variant) with the field `name` set to `value`
+fn set_field(mut object: Object, name: Symbol, value: Object) -> Result<Object, String> {
+    let (Object::Struct(_, fields) | Object::Variant(_, _, fields)) = &mut object else {
+        return Err(object.no_field(name));
+    };
+    let Some(i) = fields.iter().position(|&(other, _)| other == name) else {
+        return Err(object.no_field(name));
+    };
+    fields[i].1 = value;
+    Ok(object)
+}
+
+// whether `value` is the variant of the enum type `r#type` (written as `name`)
+// which has to have that variant, with `len` fields
+fn match_variant(
+    name: Symbol,
+    r#type: &Object,
+    variant: Symbol,
+    len: usize,
+    value: &Object,
+) -> Result<bool, String> {
+    let Object::EnumType(type_name, variants) = r#type else {
+        return Err(format!(
+            "`{name}` isn't an enum type, it's a value of type {}",
+            r#type.type_name()
+        ));
+    };
+    let Some((_, fields)) = variants.iter().find(|&&(other, _)| other == variant) else {
+        return Err(r#type.no_field(variant));
+    };
+    if fields.len() != len {
+        let plural = if fields.len() == 1 { "" } else { "s" };
+        return Err(format!(
+            "The variant `{type_name}.{variant}` has {} field{plural}, the pattern has {len}",
+            fields.len()
+        ));
+    }
+    Ok(matches!(value, Object::Variant(one, two, _) if one == type_name && *two == variant))
+}
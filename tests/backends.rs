@@ -0,0 +1,804 @@
+use interpreter::ast::Program;
+use interpreter::diagnostics::Diagnostic;
+use interpreter::emitter::Emitter;
+use interpreter::evaluation::try_eval_program;
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+use interpreter::vm::Vm;
+
+fn parse(source: &str) -> Program {
+    let (program, errors) = Parser::new(Lexer::from_string(source.into())).parse_program();
+    assert!(errors.is_empty(), "{source:?} doesn't parse: {errors:?}");
+    program
+}
+
+// the evaluator recurses on the Rust stack, and in a debug build a call to a function
+// takes so much of it that the 2 MiB of a test thread only go a few calls deep
+fn with_big_stack<T: Send + 'static>(source: &str, run: fn(&str) -> T) -> T {
+    let source = source.to_string();
+    std::thread::Builder::new()
+        .stack_size(256 << 20)
+        .spawn(move || run(&source))
+        .unwrap()
+        .join()
+        .unwrap()
+}
+
+// the value of the program, or the message of the error it stopped with
+// which the evaluator and the compiled program have to agree on
+fn run(source: &str) -> Result<String, String> {
+    let evaluated = with_big_stack(source, |source| {
+        try_eval_program(parse(source))
+            .map(|value| value.to_string())
+            .map_err(|err| err.message)
+    });
+    let mut emitter = Emitter::new();
+    emitter.compile_program(parse(source));
+    assert!(
+        emitter.errors().is_empty(),
+        "{source:?} doesn't compile: {:?}",
+        emitter.errors()
+    );
+    let compiled = Vm::new(emitter.emit()).run().map(|value| value.to_string());
+    assert_eq!(evaluated, compiled, "{source:?}");
+    evaluated
+}
+
+// the messages of the errors the emitter finds in the program
+fn compile_errors(source: &str) -> Vec<String> {
+    let mut emitter = Emitter::new();
+    emitter.compile_program(parse(source));
+    emitter
+        .errors()
+        .iter()
+        .map(|err| err.message.clone())
+        .collect()
+}
+
+// the warnings of the emitter: their message, the text they point at
+// and the text their first label points at
+fn compile_warnings(source: &str) -> Vec<(String, &str, &str)> {
+    let mut emitter = Emitter::new();
+    emitter.compile_program(parse(source));
+    let text = |(start, end): (usize, usize)| &source[start..end];
+    emitter
+        .warnings()
+        .iter()
+        .map(|warning| {
+            let label = text(warning.secondary[0].span);
+            (warning.message.clone(), text(warning.primary.span), label)
+        })
+        .collect()
+}
+
+#[test]
+fn an_index_out_of_bounds_is_an_error_in_both_backends() {
+    assert_eq!(run("let a = [1, 2, 3]; a[1] + a[2]").unwrap(), "5");
+    assert_eq!(
+        run("[1, 2, 3][3]").unwrap_err(),
+        "Index 3 is out of bounds for an array of length 3"
+    );
+    assert_eq!(
+        run("[1, 2, 3][-1]").unwrap_err(),
+        "Index -1 is out of bounds for an array of length 3"
+    );
+}
+
+#[test]
+fn a_missing_key_is_an_error_in_both_backends() {
+    assert_eq!(run(r#"{"a": 1, "b": 2}["b"]"#).unwrap(), "2");
+    assert_eq!(
+        run(r#"{"a": 1}["c"]"#).unwrap_err(),
+        r#"No entry for the key "c" in the hash"#
+    );
+}
+
+#[test]
+fn a_function_has_variables_of_its_own_in_both_backends() {
+    let source = "let x = 1; let f = fn(a, b) { let x = a + b; x * 2 }; [f(1, 2), x]";
+    assert_eq!(run(source).unwrap(), "[6, 1]");
+    assert_eq!(
+        run("let f = fn(a, b) { a + b }; f(1)").unwrap_err(),
+        "Incorrect number of arguments passed, Got: 1, Expected: 2"
+    );
+}
+
+#[test]
+fn loops_run_in_both_backends() {
+    let source = "let sum = 0; for x in [1, 2, 3] { sum += x }
+        let i = 0; while true { i += 1; if i == 5 { break } }; [sum, i]";
+    assert_eq!(run(source).unwrap(), "[6, 5]");
+    assert!(run("for x in 5 { x }").is_err());
+}
+
+#[test]
+fn the_operators_give_the_same_values_and_errors_in_both_backends() {
+    let source = "[7 % 3, 2 ** 3 ** 2, 3 >= 3, 2 <= 1, 6 & 3, 6 | 3, 6 ^ 3, 1 << 4, -16 >> 2]";
+    assert_eq!(run(source).unwrap(), "[1, 512, true, false, 2, 7, 5, 16, -4]");
+    assert_eq!(run("2 ** 64").unwrap_err(), "2 ** 64 is too big for an int");
+}
+
+#[test]
+fn a_match_picks_the_same_arm_in_both_backends() {
+    let source = "let f = fn(x) {
+            match x { 1 => \"one\", [a, b] if a < b => a + b, [_, _] => 0, _ => -1 }
+        };
+        [f(1), f([1, 2]), f([2, 1]), f(\"1\")]";
+    assert_eq!(run(source).unwrap(), r#"["one", 3, 0, -1]"#);
+}
+
+#[test]
+fn functions_declared_in_a_function_can_call_themselves_and_each_other() {
+    let source = "fn f(n) {
+            fn even(n) { if n == 0 { true } else { odd(n - 1) } }
+            fn odd(n) { if n == 0 { false } else { even(n - 1) } }
+            let fact = fn(n) { if n < 2 { 1 } else { n * fact(n - 1) } };
+            [even(n), odd(n), fact(n)]
+        }
+        f(4)";
+    assert_eq!(run(source).unwrap(), "[true, false, 24]");
+    let source = "let fact = fn(n) { if n < 2 { 1 } else { n * fact(n - 1) } }; fact(5)";
+    assert_eq!(run(source).unwrap(), "120");
+}
+
+#[test]
+fn defaults_and_rest_params_are_the_same_in_both_backends() {
+    let source = "let f = fn(a, b = a * 2, ...rest) { [a, b, rest] };
+        [f(1), f(1, 5), f(1, 5, 6, 7)]";
+    assert_eq!(run(source).unwrap(), "[[1, 2, []], [1, 5, []], [1, 5, [6, 7]]]");
+}
+
+#[test]
+fn tuples_are_built_matched_and_taken_apart_in_both_backends() {
+    let source = "let (a, [b, c]) = (1, [2, 3]); match (a, b) { (1, x) => x + c, _ => 0 }";
+    assert_eq!(run(source).unwrap(), "5");
+    assert_eq!(
+        run("let f = fn(t) { let (a, b) = t; a }; f((1, 2, 3))").unwrap_err(),
+        "Can't destructure a tuple of length 3 with a pattern of length 2"
+    );
+}
+
+#[test]
+fn structs_are_built_read_and_updated_in_both_backends() {
+    let source = "struct P { x, y } let p = P { y: 2, x: 1 }; p.x = p.y + 10; [p.x, p.y]";
+    assert_eq!(run(source).unwrap(), "[12, 2]");
+    assert_eq!(
+        run("struct P { x } let p = P { x: 1 }; p.y").unwrap_err(),
+        "The struct `P` has no field `y`"
+    );
+    assert_eq!(
+        run("struct P { x, y } P { x: 1 }").unwrap_err(),
+        "Missing fields for the struct `P`: `y`"
+    );
+}
+
+#[test]
+fn enum_variants_are_built_and_matched_in_both_backends() {
+    let source = "enum Shape { Circle(r), Square(side), Empty }
+        let area = fn(s) {
+            match s { Shape.Circle(r) => 3 * r * r, Shape.Square(a) => a * a, _ => 0 }
+        };
+        [area(Shape.Circle(2)), area(Shape.Square(3)), area(Shape.Empty)]";
+    assert_eq!(run(source).unwrap(), "[12, 9, 0]");
+    assert_eq!(
+        run("enum E { A(x) } E.A(1, 2)").unwrap_err(),
+        "Incorrect number of arguments passed to `E.A`, Got: 2, Expected: 1"
+    );
+}
+
+#[test]
+fn a_name_that_was_never_declared_is_a_compile_error() {
+    let mut emitter = Emitter::new();
+    emitter.compile_program(parse("let a = 1; a + b"));
+    let messages: Vec<_> = emitter.errors().iter().map(|err| err.message.as_str()).collect();
+    assert_eq!(messages, ["Can't find `b`, it was never declared"]);
+}
+
+#[test]
+fn a_closure_shares_the_variables_it_captures() {
+    let source = "fn counter() { let c = 0; let inc = fn() { c += 1 }; inc(); inc(); c } counter()";
+    assert_eq!(run(source).unwrap(), "2");
+    // and sees what the function it's in assigns to them later
+    let source = "fn f() { let x = 1; let get = fn() { x }; x = 2; get() } f()";
+    assert_eq!(run(source).unwrap(), "2");
+    // through a closure in between
+    let source =
+        "fn f() { let x = 1; let g = fn() { let h = fn() { x += 10 }; h(); x }; g() + x } f()";
+    assert_eq!(run(source).unwrap(), "22");
+}
+
+#[test]
+fn closures_made_by_separate_calls_have_variables_of_their_own() {
+    let source = "fn counter() { let c = 0; fn() { c += 1 } }
+        let a = counter(); let b = counter(); a(); a(); [a(), b()]";
+    assert_eq!(run(source).unwrap(), "[3, 1]");
+}
+
+#[test]
+fn the_names_a_match_arm_binds_are_its_own() {
+    let source = "let x = 1; match 5 { x if x > 10 => \"big\", _ => x }";
+    assert_eq!(run(source).unwrap(), "1");
+    let source = "let x = 1; match (2, 3) { (x, y) if x > 5 => x, (a, x) => x + a }; x";
+    assert_eq!(run(source).unwrap(), "1");
+    let source = "let z = 0; match 1 { 1 => { let z = 5; z } }; z";
+    assert_eq!(run(source).unwrap(), "0");
+    // a binding can have the name of a constant outside
+    let source = "const x = 1; match 2 { x => x + 10 }";
+    assert_eq!(run(source).unwrap(), "12");
+    // the variables from outside can still be assigned to
+    let source = "let x = 1; match 2 { y => { x = y } }; x";
+    assert_eq!(run(source).unwrap(), "2");
+    // every time the arm is taken, its bindings are new ones
+    let source = "fn f() { let fs = [];
+            for i in [1, 2] { let g = match i { v => fn() { v } }; fs = push(fs, g) };
+            [fs[0](), fs[1]()] }
+        f()";
+    assert_eq!(run(source).unwrap(), "[1, 2]");
+}
+
+#[test]
+fn a_name_bound_by_a_match_arm_is_gone_after_it() {
+    let source = "match 2 { y => y }; y";
+    let message = "Can't find `y`, it was never declared";
+    assert_eq!(try_eval_program(parse(source)).unwrap_err().message, message);
+    assert_eq!(compile_errors(source), [message]);
+}
+
+#[test]
+fn a_match_arm_is_a_scope_of_its_own_for_constants() {
+    let source = "match 1 { x => { const x = 2; x } }";
+    let err = try_eval_program(parse(source)).unwrap_err();
+    assert_eq!(err.message, "`x` is declared in this scope already");
+    assert_eq!(
+        compile_errors(source),
+        ["`x` is declared in this scope already"]
+    );
+}
+
+#[test]
+fn a_declared_function_sees_the_variables_next_to_it_change() {
+    let source = "fn outer() { let x = 1; fn get() { x } x = 2; get() } outer()";
+    assert_eq!(run(source).unwrap(), "2");
+    let source = "fn outer() { fn get() { x } let x = 1; x += 5; get() } outer()";
+    assert_eq!(run(source).unwrap(), "6");
+    let source = "fn f() { let (a, b) = (1, 2); fn g() { a + b } a = 10; g() } f()";
+    assert_eq!(run(source).unwrap(), "12");
+    let source = "fn f() { let n = 0; fn inc() { n += 1 } inc(); inc(); n } f()";
+    assert_eq!(run(source).unwrap(), "2");
+}
+
+#[test]
+fn a_let_of_a_name_sets_the_variable_it_has_already() {
+    let source = "fn f() { let x = 1; let g = fn() { x }; let x = 2; g() } f()";
+    assert_eq!(run(source).unwrap(), "2");
+    let source = "fn f(a) { let g = fn() { a }; let a = 5; g() } f(1)";
+    assert_eq!(run(source).unwrap(), "5");
+    let source = "let f = fn(n) { if n == 0 { 1 } else { n * f(n - 1) } }; f(5)";
+    assert_eq!(run(source).unwrap(), "120");
+}
+
+#[test]
+fn declared_functions_can_call_each_other() {
+    let source = "fn f() {
+            fn even(n) { if n == 0 { true } else { odd(n - 1) } }
+            fn odd(n) { if n == 0 { false } else { even(n - 1) } }
+            [even(4), odd(3), even(3)]
+        }
+        f()";
+    assert_eq!(run(source).unwrap(), "[true, true, false]");
+}
+
+#[test]
+fn integer_literals_can_have_a_base_and_underscores() {
+    assert_eq!(run("0x1f + 0b101 + 0o17 + 1_000_000").unwrap(), "1000051");
+    assert_eq!(run("0xFF_FF").unwrap(), "65535");
+    assert_eq!(run("9223372036854775807").unwrap(), "9223372036854775807");
+}
+
+#[test]
+fn floats_mix_with_ints() {
+    assert_eq!(run("1e-9").unwrap(), "1e-9");
+    assert_eq!(run("2.5e3").unwrap(), "2500.0");
+    assert_eq!(run("1 + 0.5 * 4").unwrap(), "3.0");
+    assert_eq!(run("[7 / 2.0, 7 / 2, -2.5 * 2]").unwrap(), "[3.5, 3, -5.0]");
+    assert_eq!(
+        run("[1.5 < 2, 2 > 1.5, 1.0 == 1]").unwrap(),
+        "[true, true, true]"
+    );
+    assert_eq!(
+        run("1.5 + true").unwrap_err(),
+        "Addition requires that both operands are numbers"
+    );
+}
+
+#[test]
+fn arrays_and_their_builtins() {
+    let source = "let a = [1, 2, 3];
+        [a[0], a[2], len(a), first(a), last(a), rest(a), push(a, 4), a]";
+    let value = "[1, 3, 3, 1, 3, [2, 3], [1, 2, 3, 4], [1, 2, 3]]";
+    assert_eq!(run(source).unwrap(), value);
+    let source = "[first([]), last([]), rest([]), len([]), [[1, 2], [3]][0][1]]";
+    assert_eq!(run(source).unwrap(), "[null, null, null, 0, 2]");
+    assert_eq!(
+        run("[1, 2][2]").unwrap_err(),
+        "Index 2 is out of bounds for an array of length 2"
+    );
+}
+
+#[test]
+fn a_block_is_the_value_of_its_last_expression() {
+    assert_eq!(run("let x = 1; { x }").unwrap(), "1");
+    assert_eq!(run("let y = { let a = 2; a * 3 }; y").unwrap(), "6");
+    assert_eq!(run("fn f() { { 1 } } f()").unwrap(), "1");
+    // or null, when it doesn't end with one
+    assert_eq!(run("let y = if true { let x = 1 }; y").unwrap(), "null");
+    assert_eq!(run("if true { }").unwrap(), "null");
+    assert_eq!(
+        run("let y = match 1 { _ => { let z = 1 } }; y").unwrap(),
+        "null"
+    );
+}
+
+#[test]
+fn hashes_and_their_builtins() {
+    let source = "let h = {\"name\": \"x\", 1: true, false: [1]};
+        [h[\"name\"], h[1], h[false], contains(h, 1), contains(h, 2)]";
+    assert_eq!(run(source).unwrap(), "[\"x\", true, [1], true, false]");
+    let source = "[keys({1: 2, 3: 4}), values({1: 2}), {1: 2, 1: 3}, {}]";
+    assert_eq!(run(source).unwrap(), "[[1, 3], [2], {1: 3}, {}]");
+    assert_eq!(run("{1: 2, 3: 4} == {3: 4, 1: 2}").unwrap(), "true");
+    assert_eq!(
+        run("{\"a\": 1}[\"b\"]").unwrap_err(),
+        "No entry for the key \"b\" in the hash"
+    );
+}
+
+#[test]
+fn assignments_set_the_variable_they_name() {
+    let source = "let x = 1; x = 5; x += 2; x -= 1; x *= 3; x /= 2; x";
+    assert_eq!(run(source).unwrap(), "9");
+    let source = "let x = 1; let f = fn() { x += 1 }; f(); f(); x";
+    assert_eq!(run(source).unwrap(), "3");
+    // an assignment is an expression with the value assigned
+    assert_eq!(
+        run("let x = 1; let y = x = 4; [x, y, (x = 3) + 1]").unwrap(),
+        "[4, 4, 4]"
+    );
+}
+
+#[test]
+fn a_variable_has_to_be_declared_to_be_assigned() {
+    for source in ["y = 1", "let x = 1; y += x"] {
+        let err = try_eval_program(parse(source)).unwrap_err();
+        let message = "Can't assign to `y`, it was never declared (use `let` to declare it)";
+        assert_eq!(err.message, message);
+        assert_eq!(compile_errors(source), [message]);
+    }
+}
+
+#[test]
+fn loops_with_break_and_continue() {
+    let source = "let i = 0; let s = 0;
+        while i < 5 { i += 1; if i == 2 { continue } if i == 4 { break } s += i }; [i, s]";
+    assert_eq!(run(source).unwrap(), "[4, 4]");
+    let source = "let s = []; for x in [1, 2, 3] { s = push(s, x * 2) }; s";
+    assert_eq!(run(source).unwrap(), "[2, 4, 6]");
+    let source = "let s = 0;
+        'outer: for i in [1, 2, 3] {
+            for j in [1, 2, 3] { if j == 2 { continue 'outer } if i == 3 { break 'outer } s += j }
+        }; s";
+    assert_eq!(run(source).unwrap(), "2");
+    // a loop doesn't grow the stack, however many times it goes around
+    assert_eq!(
+        run("let i = 0; while i < 10000 { i += 1 }; i").unwrap(),
+        "10000"
+    );
+    assert_eq!(run("while false { }").unwrap(), "null");
+    assert_eq!(
+        run("for x in 5 { x }").unwrap_err(),
+        "Can't iterate over a value of type int"
+    );
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+    let source = "[true && false, true || false, false || false, true && true]";
+    assert_eq!(run(source).unwrap(), "[false, true, false, true]");
+    let source = "let n = 0; let f = fn() { n += 1; true }; [false && f(), true || f(), n]";
+    assert_eq!(run(source).unwrap(), "[false, true, 0]");
+    // the right side that would fail is never evaluated
+    assert_eq!(run("1 == 1 || 1 / 0 == 1").unwrap(), "true");
+    // && binds tighter than ||, and both looser than the comparisons
+    assert_eq!(run("true || false && false").unwrap(), "true");
+    assert_eq!(run("1 + 1 == 2 && 1 < 2").unwrap(), "true");
+}
+
+#[test]
+fn comparison_arithmetic_and_bitwise_operators() {
+    let source = "[1 <= 1, 2 >= 3, 7 % 3, -7 % 3, 6 & 3, 6 | 3, 6 ^ 3, 1 << 4, -16 >> 2]";
+    assert_eq!(
+        run(source).unwrap(),
+        "[true, false, 1, -1, 2, 7, 5, 16, -4]"
+    );
+    // ** is right associative and binds tighter than *
+    assert_eq!(
+        run("[2 ** 3 ** 2, 1 + 2 * 3 ** 2, 2 ** -1]").unwrap(),
+        "[512, 19, 0.5]"
+    );
+    assert_eq!(run("[1 | 2 ^ 3 & 4, 1 + 2 << 1]").unwrap(), "[3, 6]");
+    let errors = [
+        ("1 % 0", "Modulo by zero"),
+        (
+            "1 << 64",
+            "Can't shift by 64, it has to be between 0 and 63",
+        ),
+        (
+            "1.5 & 1",
+            "& requires two ints or two bools, got float and int",
+        ),
+        ("2 ** 64", "2 ** 64 is too big for an int"),
+    ];
+    for (source, message) in errors {
+        assert_eq!(run(source).unwrap_err(), message);
+    }
+}
+
+#[test]
+fn else_if_chains() {
+    let source = "fn f(n) {
+            if n < 0 { \"neg\" } else if n == 0 { \"zero\" }
+            else if n < 10 { \"small\" } else { \"big\" }
+        }
+        [f(-1), f(0), f(5), f(50)]";
+    assert_eq!(
+        run(source).unwrap(),
+        "[\"neg\", \"zero\", \"small\", \"big\"]"
+    );
+    // without an else at the end, the chain is null when nothing holds
+    let source = "fn f(n) { if n == 1 { 1 } else if n == 2 { 2 } } [f(1), f(2), f(3)]";
+    assert_eq!(run(source).unwrap(), "[1, 2, null]");
+}
+
+#[test]
+fn match_patterns_and_guards() {
+    let source = "fn f(x) {
+            match x {
+                1 => \"one\", \"s\" => \"str\", true => \"yes\", [a, b] => a + b,
+                (p, _, q) => p * q, n if n > 100 => \"big\", _ => \"other\"
+            }
+        }
+        [f(1), f(\"s\"), f(true), f([2, 3]), f((2, 0, 5)), f(500), f(7)]";
+    let value = "[\"one\", \"str\", \"yes\", 5, 10, \"big\", \"other\"]";
+    assert_eq!(run(source).unwrap(), value);
+    // the arms with literals only go through a jump table
+    let source = "match 3 { 1 => \"a\", 2 => \"b\", 3 => \"c\", 4 => \"d\", _ => \"e\" }";
+    assert_eq!(run(source).unwrap(), "\"c\"");
+    assert_eq!(
+        run("match [1, 2, 3] { [a, b] => a, [a, b, c] => c }").unwrap(),
+        "3"
+    );
+    // nothing matching is null
+    assert_eq!(run("match 5 { 1 => 1 }").unwrap(), "null");
+}
+
+#[test]
+fn an_arm_after_one_that_covers_it_is_unreachable() {
+    let unreachable = |arm, earlier| vec![("Unreachable match arm".to_string(), arm, earlier)];
+    assert_eq!(
+        compile_warnings("match 1 { _ => 1, 2 => 2 }"),
+        unreachable("2", "_")
+    );
+    assert_eq!(
+        compile_warnings("match 1 { x => 1, _ => 2 }"),
+        unreachable("_", "x")
+    );
+    assert_eq!(
+        compile_warnings("match 1 { 1 => 1, 1 => 2, _ => 3 }"),
+        unreachable("1", "1")
+    );
+    // an arm with a guard doesn't cover anything
+    assert_eq!(compile_warnings("match 1 { x if x > 0 => 1, _ => 2 }"), []);
+}
+
+#[test]
+fn declared_functions_are_hoisted_and_keep_their_name() {
+    assert_eq!(run("let a = f(2); fn f(n) { n * 10 } a").unwrap(), "20");
+    assert_eq!(run("{ let v = g(); fn g() { 3 } v }").unwrap(), "3");
+    let source = "fn even(n) { if n == 0 { true } else { odd(n - 1) } }
+        fn odd(n) { if n == 0 { false } else { even(n - 1) } }
+        [even(4), odd(5)]";
+    assert_eq!(run(source).unwrap(), "[true, true]");
+    assert_eq!(run("fn add(a, b) { a + b } add").unwrap(), "fn add");
+    assert_eq!(
+        run("fn f(a) { a } f(1, 2)").unwrap_err(),
+        "Incorrect number of arguments passed to `f`, Got: 2, Expected: 1"
+    );
+}
+
+#[test]
+fn default_and_rest_params() {
+    let source = "fn f(a, b = 10, ...rest) { [a, b, rest] } [f(1), f(1, 2), f(1, 2, 3, 4)]";
+    assert_eq!(
+        run(source).unwrap(),
+        "[[1, 10, []], [1, 2, []], [1, 2, [3, 4]]]"
+    );
+    // a default is evaluated at every call that leaves it out, and sees the params before it
+    assert_eq!(
+        run("fn f(a, b = a * 2) { b } [f(3), f(3, 1)]").unwrap(),
+        "[6, 1]"
+    );
+    let source = "let n = 0; fn f(a = { n += 1; n }) { a } [f(), f(), f(9), n]";
+    assert_eq!(run(source).unwrap(), "[1, 2, 9, 2]");
+    let arity = |source, expected| {
+        let message = format!("Incorrect number of arguments passed to `f`, Got: {expected}");
+        assert_eq!(run(source).unwrap_err(), message);
+    };
+    arity("fn f(a, b = 1) { a } f()", "0, Expected: 1 to 2");
+    arity("fn f(a, b = 1) { a } f(1, 2, 3)", "3, Expected: 1 to 2");
+    arity("fn f(a, ...r) { a } f()", "0, Expected: at least 1");
+}
+
+#[test]
+fn macros_are_expanded_before_the_program_runs() {
+    assert_eq!(
+        run("let m = macro(a) { quote(unquote(a) + 1) }; m(2)").unwrap(),
+        "3"
+    );
+    let source = "let unless = macro(c, a, b) {
+            quote(if !(unquote(c)) { unquote(a) } else { unquote(b) })
+        };
+        unless(1 > 2, \"x\", \"y\")";
+    assert_eq!(run(source).unwrap(), "\"x\"");
+    // the names the macro declares don't capture the ones it's given
+    let source = "let tmp = 5;
+        let add = macro(x) { quote({ let tmp = 1; unquote(x) + tmp }) };
+        [add(tmp), tmp]";
+    assert_eq!(run(source).unwrap(), "[6, 5]");
+    // the argument is spliced in as it was written, not evaluated once
+    let source = "let twice = macro(a) { quote([unquote(a), unquote(a)]) }; let n = 0;
+        twice({ n += 1; n })";
+    assert_eq!(run(source).unwrap(), "[1, 2]");
+}
+
+#[test]
+fn a_macro_renames_a_name_only_where_the_quote_binds_it() {
+    // `len` is the builtin outside of the function whose param it is
+    let source = "let m = macro() { quote([len([1]), (fn(len) { len })(3)]) }; m()";
+    assert_eq!(run(source).unwrap(), "[1, 3]");
+    // and `x` is the global till the let of the quote
+    let source = "let m = macro() { quote({ let y = x; let x = 5; y + x }) }; let x = 1; m()";
+    assert_eq!(run(source).unwrap(), "6");
+}
+
+#[test]
+fn a_name_a_macro_doesnt_bind_is_the_one_where_the_macro_is_defined() {
+    let source = "let x = 1; let m = macro() { quote(x) }; fn f() { let x = 2; m() } f()";
+    assert_eq!(run(source).unwrap(), "1");
+    // a variable where the macro is called can't be reached, and the error has its name as written
+    let source = "let m = macro() { quote(y) }; fn f() { let y = 2; m() } f()";
+    let message = "Can't find `y`, it was never declared";
+    assert_eq!(try_eval_program(parse(source)).unwrap_err().message, message);
+    assert_eq!(compile_errors(source), [message]);
+}
+
+#[test]
+fn a_macro_that_cant_be_expanded_is_an_error_in_both_backends() {
+    let errors = [
+        (
+            "let m = macro(a) { 1 }; m(2)",
+            "`m` gave back a value of type int, but macros have to give back a quote",
+        ),
+        (
+            "let m = macro() { quote(1) }; m(2)",
+            "Incorrect number of arguments passed to `m`, Got: 1, Expected: 0",
+        ),
+    ];
+    for (source, message) in errors {
+        assert_eq!(
+            try_eval_program(parse(source)).unwrap_err().message,
+            message
+        );
+        assert_eq!(compile_errors(source), [message]);
+    }
+    let source = "quote(1 + 2)";
+    assert_eq!(try_eval_program(parse(source)).unwrap().to_string(), source);
+    assert_eq!(
+        compile_errors(source),
+        ["`quote` and `unquote` can only be used in macros when the program is compiled"]
+    );
+}
+
+#[test]
+fn tuples_and_destructuring_lets() {
+    let source = "let t = (1, \"a\", [2]); [t, (1,), (), (1), t[0], (1, 2) == (1, 2)]";
+    assert_eq!(
+        run(source).unwrap(),
+        "[(1, \"a\", [2]), (1,), (), 1, 1, true]"
+    );
+    let source = "fn divmod(a, b) { (a / b, a % b) } let (q, r) = divmod(17, 5); [q, r]";
+    assert_eq!(run(source).unwrap(), "[3, 2]");
+    let source = "let (a, [b, _], (c,)) = (1, [2, 3], (4,)); [a, b, c]";
+    assert_eq!(run(source).unwrap(), "[1, 2, 4]");
+    assert_eq!(
+        run("let [x] = 5").unwrap_err(),
+        "Expected an array to destructure, got a value of type int"
+    );
+}
+
+#[test]
+fn a_let_with_a_pattern_of_the_wrong_shape_is_an_error() {
+    let errors = [
+        (
+            "let [x, y] = [1, 2, 3]",
+            "Can't destructure an array of length 3 with a pattern of length 2",
+        ),
+        (
+            "let (x, y) = (1,)",
+            "Can't destructure a tuple of length 1 with a pattern of length 2",
+        ),
+        (
+            "let (x, y) = [1, 2]",
+            "Expected a tuple to destructure, got a value of type array",
+        ),
+    ];
+    // the emitter knows the shape of an array or tuple literal, so it finds these before running
+    for (source, message) in errors {
+        assert_eq!(
+            try_eval_program(parse(source)).unwrap_err().message,
+            message
+        );
+        assert_eq!(compile_errors(source), [message]);
+    }
+}
+
+// the message of the diagnostic, and the source from where it and its labels point on
+fn pointed_at<'a>(source: &'a str, diagnostic: &Diagnostic) -> (String, &'a str, Vec<&'a str>) {
+    let labels = diagnostic
+        .secondary
+        .iter()
+        .map(|label| &source[label.span.0..]);
+    let primary = &source[diagnostic.primary.span.0..];
+    (diagnostic.message.clone(), primary, labels.collect())
+}
+
+#[test]
+fn a_constant_cant_be_assigned_or_declared_again() {
+    let assign = "Can't assign to `a`, it's a constant";
+    let declare = "`a` is a constant, it can't be declared again";
+    let errors = [
+        ("const a = 1; a = 2", assign, "a = 2"),
+        ("const a = 1; a += 2", assign, "a += 2"),
+        ("const a = 1; fn f() { a = 2 } f()", assign, "a = 2 } f()"),
+        ("const a = 1; let a = 2", declare, "a = 2"),
+        ("const a = 1; { let a = 5; a }", declare, "a = 5; a }"),
+        ("const (b, a) = (1, 2); a = 3", assign, "a = 3"),
+    ];
+    for (source, message, primary) in errors {
+        // the first `a` in the source is the constant
+        let constant = &source[source.find('a').unwrap()..];
+        let expected = (message.to_string(), primary, vec![constant]);
+        let err = try_eval_program(parse(source)).unwrap_err();
+        assert_eq!(pointed_at(source, &err), expected);
+        let mut emitter = Emitter::new();
+        emitter.compile_program(parse(source));
+        let errors: Vec<_> = emitter
+            .errors()
+            .iter()
+            .map(|err| pointed_at(source, err))
+            .collect();
+        assert_eq!(errors, [expected]);
+    }
+    // a function is a scope of its own, where the name can be declared again
+    assert_eq!(
+        run("const a = 1; fn f() { let a = 2; a } [f(), a]").unwrap(),
+        "[2, 1]"
+    );
+    assert_eq!(run("const a = 1; let f = fn() { a }; f()").unwrap(), "1");
+}
+
+#[test]
+fn structs_with_field_access_and_update() {
+    let source = "struct Point { x, y } let p = Point { x: 1, y: 2 }; p.x = p.y + 10;
+        [p, p.x, Point { y: 2, x: 12 } == p]";
+    assert_eq!(run(source).unwrap(), "[Point { x: 12, y: 2 }, 12, true]");
+    assert_eq!(
+        run("struct P { x } let x = 3; P { x }").unwrap(),
+        "P { x: 3 }"
+    );
+    assert_eq!(run("struct P { x } P").unwrap(), "struct P { x }");
+    // a struct is a value, updating a copy leaves the original as it is
+    let source = "struct P { x } let p = P { x: 1 }; let q = p; q.x = 5; [p.x, q.x]";
+    assert_eq!(run(source).unwrap(), "[1, 5]");
+    // structs of different types are never equal
+    let source = "struct P { x } struct Q { x } P { x: 1 } == Q { x: 1 }";
+    assert_eq!(run(source).unwrap(), "false");
+}
+
+#[test]
+fn a_struct_has_the_fields_of_its_type() {
+    let errors = [
+        (
+            "struct P { x, y } P { x: 1 }",
+            "Missing fields for the struct `P`: `y`",
+        ),
+        (
+            "struct P { x } P { x: 1, z: 2 }",
+            "The struct `P` has no field `z`",
+        ),
+        (
+            "struct P { x } P { x: 1 }.z",
+            "The struct `P` has no field `z`",
+        ),
+        (
+            "struct P { x } let p = P { x: 1 }; p.z = 2",
+            "The struct `P` has no field `z`",
+        ),
+        (
+            "let a = 1; a { x: 1 }",
+            "`a` isn't a struct type, it's a value of type int",
+        ),
+    ];
+    for (source, message) in errors {
+        assert_eq!(run(source).unwrap_err(), message);
+    }
+}
+
+#[test]
+fn enum_variants_are_made_and_matched() {
+    let source = "enum Shape { Circle(r), Rect(w, h), Empty }
+        fn area(s) {
+            match s { Shape.Circle(r) => 3 * r * r, Shape.Rect(w, h) => w * h, Shape.Empty => 0 }
+        }
+        [area(Shape.Circle(2)), area(Shape.Rect(2, 5)), area(Shape.Empty)]";
+    assert_eq!(run(source).unwrap(), "[12, 10, 0]");
+    let source = "enum Shape { Circle(r), Empty }
+        [Shape.Circle(1), Shape.Empty, Shape.Circle, Shape]";
+    let value = "[Shape.Circle(1), Shape.Empty, fn Shape.Circle, enum Shape { Circle(r), Empty }]";
+    assert_eq!(run(source).unwrap(), value);
+    let source = "enum Shape { Circle(r) } enum Other { Circle(r) }
+        [Shape.Circle(1) == Shape.Circle(1), Shape.Circle(1) == Shape.Circle(2),
+            Shape.Circle(1) == Other.Circle(1)]";
+    assert_eq!(run(source).unwrap(), "[true, false, false]");
+}
+
+#[test]
+fn a_variant_has_the_fields_of_its_declaration() {
+    let errors = [
+        (
+            "enum Shape { Circle(r) } Shape.Circle(1, 2)",
+            "Incorrect number of arguments passed to `Shape.Circle`, Got: 2, Expected: 1",
+        ),
+        (
+            "enum Shape { Circle(r) } Shape.Square(1)",
+            "The enum `Shape` has no variant `Square`",
+        ),
+        (
+            "enum Shape { Circle(r) } match Shape.Circle(1) { Shape.Circle(a, b) => a }",
+            "The variant `Shape.Circle` has 1 field, the pattern has 2",
+        ),
+        (
+            "enum Shape { Circle(r) } match Shape.Circle(1) { Shape.Square(a) => a, _ => 0 }",
+            "The enum `Shape` has no variant `Square`",
+        ),
+    ];
+    for (source, message) in errors {
+        assert_eq!(run(source).unwrap_err(), message);
+    }
+}
+
+#[test]
+fn an_int_that_overflows_is_an_error_in_both_backends() {
+    assert_eq!(
+        run("9223372036854775807 + 1").unwrap_err(),
+        "9223372036854775807 + 1 is too big for an int"
+    );
+    assert_eq!(
+        run("let x = -9223372036854775807; x -= 2").unwrap_err(),
+        "-9223372036854775807 - 2 is too big for an int"
+    );
+    assert_eq!(
+        run("4611686018427387904 * 2").unwrap_err(),
+        "4611686018427387904 * 2 is too big for an int"
+    );
+    assert_eq!(run("9223372036854775806 + 1").unwrap(), "9223372036854775807");
+}
@@ -0,0 +1,54 @@
+use interpreter::evaluation::try_eval_program;
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+
+// the first error in the source, rendered against it
+fn rendered(source: &str) -> String {
+    let (program, errors) = Parser::new(Lexer::from_string(source.into())).parse_program();
+    let diagnostic = match errors.first() {
+        Some(err) => err.diagnostic(),
+        None => try_eval_program(program).unwrap_err(),
+    };
+    diagnostic.render(source, "main.txt")
+}
+
+#[test]
+fn a_parse_error_points_at_where_it_is() {
+    let expected = "\
+error: Expected an expression, found `;`
+ --> main.txt:1:12
+  |
+1 | let x = 1 +;
+  |            ^ an expression can't start with this
+";
+    assert_eq!(rendered("let x = 1 +;"), expected);
+}
+
+#[test]
+fn an_arity_error_points_at_the_function_too() {
+    let expected = "\
+error: Incorrect number of arguments passed, Got: 3, Expected: 1 to 2
+ --> main.txt:2:2
+  |
+1 | let f = fn(a, b = 1) { a };
+  |         -- function defined here
+2 | f(1, 2, 3)
+  |  ^
+";
+    assert_eq!(
+        rendered("let f = fn(a, b = 1) { a };\nf(1, 2, 3)"),
+        expected
+    );
+}
+
+#[test]
+fn tabs_are_expanded_so_the_underline_lines_up() {
+    let expected = "\
+error: Division by zero
+ --> main.txt:2:12
+  |
+2 |     let b = a / 0;
+  |               ^
+";
+    assert_eq!(rendered("let a = 1;\n\tlet b = a / 0;\nb"), expected);
+}
@@ -0,0 +1,103 @@
+use interpreter::evaluation::try_eval_program;
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+
+// the value of the program, or the message of the error it stopped with
+fn run(source: &str) -> Result<String, String> {
+    let (program, errors) = Parser::new(Lexer::from_string(source.into())).parse_program();
+    assert!(errors.is_empty(), "{source:?} doesn't parse: {errors:?}");
+    try_eval_program(program)
+        .map(|value| value.to_string())
+        .map_err(|err| err.message)
+}
+
+// the message of the error the program stops with, and the source from where it points on
+fn error(source: &str) -> (String, &str) {
+    let (program, errors) = Parser::new(Lexer::from_string(source.into())).parse_program();
+    assert!(errors.is_empty(), "{source:?} doesn't parse: {errors:?}");
+    let err = try_eval_program(program).unwrap_err();
+    (err.message, &source[err.primary.span.0..])
+}
+
+#[test]
+fn a_signal_in_the_value_of_an_assignment_isnt_stored() {
+    let source = "let x = 0; for i in [1, 2] { x = if i == 2 { break } else { i } } x";
+    assert_eq!(run(source).unwrap(), "1");
+    let source = "struct P { x } let p = P { x: 0 };
+        for i in [1, 2] { p.x = if i == 2 { break } else { i } } p.x";
+    assert_eq!(run(source).unwrap(), "1");
+    let source =
+        "fn f(c) { let x = 0; x = if c { return 7 } else { 1 }; x + 10 } [f(true), f(false)]";
+    assert_eq!(run(source).unwrap(), "[7, 11]");
+}
+
+#[test]
+fn indexing_an_array_is_checked() {
+    let out_of_bounds = "Index 2 is out of bounds for an array of length 2";
+    assert_eq!(error("let a = [1, 2]; a[2]"), (out_of_bounds.into(), "[2]"));
+    let out_of_bounds = "Index -1 is out of bounds for an array of length 2";
+    assert_eq!(error("[1, 2][-1]"), (out_of_bounds.into(), "[-1]"));
+    let not_an_int = "Arrays are indexed by ints, got bool";
+    assert_eq!(error("[1][true]"), (not_an_int.into(), "[true]"));
+}
+
+#[test]
+fn array_builtins_check_their_arguments() {
+    let message = "`len` takes 1 argument, got 2".into();
+    assert_eq!(error("len([1], [2])"), (message, "([1], [2])"));
+    let message = "`push` is not supported for int".into();
+    assert_eq!(error("push(1, 2)"), (message, "(1, 2)"));
+}
+
+#[test]
+fn only_ints_bools_and_strings_are_hash_keys() {
+    let unhashable = |r#type: &str| {
+        format!("Values of type {type} can't be hash keys, only ints, bools and strings can")
+    };
+    assert_eq!(
+        error("{fn() { 1 }: 2}"),
+        (unhashable("function"), "{fn() { 1 }: 2}")
+    );
+    assert_eq!(
+        error("let h = {1: 2}; h[[1]]"),
+        (unhashable("array"), "[[1]]")
+    );
+}
+
+#[test]
+fn an_arity_error_points_at_the_call_and_the_function() {
+    let source = "fn f(a) { a } f(1, 2)";
+    let (program, _) = Parser::new(Lexer::from_string(source.into())).parse_program();
+    let err = try_eval_program(program).unwrap_err();
+    assert_eq!(&source[err.primary.span.0..], "(1, 2)");
+    let [defined] = &err.secondary[..] else {
+        panic!("{:?}", err.secondary)
+    };
+    assert_eq!(defined.message, "function defined here");
+    assert_eq!(&source[defined.span.0..defined.span.1], "fn");
+}
+
+#[test]
+fn recursing_too_deep_is_an_error_and_not_a_stack_overflow() {
+    // a debug build needs more stack for the calls up to the limit than a test thread has
+    let (shallow, deep) = std::thread::Builder::new()
+        .stack_size(256 << 20)
+        .spawn(|| {
+            let source = "fn f(n) { if n == 0 { 0 } else { f(n - 1) } } f(150)";
+            let shallow = run(source);
+            let deep = error("fn f(n) { if n == 0 { 0 } else { f(n - 1) } } f(100000)");
+            (shallow, deep)
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    assert_eq!(shallow.unwrap(), "0");
+    let message = "Maximum call depth of 200 exceeded".into();
+    assert_eq!(deep, (message, "(n - 1) } } f(100000)"));
+}
+
+#[test]
+fn quotes_are_shown_as_code_in_an_error() {
+    let message = "==/!= operator is not valid for quote(1 + 2) and quote(3)".into();
+    assert_eq!(error("quote(1 + 2) == quote(3)"), (message, "== quote(3)"));
+}
@@ -0,0 +1,180 @@
+use interpreter::lexer::Lexer;
+use interpreter::parser::incremental::{Document, Edit};
+use interpreter::parser::Parser;
+
+const SOURCE: &str = "let a = 1;
+let b = fn(x) { x + a };
+b(2) // c
+
+let c = if a < 2 { 3 } else { 4 }
+c * 2; (5)
+let d = 1.5e3;
+struct Point { x, y }
+match d { 1 => a, _ => b(c) }";
+
+// bits of text that open, close, join and split statements
+const SNIPPETS: &[&str] = &[
+    "1",
+    ";",
+    "(",
+    ")",
+    "x",
+    " ",
+    "\n",
+    "let q = 4;",
+    "}",
+    "{",
+    "=",
+    "//",
+    "+ 2",
+    "e",
+    ".",
+    "fn",
+    "\"",
+    "if a { 1 } else",
+    "let",
+    ",",
+];
+
+// the ast and the errors, spans included
+fn parsed(document: &Document) -> String {
+    format!(
+        "{:?} {:?}",
+        document.program(),
+        document.errors().collect::<Vec<_>>()
+    )
+}
+
+fn reparsed(source: &str) -> String {
+    let (program, errors) = Parser::new(Lexer::from_string(source.into())).parse_program();
+    format!("{:?} {:?}", program, errors)
+}
+
+// a small linear congruential generator, so a failure can always be reproduced
+struct Random(u64);
+
+impl Random {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) as usize % n
+    }
+}
+
+// the offsets where a statement may start or end, right before or after a separator
+// (the sources here are ascii, so every offset is a char boundary)
+fn boundaries(source: &str) -> Vec<usize> {
+    let mut offsets = vec![0, source.len()];
+    for (i, c) in source.char_indices() {
+        if matches!(c, ';' | '\n' | '}') {
+            offsets.extend([i, i + 1]);
+        }
+    }
+    offsets
+}
+
+// applies random edits made by `edit` and checks after each one that the document
+// is the same as parsing its whole source again
+fn check_edits(seed: u64, edit: impl Fn(&mut Random, &str, usize) -> Edit) {
+    let mut random = Random(seed);
+    for _ in 0..200 {
+        let mut document = Document::new(SOURCE.to_string());
+        for _ in 0..10 {
+            let source = document.source().to_string();
+            let offsets = boundaries(&source);
+            // edit around a statement boundary most of the time, anywhere otherwise
+            let at = if random.below(4) == 0 {
+                random.below(source.len() + 1)
+            } else {
+                offsets[random.below(offsets.len())]
+            };
+            document.apply(edit(&mut random, &source, at));
+            assert_eq!(
+                parsed(&document),
+                reparsed(document.source()),
+                "source: {:?}",
+                document.source()
+            );
+        }
+    }
+}
+
+fn span_around(random: &mut Random, source: &str, at: usize) -> (usize, usize) {
+    let start = at.saturating_sub(random.below(6));
+    let end = (at + random.below(6)).min(source.len());
+    (start, end)
+}
+
+#[test]
+fn inserting_matches_reparsing() {
+    check_edits(1, |random, _, at| {
+        Edit::new((at, at), SNIPPETS[random.below(SNIPPETS.len())])
+    });
+}
+
+#[test]
+fn deleting_matches_reparsing() {
+    check_edits(2, |random, source, at| {
+        Edit::new(span_around(random, source, at), "")
+    });
+}
+
+#[test]
+fn replacing_matches_reparsing() {
+    check_edits(3, |random, source, at| {
+        let text = SNIPPETS[random.below(SNIPPETS.len())];
+        Edit::new(span_around(random, source, at), text)
+    });
+}
+
+#[test]
+fn edits_spanning_several_statements_match_reparsing() {
+    let mut document = Document::new(SOURCE.to_string());
+    let start = SOURCE.find("b(2)").unwrap();
+    let end = SOURCE.find("c * 2").unwrap();
+    document.apply(Edit::new((start, end), "let e = [1, 2];\n"));
+    assert_eq!(parsed(&document), reparsed(document.source()));
+
+    // joins the first statement with everything up to the struct
+    let end = document.source().find("struct").unwrap();
+    document.apply(Edit::new((9, end), " + "));
+    assert_eq!(parsed(&document), reparsed(document.source()));
+}
+
+#[test]
+fn an_edit_inside_a_statement_only_parses_that_statement_again() {
+    let mut document = Document::new(SOURCE.to_string());
+    assert_eq!(document.reparsed(), document.program().statements.len());
+    let at = SOURCE.find("{ 3 }").unwrap() + 2;
+    document.apply(Edit::new((at, at + 1), "30"));
+    assert_eq!(document.reparsed(), 1);
+    assert_eq!(parsed(&document), reparsed(document.source()));
+
+    // `c * 2; (5)` becomes the one statement `c * 2 (5)`, the ones after it are still reused
+    let len = document.program().statements.len();
+    let at = document.source().find("; (5)").unwrap();
+    document.apply(Edit::new((at, at + 1), ""));
+    assert_eq!(document.reparsed(), 1);
+    assert_eq!(document.program().statements.len(), len - 1);
+    assert_eq!(parsed(&document), reparsed(document.source()));
+}
+
+#[test]
+fn an_edit_brings_in_and_takes_away_errors() {
+    let mut document = Document::new("let a = 1;\nlet b = a + 2;\nb".to_string());
+    let at = document.source().find("2;").unwrap();
+    document.apply(Edit::new((at, at + 1), ")"));
+    let messages: Vec<_> = document
+        .errors()
+        .map(|err| err.diagnostic().message)
+        .collect();
+    assert_eq!(messages, ["Expected an expression, found `)`"]);
+    document.apply(Edit::new((at, at + 1), "3"));
+    assert_eq!(document.errors().count(), 0);
+    assert_eq!(
+        document.program().to_string(),
+        "let a = 1;\nlet b = a + 3;\nb;\n"
+    );
+}
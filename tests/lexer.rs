@@ -0,0 +1,67 @@
+use interpreter::lexer::Lexer;
+use interpreter::token::symbol::Symbol;
+use interpreter::token::token_type::TokenType;
+use interpreter::token::TriviaKind;
+
+// the trivia and the text of every token, one after the other
+fn relexed(source: &str) -> String {
+    let mut text = String::new();
+    for token in Lexer::from_string(source.into()).with_trivia() {
+        for trivia in &token.leading_trivia {
+            text += trivia.text(source);
+        }
+        text += token.text(source);
+    }
+    text
+}
+
+#[test]
+fn the_tokens_and_their_trivia_give_back_the_source() {
+    let sources = [
+        "",
+        "   ",
+        "// only a comment",
+        "let a = 1; // one\n\n\t// two\r\nlet b = a  +  2;\n",
+        "fn f(x) {\n    x * 2 // twice\n}\n\nf(0x1_f) // end without a newline",
+        "\"a string // not a comment\" @ #",
+    ];
+    for source in sources {
+        assert_eq!(relexed(source), source);
+    }
+}
+
+#[test]
+fn comments_and_whitespace_are_told_apart() {
+    let source = "a // c\n  b";
+    let tokens: Vec<_> = Lexer::from_string(source.into()).with_trivia().collect();
+    let trivia: Vec<_> = tokens[1]
+        .leading_trivia
+        .iter()
+        .map(|trivia| (trivia.kind, trivia.text(source)))
+        .collect();
+    assert_eq!(
+        trivia,
+        [
+            (TriviaKind::Whitespace, " "),
+            (TriviaKind::Comment, "// c"),
+            (TriviaKind::Whitespace, "\n  ")
+        ]
+    );
+    assert!(matches!(tokens.last().unwrap().r#type, TokenType::Eof));
+    // without asking for it, there's no trivia at all
+    let mut tokens = Lexer::from_string(source.into());
+    assert!(tokens.all(|token| token.leading_trivia.is_empty()));
+}
+
+#[test]
+fn a_name_is_the_same_symbol_every_time_it_is_lexed() {
+    let source = "counter + counter2 * counter";
+    let names: Vec<_> = Lexer::from_string(source.into())
+        .filter_map(|token| token.get_ident_name())
+        .collect();
+    assert_eq!(names[0], names[2]);
+    assert_ne!(names[0], names[1]);
+    assert_eq!(names[1].as_str(), "counter2");
+    // and the same symbol as the program gets when it asks for it
+    assert_eq!(names[0], Symbol::intern("counter"));
+}
@@ -0,0 +1,166 @@
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use interpreter::emitter::Emitter;
+use interpreter::evaluation::eval_modules;
+use interpreter::module::{Loader, Module, ModuleError};
+use interpreter::vm::Vm;
+
+// a directory under the system's temp dir, removed with everything in it when dropped
+struct TempDir(PathBuf);
+
+impl Deref for TempDir {
+    type Target = Path;
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+// a directory of its own for every test, with the files in it
+fn files(test: &str, files: &[(&str, &str)]) -> TempDir {
+    let dir = TempDir(std::env::temp_dir().join(format!("modules-{test}-{}", std::process::id())));
+    for (path, source) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, source).unwrap();
+    }
+    dir
+}
+
+fn load(entry: PathBuf, search_path: Vec<PathBuf>) -> Result<Vec<Module>, ModuleError> {
+    Loader::new().with_search_path(search_path).load(&entry)
+}
+
+// the value of the entry, which evaluating and compiling the modules have to agree on
+fn run(entry: PathBuf, search_path: Vec<PathBuf>) -> String {
+    let modules = load(entry, search_path).unwrap();
+    let evaluated = eval_modules(&modules).unwrap().to_string();
+    let mut emitter = Emitter::new();
+    emitter.compile_modules(&modules).unwrap();
+    let compiled = Vm::new(emitter.emit()).run().unwrap().to_string();
+    assert_eq!(evaluated, compiled);
+    evaluated
+}
+
+// the message of every diagnostic, and the file it's in
+fn diagnostics(err: ModuleError) -> Vec<(String, String)> {
+    err.diagnostics
+        .into_iter()
+        .map(|file| {
+            let name = file.path.file_name().unwrap().to_string_lossy().into();
+            (file.diagnostic.message, name)
+        })
+        .collect()
+}
+
+const MATH: &str = "export fn double(x) { x * 2 }
+export const pi = 3;
+let hidden = 1;
+export fn inverse(a) { 1 / a }";
+
+#[test]
+fn a_module_uses_what_the_modules_it_imports_export() {
+    let dir = files(
+        "exports",
+        &[
+            ("lib/math.si", MATH),
+            (
+                "main.si",
+                "import \"lib/math\"; let hidden = 10; [double(pi), hidden]",
+            ),
+            ("vendor/util.si", "export let answer = 42;"),
+            (
+                "search.si",
+                "import \"util\"; import \"lib/math.si\"; double(answer)",
+            ),
+        ],
+    );
+    // the globals of every module are its own
+    assert_eq!(run(dir.join("main.si"), vec![]), "[6, 10]");
+    // a module that isn't next to the importer is looked for in the search path
+    assert_eq!(run(dir.join("search.si"), vec![dir.join("vendor")]), "84");
+    let err = load(dir.join("search.si"), vec![]).unwrap_err();
+    let missing = ("Can't find the module `util`".into(), "search.si".into());
+    assert_eq!(diagnostics(err), [missing]);
+}
+
+#[test]
+fn what_a_module_doesnt_export_stays_in_it() {
+    let dir = files(
+        "hidden",
+        &[
+            ("lib/math.si", MATH),
+            ("main.si", "import \"lib/math\"; hidden"),
+        ],
+    );
+    let modules = load(dir.join("main.si"), vec![]).unwrap();
+    let evaluated = diagnostics(eval_modules(&modules).unwrap_err());
+    let undeclared = ("Can't find `hidden`, it was never declared".into(), "main.si".into());
+    assert_eq!(evaluated, [undeclared]);
+    let compiled = diagnostics(Emitter::new().compile_modules(&modules).unwrap_err());
+    assert_eq!(compiled, evaluated);
+}
+
+#[test]
+fn modules_cant_import_each_other_in_a_cycle() {
+    let dir = files(
+        "cycle",
+        &[("a.si", "import \"b\"; 1"), ("b.si", "import \"a\"; 2")],
+    );
+    let err = load(dir.join("a.si"), vec![]).unwrap_err();
+    let cycle = (
+        "Modules can't import each other in a cycle".into(),
+        "b.si".into(),
+    );
+    assert_eq!(diagnostics(err), [cycle]);
+}
+
+#[test]
+fn an_error_points_into_the_module_it_comes_from() {
+    let dir = files(
+        "errors",
+        &[
+            ("lib/math.si", MATH),
+            ("zero.si", "import \"lib/math\"; inverse(0)"),
+            ("arity.si", "import \"lib/math\"; double()"),
+            ("assign.si", "import \"lib/math\"; pi = 4"),
+        ],
+    );
+    let modules = load(dir.join("zero.si"), vec![]).unwrap();
+    let err = eval_modules(&modules).unwrap_err();
+    assert_eq!(
+        diagnostics(err),
+        [("Division by zero".into(), "math.si".into())]
+    );
+
+    let modules = load(dir.join("arity.si"), vec![]).unwrap();
+    let err = eval_modules(&modules).unwrap_err();
+    let arity = "Incorrect number of arguments passed to `double`, Got: 0, Expected: 1";
+    let expected = [
+        (arity.into(), "arity.si".into()),
+        ("function defined here".into(), "math.si".into()),
+    ];
+    assert_eq!(diagnostics(err), expected);
+
+    // a constant stays one in the modules that import it
+    let modules = load(dir.join("assign.si"), vec![]).unwrap();
+    let expected = [
+        (
+            "Can't assign to `pi`, it's a constant".into(),
+            "assign.si".into(),
+        ),
+        (
+            "`pi` is declared as a constant here".into(),
+            "math.si".into(),
+        ),
+    ];
+    assert_eq!(diagnostics(eval_modules(&modules).unwrap_err()), expected);
+    let err = Emitter::new().compile_modules(&modules).unwrap_err();
+    assert_eq!(diagnostics(err), expected);
+}
@@ -0,0 +1,236 @@
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+
+// every error in the source: its message, what it says about the place it points at,
+// and the text there
+fn errors(source: &str) -> Vec<(String, String, &str)> {
+    let (_, errors) = Parser::new(Lexer::from_string(source.into())).parse_program();
+    errors
+        .iter()
+        .map(|err| {
+            let diagnostic = err.diagnostic();
+            let (start, end) = diagnostic.primary.span;
+            let text = &source[start..end];
+            (diagnostic.message, diagnostic.primary.message, text)
+        })
+        .collect()
+}
+
+// the error for a param with the name of one before it
+fn duplicate(name: &str) -> Vec<(String, String, &str)> {
+    vec![(
+        format!("Expected another param, found identifier `{name}`"),
+        format!("the param `{name}` is there already"),
+        name,
+    )]
+}
+
+#[test]
+fn a_param_can_only_be_there_once() {
+    assert_eq!(errors("let f = fn(a, a = 1) { a };"), duplicate("a"));
+    assert_eq!(errors("fn f(a, b = 1, ...a) { a }"), duplicate("a"));
+    assert_eq!(errors("let m = macro(x, x) { x };"), duplicate("x"));
+    assert_eq!(errors("fn f(a, b = a, ...c) { a }"), []);
+}
+
+#[test]
+fn the_rest_param_is_last_and_defaults_are_at_the_end() {
+    let found = (
+        "Expected `)`, found identifier `a`".to_string(),
+        "the rest param has to be the last one".to_string(),
+        "a",
+    );
+    assert_eq!(errors("fn f(...r, a) { a }"), [found]);
+    let found = (
+        "Expected `=`, found `)`".to_string(),
+        "the params after one with a default need a default too".to_string(),
+        ")",
+    );
+    assert_eq!(errors("fn f(a = 1, b) { a }"), [found]);
+}
+
+#[test]
+fn an_integer_literal_has_to_fit_and_have_digits_of_its_base() {
+    let invalid = |literal: &str, why: &str| {
+        let message = format!("Expected a valid integer literal, found integer `{literal}`");
+        assert_eq!(
+            errors(&format!("1 + {literal};")),
+            [(message, why.to_string(), literal)]
+        );
+    };
+    let too_big = "integer literal `9223372036854775808` is out of range, \
+        the maximum is 9223372036854775807";
+    invalid("9223372036854775808", too_big);
+    let too_big = "integer literal `0xffffffffffffffffff` is out of range, \
+        the maximum is 9223372036854775807";
+    invalid("0xffffffffffffffffff", too_big);
+    invalid("0x", "integer literal `0x` has no digits");
+    invalid(
+        "0b102",
+        "invalid digit `2` in base 2 integer literal `0b102`",
+    );
+    invalid("0o8", "invalid digit `8` in base 8 integer literal `0o8`");
+}
+
+#[test]
+fn a_float_literal_has_to_fit() {
+    assert_eq!(
+        errors("1.5e999"),
+        [(
+            "Expected a valid float literal, found float `1.5e999`".to_string(),
+            "float literal `1.5e999` is out of range".to_string(),
+            "1.5e999"
+        )]
+    );
+}
+
+#[test]
+fn every_statement_with_an_error_is_reported() {
+    let source = "let a = ; let b = 2; let c 3; b";
+    let found = [
+        (
+            "Expected an expression, found `;`".to_string(),
+            "an expression can't start with this".to_string(),
+            ";",
+        ),
+        (
+            "Expected `=`, found integer `3`".to_string(),
+            String::new(),
+            "3",
+        ),
+    ];
+    assert_eq!(errors(source), found);
+    // and the statements around them are still there
+    let (program, _) = Parser::new(Lexer::from_string(source.into())).parse_program();
+    assert_eq!(program.to_string(), "<error>;\nlet b = 2;\n<error>;\nb;\n");
+}
+
+#[test]
+fn only_variables_and_fields_can_be_assigned_to() {
+    for source in ["1 = 2;", "let a = [1]; a[0] = 5;"] {
+        let found = (
+            "Expected a variable on the left of the assignment, found `=`".to_string(),
+            "only variables and their fields can be assigned to".to_string(),
+            "=",
+        );
+        assert_eq!(errors(source), [found]);
+    }
+}
+
+#[test]
+fn break_and_continue_have_to_be_in_a_loop() {
+    let outside = |keyword: &'static str| {
+        let message = format!("Expected a statement, found `{keyword}`");
+        (
+            message,
+            format!("`{keyword}` is only allowed inside a loop"),
+            keyword,
+        )
+    };
+    assert_eq!(errors("break;"), [outside("break")]);
+    assert_eq!(
+        errors("while true { fn() { continue } }"),
+        [outside("continue")]
+    );
+    let found = (
+        "Expected a statement, found label `'b`".to_string(),
+        "there's no loop with label `'b` around this".to_string(),
+        "'b",
+    );
+    assert_eq!(errors("'a: while true { break 'b }"), [found]);
+}
+
+#[test]
+fn a_logical_operator_needs_a_left_side() {
+    let found = (
+        "Expected an expression, found `&&`".to_string(),
+        "an expression can't start with this".to_string(),
+        "&&",
+    );
+    assert_eq!(errors("let a = && true;"), [found]);
+}
+
+#[test]
+fn else_is_followed_by_a_block_or_an_if() {
+    let found = (
+        "Expected `{`, found integer `2`".to_string(),
+        String::new(),
+        "2",
+    );
+    assert_eq!(errors("if true { 1 } else 2;"), [found]);
+}
+
+#[test]
+fn match_arms_are_separated_by_commas() {
+    let found = (
+        "Expected `,` or `}`, found integer `2`".to_string(),
+        String::new(),
+        "2",
+    );
+    assert_eq!(errors("match 1 { 1 => 1 2 => 2 }")[0], found);
+    let found = (
+        "Expected a pattern, found `=>`".to_string(),
+        "a pattern can't start with this".to_string(),
+        "=>",
+    );
+    assert_eq!(errors("match 1 { => 1 }")[0], found);
+}
+
+#[test]
+fn a_pattern_binds_a_name_only_once() {
+    let duplicate = |name: &'static str| {
+        let message = format!("Expected another binding, found identifier `{name}`");
+        (
+            message,
+            format!("the binding `{name}` is there already"),
+            name,
+        )
+    };
+    assert_eq!(errors("let (a, a) = (1, 2);"), [duplicate("a")]);
+    assert_eq!(errors("let [a, (b, [c, b])] = x;"), [duplicate("b")]);
+    // (the rest of a match with an error doesn't parse either)
+    assert_eq!(
+        errors("match x { (a, [b, _, a]) => a, _ => 0 }")[0],
+        duplicate("a")
+    );
+    assert_eq!(errors("match x { S.V(a, a) => a }")[0], duplicate("a"));
+    // the same name in different arms is fine
+    assert_eq!(errors("match x { (a, _) => a, [a] => a }"), []);
+}
+
+#[test]
+fn a_let_pattern_only_binds() {
+    let found = (
+        "Expected a name, `_`, a tuple or an array, found integer `1`".to_string(),
+        "a let can't compare values, use a match for that".to_string(),
+        "1",
+    );
+    assert_eq!(errors("let (1, b) = (1, 2);"), [found]);
+}
+
+#[test]
+fn a_field_is_there_only_once() {
+    let duplicate = (
+        "Expected another field, found identifier `x`".to_string(),
+        "the field `x` is there already".to_string(),
+        "x",
+    );
+    assert_eq!(errors("struct P { x, x }")[0], duplicate);
+    assert_eq!(errors("P { x: 1, x: 2 }")[0], duplicate);
+}
+
+#[test]
+fn a_variant_and_its_fields_are_there_only_once() {
+    let variant = (
+        "Expected another variant, found identifier `Circle`".to_string(),
+        "the variant `Circle` is there already".to_string(),
+        "Circle",
+    );
+    assert_eq!(errors("enum Shape { Circle(r), Circle(s) }")[0], variant);
+    let field = (
+        "Expected another field, found identifier `r`".to_string(),
+        "the field `r` is there already".to_string(),
+        "r",
+    );
+    assert_eq!(errors("enum Shape { Circle(r, r) }")[0], field);
+}
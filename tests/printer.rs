@@ -0,0 +1,143 @@
+use interpreter::ast::fold::Folder;
+use interpreter::ast::Program;
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+use interpreter::printer::format_source;
+use interpreter::token::{Span, Token};
+
+const PROGRAMS: &[&str] = &[
+    "let a = 1; let b = -a + 2 * (3 - a) ** 2 ** 2; (a - b) - (a - (b - 1))",
+    "let f = fn(x, y = 2, ...rest) { x + y }; f(1)(2)[0]; -(-a); !!b",
+    "fn fib(n) { if n < 2 { return n } fib(n - 1) + fib(n - 2) } fib(10)",
+    "let a = [1, 2.5, 0xff, \"s\"]; let t = (1,); let h = {1: 2, \"k\": [3]}; h[1]",
+    "let x = if a { 1 } else if b { 2 } else { 3 }; { let y = x; y }",
+    "'outer: while a { for i in [1, 2] { if i == 2 { break 'outer } continue } }",
+    "struct Point { x, y } let p = Point { x: 1, y }; p.x = p.y + 1; p.x",
+    "enum Shape { Circle(r), Empty } match Shape.Circle(1) { Shape.Circle(r) if r > 0 => r, _ => 0 }",
+    "let (a, [b, _]) = (1, [2, 3]); const c = a; x = y += 1; a.b.c = 1",
+    "if (Point { x: 1 }).x == 1 { match (Point { x: 1 }) { p => p } }",
+    "let m = macro(a) { quote(unquote(a) + 1) }; m(2)",
+];
+
+// the spans of the same program are different once it is printed
+struct EraseSpans;
+
+impl Folder for EraseSpans {
+    fn fold_token(&mut self, mut token: Token) -> Token {
+        token.span = (0, 0);
+        token
+    }
+
+    fn fold_error(&mut self, _: Span) -> Span {
+        (0, 0)
+    }
+}
+
+fn parse(source: &str) -> Program {
+    let (program, errors) = Parser::new(Lexer::from_string(source.into())).parse_program();
+    assert!(errors.is_empty(), "{source:?} doesn't parse: {errors:?}");
+    program
+}
+
+fn without_spans(program: Program) -> String {
+    format!("{:?}", EraseSpans.fold_program(program))
+}
+
+#[test]
+fn parsing_the_printed_program_gives_the_same_ast() {
+    for source in PROGRAMS {
+        let program = parse(source);
+        let printed = program.to_string();
+        assert_eq!(
+            without_spans(parse(&printed)),
+            without_spans(program),
+            "printed as {printed:?}"
+        );
+    }
+}
+
+#[test]
+fn printing_is_idempotent() {
+    for source in PROGRAMS {
+        let printed = format_source(source).unwrap();
+        assert_eq!(format_source(&printed).unwrap(), printed);
+    }
+}
+
+#[test]
+fn comments_between_statements_stay_in_place() {
+    let source = "// header
+let a = 1; // one
+let f = fn(x) {
+    // inside
+    x + 1 // trailing
+    // end of body
+};
+// the end
+";
+    let expected = "// header
+let a = 1;  // one
+let f = fn(x) {
+    // inside
+    x + 1  // trailing
+    // end of body
+};
+// the end
+";
+    assert_eq!(format_source(source).unwrap(), expected);
+}
+
+#[test]
+fn a_comment_before_else_stays_before_else() {
+    let source = "let c = if a < 2 { 3 } // c
+else { 4 };
+c;
+";
+    assert_eq!(format_source(source).unwrap(), source);
+}
+
+#[test]
+fn statements_with_comments_inside_are_kept_as_written() {
+    let source = "let a = f(1, // first
+    2);
+fn g() {
+    let b = [1, // one
+        2];
+    b
+}
+";
+    let formatted = format_source(source).unwrap();
+    assert_eq!(formatted, source);
+    assert_eq!(
+        without_spans(parse(&formatted)),
+        without_spans(parse(source))
+    );
+}
+
+#[test]
+fn comments_in_an_empty_block_are_kept() {
+    let formatted = format_source("fn f() {\n  // todo\n}\nf()").unwrap();
+    assert_eq!(formatted, "fn f() {\n  // todo\n}\nf();\n");
+}
+
+#[test]
+fn a_comment_at_the_end_of_a_nested_block_stays_in_it() {
+    let source = "if a {\n    if b {\n        1\n        // inner\n    }\n    // outer\n};\n";
+    assert_eq!(format_source(source).unwrap(), source);
+}
+
+#[test]
+fn only_the_parentheses_the_ast_needs_are_printed() {
+    let formatted = format_source("((1 + 2)) * (3 * 4) - (5 - 6) ** (2 ** 3); -(a + b)").unwrap();
+    assert_eq!(
+        formatted,
+        "(1 + 2) * (3 * 4) - (5 - 6) ** 2 ** 3;\n-(a + b);\n"
+    );
+}
+
+#[test]
+fn a_source_with_errors_isnt_formatted() {
+    let errors = format_source("let a = 1;\nlet b = ;").unwrap_err();
+    let messages: Vec<_> = errors.iter().map(|err| err.diagnostic().message).collect();
+    assert_eq!(messages, ["Expected an expression, found `;`"]);
+}
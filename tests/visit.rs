@@ -0,0 +1,77 @@
+use interpreter::ast::expression::{Block, Ident};
+use interpreter::ast::fold::{self, Folder};
+use interpreter::ast::visit::{self, Visitor};
+use interpreter::ast::Program;
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+use interpreter::token::token_type::TokenType;
+
+fn parse(source: &str) -> Program {
+    let (program, errors) = Parser::new(Lexer::from_string(source.into())).parse_program();
+    assert!(errors.is_empty(), "{errors:?}");
+    program
+}
+
+#[derive(Default)]
+struct Names {
+    idents: Vec<String>,
+    blocks: usize,
+}
+
+impl Visitor for Names {
+    fn visit_ident(&mut self, ident: &Ident) {
+        self.idents.push(ident.get_symbol().to_string());
+        visit::walk_ident(self, ident);
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        self.blocks += 1;
+        visit::walk_block(self, block);
+    }
+}
+
+#[test]
+fn variables_and_block_expressions_reach_their_hooks() {
+    let program = parse("let a = 1; { let b = a; { b } }; if a { a } else { c }");
+    let mut names = Names::default();
+    names.visit_program(&program);
+    assert_eq!(names.idents, ["a", "b", "a", "b", "a", "a", "c"]);
+    // the two block expressions, and the blocks of the if
+    assert_eq!(names.blocks, 4);
+}
+
+struct Rename;
+
+impl Folder for Rename {
+    fn fold_ident(&mut self, ident: Ident) -> Ident {
+        let mut token = ident.token().clone();
+        if ident.get_symbol().as_str() == "a" {
+            token.r#type = TokenType::Ident("z".into());
+        }
+        fold::fold_ident(self, Ident::new(token))
+    }
+}
+
+#[test]
+fn folding_an_ident_renames_the_variables_too() {
+    let program = Rename.fold_program(parse("let a = 1; { a + 1 }"));
+    assert_eq!(program.to_string(), "let z = 1;\n{\n    z + 1\n};\n");
+}
+
+#[test]
+fn the_default_walk_reaches_every_kind_of_node() {
+    let program = parse(
+        "fn f(p, q = a) { p } struct S { x } let s = S { x: b };
+        for i in [c, {\"k\": d}] { while e { break } }
+        match (g, h.x) { (1, y) if y => j, _ => -k[l] }
+        let t = (m && n, o || r); u.x = v += w",
+    );
+    let mut names = Names::default();
+    names.visit_program(&program);
+    // the names being declared and the field names are idents as well
+    let expected = [
+        "f", "p", "q", "a", "p", "S", "x", "s", "S", "x", "b", "i", "c", "d", "e", "g", "h", "x",
+        "y", "y", "j", "k", "l", "t", "m", "n", "o", "r", "u", "x", "v", "w",
+    ];
+    assert_eq!(names.idents, expected);
+}